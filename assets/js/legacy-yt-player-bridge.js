@@ -2,6 +2,7 @@
  * Injects into the stock HTML5 player settings (gear):
  * — Hides native Quality + Speed rows; adds our Speed (playbackRate) then Quality + Codec
  * — Default stream URL has no quality= (matches server /direct_url?video_id=…)
+ * — Subtitles: <track> (WebVTT) from /get_captions.php
  *
  * Mini player button: disabled (see commented block at bottom).
  */
@@ -39,7 +40,9 @@
     quality: "auto",
     codec: "",
     speed: "1",
+    subs: "",
   };
+  var captionTracks = null;
 
   function getPlayerRoot() {
     return document.getElementById("movie_player");
@@ -70,18 +73,87 @@
     if (quality && quality !== "auto") {
       q += "&quality=" + encodeURIComponent(quality);
     }
-    if (codec === "mpeg4") {
-      q += "&codec=mpeg4";
+    if (codec === "mpeg4" || codec === "webm") {
+      q += "&codec=" + codec;
     }
     return base.replace(/\/$/, "") + "/direct_url?" + q;
   }
 
-  function singleFmtMap(url) {
-    return (
-      "url=" +
-      encodeURIComponent(url) +
-      "&itag=18&type=video%2Fmp4&sig=legacy1"
-    );
+  function singleFmtMap(url, codec) {
+    /* WebM (VP8/Vorbis) must be advertised as itag 43 so old Firefox/Opera pick it */
+    var fmt =
+      codec === "webm"
+        ? "&itag=43&type=" + encodeURIComponent('video/webm; codecs="vp8.0, vorbis"')
+        : "&itag=18&type=video%2Fmp4";
+    return "url=" + encodeURIComponent(url) + fmt + "&sig=legacy1";
+  }
+
+  function captionsBase() {
+    var base = window.__YT_LEGACY_BASE__ || "";
+    var vid = window.__YT_LEGACY_VIDEO_ID__ || "";
+    return base.replace(/\/$/, "") + "/get_captions.php?video_id=" + encodeURIComponent(vid);
+  }
+
+  /** state.subs = "lang|kind" or "" (off) */
+  function applySubtitles() {
+    waitForVideo(function (video) {
+      var old = video.getElementsByTagName("track");
+      for (var i = old.length - 1; i >= 0; i--) {
+        if (old[i].getAttribute("data-yt-legacy")) video.removeChild(old[i]);
+      }
+      if (!state.subs) return;
+      var parts = state.subs.split("|");
+      var track = document.createElement("track");
+      track.setAttribute("data-yt-legacy", "subs");
+      track.kind = "subtitles";
+      track.srclang = parts[0];
+      track.label = parts[0];
+      track.src =
+        captionsBase() +
+        "&lang=" + encodeURIComponent(parts[0]) +
+        "&kind=" + encodeURIComponent(parts[1] || "standard") +
+        "&format=vtt";
+      track["default"] = true;
+      video.appendChild(track);
+      try {
+        if (video.textTracks && video.textTracks.length) {
+          video.textTracks[video.textTracks.length - 1].mode = "showing";
+        }
+      } catch (e) {}
+    });
+  }
+
+  function fillSubtitleSelect(select) {
+    if (!select) return;
+    function fill() {
+      while (select.options.length > 1) select.remove(1);
+      for (var i = 0; i < captionTracks.length; i++) {
+        var t = captionTracks[i];
+        var opt = document.createElement("option");
+        opt.value = t.language_code + "|" + t.kind;
+        opt.text = (t.name || t.language_code) + (t.kind === "asr" ? " (auto)" : "");
+        select.appendChild(opt);
+      }
+      select.value = state.subs;
+    }
+    if (captionTracks) {
+      fill();
+      return;
+    }
+    if (!window.XMLHttpRequest || !window.JSON) return;
+    var xhr = new XMLHttpRequest();
+    xhr.onreadystatechange = function () {
+      if (xhr.readyState !== 4) return;
+      captionTracks = [];
+      if (xhr.status === 200) {
+        try {
+          captionTracks = JSON.parse(xhr.responseText).tracks || [];
+        } catch (e) {}
+      }
+      fill();
+    };
+    xhr.open("GET", captionsBase(), true);
+    xhr.send(null);
   }
 
   function applyPlaybackRate() {
@@ -118,7 +190,7 @@
     var url = directUrl(base, vid, state.quality, state.codec);
     var cfg = JSON.parse(JSON.stringify(tpl));
     cfg.args = cfg.args || {};
-    cfg.args.url_encoded_fmt_stream_map = singleFmtMap(url);
+    cfg.args.url_encoded_fmt_stream_map = singleFmtMap(url, state.codec);
     cfg.args.adaptive_fmts = "";
     cfg.args.dash = "0";
     delete cfg.args.dashmpd;
@@ -136,6 +208,7 @@
     /* Restore playback state after rebuild */
     waitForVideo(function () {
       applyPlaybackRate();
+      applySubtitles();
       syncInjectedSelects();
       
       /* Restore playback position and state */
@@ -163,6 +236,8 @@
     if (s) s.value = state.speed;
     if (q) q.value = state.quality;
     if (c) c.value = state.codec || "";
+    var t = root.querySelector('select[data-yt-legacy="subs"]');
+    if (t) t.value = state.subs;
   }
 
   function rowMenuTitleText(row) {
@@ -289,6 +364,13 @@
       '<select data-yt-legacy="codec" class="yt-legacy-stream-select" title="Codec">' +
       '<option value="">Standard</option>' +
       '<option value="mpeg4">MPEG4</option>' +
+      '<option value="webm">WebM (VP8)</option>' +
+      "</select></div></div>" +
+      '<div class="ytp-menu-row ytp-legacy-stream-row" role="menuitem">' +
+      '<div class="ytp-menu-cell ytp-menu-title">Subtitles</div>' +
+      '<div class="ytp-menu-cell ytp-legacy-stream-cell">' +
+      '<select data-yt-legacy="subs" class="yt-legacy-stream-select" title="Subtitles">' +
+      '<option value="">Off</option>' +
       "</select></div></div>"
     );
   }
@@ -318,6 +400,14 @@
         rebuildPlayer();
       });
     }
+    var t = container.querySelector('select[data-yt-legacy="subs"]');
+    if (t) {
+      fillSubtitleSelect(t);
+      legacyAddListener(t, "change", function () {
+        state.subs = t.value || "";
+        applySubtitles();
+      });
+    }
   }
 
   function injectStreamSettings() {
@@ -429,6 +519,19 @@
     };
   }
 
+  /* Chapter / description timestamp links: <a onclick="return window.__YT_LEGACY_SEEK__(83)"> */
+  window.__YT_LEGACY_SEEK__ = function (seconds) {
+    waitForVideo(function (v) {
+      try {
+        v.currentTime = seconds;
+        if (v.paused) v.play();
+      } catch (e) {}
+    });
+    var root = getPlayerRoot();
+    if (root && root.scrollIntoView) root.scrollIntoView();
+    return false;
+  };
+
   function boot() {
     waitForVideo(function () {
       applyPlaybackRate();
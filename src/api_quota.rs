@@ -0,0 +1,293 @@
+//! Per-key YouTube Data API v3 quota accounting. Every call is charged its
+//! documented unit cost against the key's daily budget; keys answering with
+//! `quotaExceeded` / `keyInvalid` are quarantined until the next quota reset
+//! (midnight Pacific time), when all counters start from zero again.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+#[derive(Debug, Default, Clone)]
+struct KeyUsage {
+    /// Pacific date the counters belong to.
+    day: Option<NaiveDate>,
+    units: u64,
+    calls: u64,
+    errors: u64,
+    quarantined_until: Option<i64>,
+    quarantine_reason: Option<String>,
+    last_error: Option<String>,
+}
+
+static USAGE: Mutex<Option<HashMap<String, KeyUsage>>> = Mutex::new(None);
+/// Keys switched off from the admin dashboard since startup.
+static DISABLED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+/// Keys switched on from the admin dashboard since startup; they are used even
+/// if the config loaded at startup listed them as disabled (or not at all).
+static ENABLED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyStatus {
+    /// Masked key (first 3 and last 2 characters).
+    pub key: String,
+    /// `active` or `disabled` in config.yml.
+    pub configured: String,
+    pub units_used: u64,
+    pub daily_quota: u64,
+    pub calls: u64,
+    pub errors: u64,
+    pub quarantined: bool,
+    /// Unix time the quarantine ends (next Pacific midnight).
+    pub quarantined_until: Option<i64>,
+    pub quarantine_reason: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiQuotaStatus {
+    /// Current quota day (Pacific time), YYYY-MM-DD.
+    pub quota_day: String,
+    /// Unix time of the next quota reset.
+    pub resets_at: i64,
+    pub keys: Vec<ApiKeyStatus>,
+}
+
+fn nth_sunday(year: i32, month: u32, n: i64) -> NaiveDate {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_default();
+    let to_sunday = (7 - first.weekday().num_days_from_sunday() as i64) % 7;
+    first + Duration::days(to_sunday + 7 * (n - 1))
+}
+
+/// UTC offset of America/Los_Angeles in hours: PDT from the second Sunday of
+/// March 02:00 PST to the first Sunday of November 02:00 PDT, PST otherwise.
+fn pacific_offset_hours(utc: NaiveDateTime) -> i64 {
+    let year = utc.year();
+    let dst_start = nth_sunday(year, 3, 2).and_hms_opt(10, 0, 0).unwrap_or_default();
+    let dst_end = nth_sunday(year, 11, 1).and_hms_opt(9, 0, 0).unwrap_or_default();
+    if utc >= dst_start && utc < dst_end {
+        -7
+    } else {
+        -8
+    }
+}
+
+fn quota_day_at(now: DateTime<Utc>) -> NaiveDate {
+    let utc = now.naive_utc();
+    (utc + Duration::hours(pacific_offset_hours(utc))).date()
+}
+
+fn quota_day() -> NaiveDate {
+    quota_day_at(Utc::now())
+}
+
+/// Unix time of the next midnight in Pacific time.
+pub fn next_reset() -> i64 {
+    let now = Utc::now();
+    let local_midnight = (quota_day_at(now) + Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default();
+    // Полночь никогда не попадает на переход летнего времени (02:00), так что
+    // смещение, посчитанное по приблизительному UTC, верное
+    let offset = pacific_offset_hours(local_midnight + Duration::hours(8));
+    (local_midnight - Duration::hours(offset)).and_utc().timestamp()
+}
+
+/// Documented unit cost of a Data API v3 request: 100 for search.list, 50 for
+/// writes (insert/update/delete, videos.rate), 1 for every other read.
+pub fn unit_cost(method: &str, url: &str) -> u64 {
+    let path = url
+        .split("/youtube/v3/")
+        .nth(1)
+        .unwrap_or("")
+        .split('?')
+        .next()
+        .unwrap_or("")
+        .trim_end_matches('/');
+    match (method.to_ascii_uppercase().as_str(), path) {
+        (_, "search") => 100,
+        (_, "videos/rate") => 50,
+        ("GET", _) => 1,
+        _ => 50,
+    }
+}
+
+/// `error.errors[].reason` / `error.details[].reason` of a Data API error body.
+pub fn error_reason(body: &Value) -> Option<String> {
+    let error = body.get("error")?;
+    ["errors", "details"]
+        .iter()
+        .filter_map(|field| error.get(*field).and_then(|v| v.as_array()))
+        .flatten()
+        .find_map(|e| e.get("reason").and_then(|r| r.as_str()))
+        .map(|r| r.to_string())
+        .or_else(|| error.get("status").and_then(|s| s.as_str()).map(|s| s.to_string()))
+}
+
+fn is_quarantine_reason(reason: &str) -> bool {
+    matches!(
+        reason,
+        "quotaExceeded" | "dailyLimitExceeded" | "keyInvalid" | "keyExpired" | "API_KEY_INVALID" | "API_KEY_EXPIRED"
+    )
+}
+
+/// Entry for `key`, with counters reset if the quota day has changed.
+fn entry<'a>(map: &'a mut HashMap<String, KeyUsage>, key: &str, today: NaiveDate) -> &'a mut KeyUsage {
+    let usage = map.entry(key.to_string()).or_default();
+    if usage.day != Some(today) {
+        *usage = KeyUsage {
+            day: Some(today),
+            last_error: usage.last_error.take(),
+            ..KeyUsage::default()
+        };
+    }
+    usage
+}
+
+/// Charges a request to `key`.
+pub fn record_call(key: &str, method: &str, url: &str) {
+    let key = key.trim();
+    if key.is_empty() {
+        return;
+    }
+    let mut guard = USAGE.lock().unwrap();
+    let usage = entry(guard.get_or_insert_with(HashMap::new), key, quota_day());
+    usage.calls += 1;
+    usage.units += unit_cost(method, url);
+}
+
+/// Records an error answer; quota and invalid-key errors quarantine the key
+/// until the next Pacific midnight.
+pub fn record_error(key: &str, reason: &str) {
+    let key = key.trim();
+    if key.is_empty() {
+        return;
+    }
+    let mut guard = USAGE.lock().unwrap();
+    let usage = entry(guard.get_or_insert_with(HashMap::new), key, quota_day());
+    usage.errors += 1;
+    usage.last_error = Some(reason.to_string());
+    if is_quarantine_reason(reason) && usage.quarantine_reason.is_none() {
+        let until = next_reset();
+        usage.quarantined_until = Some(until);
+        usage.quarantine_reason = Some(reason.to_string());
+        crate::log::info!(
+            "Data API key {} quarantined until {} ({})",
+            mask_key(key),
+            until,
+            reason
+        );
+    }
+}
+
+/// Lifts a quarantine early, e.g. after `/admin/check_failed_api_keys` found the key working.
+pub fn release(key: &str) {
+    let mut guard = USAGE.lock().unwrap();
+    if let Some(usage) = guard.as_mut().and_then(|m| m.get_mut(key.trim())) {
+        usage.quarantined_until = None;
+        usage.quarantine_reason = None;
+    }
+}
+
+/// Switches a key off (or back on) for this process without waiting for a restart.
+pub fn set_disabled(key: &str, disabled: bool) {
+    let key = key.trim().to_string();
+    let mut disabled_set = DISABLED.lock().unwrap();
+    let mut enabled_set = ENABLED.lock().unwrap();
+    let disabled_set = disabled_set.get_or_insert_with(HashSet::new);
+    let enabled_set = enabled_set.get_or_insert_with(HashSet::new);
+    if disabled {
+        enabled_set.remove(&key);
+        disabled_set.insert(key);
+    } else {
+        disabled_set.remove(&key);
+        enabled_set.insert(key);
+    }
+}
+
+/// Keys enabled from the dashboard since startup, in no particular order.
+pub fn runtime_enabled() -> Vec<String> {
+    let mut keys: Vec<String> = ENABLED.lock().unwrap().iter().flatten().cloned().collect();
+    keys.sort();
+    keys
+}
+
+/// Not disabled at runtime, not quarantined and below the daily budget.
+pub fn is_available(key: &str, cost: u64, config: &Config) -> bool {
+    if DISABLED.lock().unwrap().as_ref().is_some_and(|set| set.contains(key.trim())) {
+        return false;
+    }
+    let guard = USAGE.lock().unwrap();
+    let usage = match guard.as_ref().and_then(|m| m.get(key.trim())) {
+        Some(u) => u,
+        None => return true,
+    };
+    if usage.day != Some(quota_day()) {
+        return true;
+    }
+    let quarantined = usage
+        .quarantined_until
+        .map(|until| until > Utc::now().timestamp())
+        .unwrap_or(false);
+    !quarantined && usage.units + cost <= config.api.keys.daily_quota
+}
+
+pub fn mask_key(key: &str) -> String {
+    let trimmed = key.trim();
+    if trimmed.len() <= 6 {
+        return "***".to_string();
+    }
+    let (start, end) = trimmed.split_at(3);
+    let suffix = &end[end.len().saturating_sub(2)..];
+    format!("{}***{}", start, suffix)
+}
+
+/// Today's counters of one key; `configured` is `active` or `disabled`.
+pub fn key_status(key: &str, configured: &str, config: &Config) -> ApiKeyStatus {
+    let today = quota_day();
+    let now = Utc::now().timestamp();
+    let usage = USAGE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|m| m.get(key.trim()))
+        .filter(|u| u.day == Some(today))
+        .cloned()
+        .unwrap_or_default();
+    let quarantined_until = usage.quarantined_until.filter(|until| *until > now);
+    ApiKeyStatus {
+        key: mask_key(key),
+        configured: configured.to_string(),
+        units_used: usage.units,
+        daily_quota: config.api.keys.daily_quota,
+        calls: usage.calls,
+        errors: usage.errors,
+        quarantined: quarantined_until.is_some(),
+        quarantined_until,
+        quarantine_reason: quarantined_until.and(usage.quarantine_reason),
+        last_error: usage.last_error,
+    }
+}
+
+pub fn status(config: &Config) -> ApiQuotaStatus {
+    let today = quota_day();
+    let keys = config
+        .api
+        .keys
+        .active
+        .iter()
+        .map(|k| (k, "active"))
+        .chain(config.api.keys.disabled.iter().map(|k| (k, "disabled")))
+        .filter(|(k, _)| !k.trim().is_empty())
+        .map(|(key, configured)| key_status(key, configured, config))
+        .collect();
+    ApiQuotaStatus {
+        quota_day: today.format("%Y-%m-%d").to_string(),
+        resets_at: next_reset(),
+        keys,
+    }
+}
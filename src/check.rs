@@ -46,6 +46,7 @@ video:
     - "1440"
     - "2160"
   default_count: 50
+  channel_max_pages: 50
 
 proxy:
   thumbnails:
@@ -58,6 +59,9 @@ cache:
   temp_folder_max_size_mb: 5120
   cleanup_threshold_mb: 100
 
+device_profiles:
+  auto_detect: false
+
 instances:
   - "https://yt.legacyprojects.ru"
   - "https://yt.modyleprojects.ru"
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use utoipa::ToSchema;
@@ -12,6 +12,26 @@ pub struct ServerConfig {
     pub main_url: String,
     #[serde(rename = "secret_key")]
     pub secretkey: String,
+    #[serde(default)]
+    pub admin: AdminConfig,
+}
+
+/// Credentials for the `/admin` scope: `Authorization: Bearer <token>` (or
+/// `X-Admin-Token`) and/or HTTP Basic. With neither set, admin endpoints answer 403.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema, Default)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+impl AdminConfig {
+    pub fn is_configured(&self) -> bool {
+        !self.token.is_empty() || (!self.username.is_empty() && !self.password.is_empty())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
@@ -20,6 +40,10 @@ pub struct ApiKeysConfig {
     pub active: Vec<String>,
     #[serde(default)]
     pub disabled: Vec<String>,
+    /// Data API units a key may spend per day (Google's default project quota
+    /// is 10000); a key over budget is skipped until the Pacific-midnight reset.
+    #[serde(default = "default_daily_quota")]
+    pub daily_quota: u64,
 }
 
 impl Default for ApiKeysConfig {
@@ -27,10 +51,15 @@ impl Default for ApiKeysConfig {
         Self {
             active: Vec::new(),
             disabled: Vec::new(),
+            daily_quota: default_daily_quota(),
         }
     }
 }
 
+fn default_daily_quota() -> u64 {
+    10000
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct InnertubeClientConfig {
     #[serde(default = "default_client_name")]
@@ -51,6 +80,14 @@ pub struct InnertubeClientConfig {
     #[serde(default = "default_os_version")]
     #[serde(rename = "os_version")]
     pub os_version: String,
+    /// User-Agent for this client; falls back to `innertube.user_agent`.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// `EMBED` for the embedded web player.
+    #[serde(default)]
+    pub client_screen: Option<String>,
+    #[serde(default)]
+    pub android_sdk_version: Option<u32>,
 }
 
 fn default_client_name() -> String {
@@ -81,23 +118,101 @@ impl Default for InnertubeClientConfig {
             device_model: default_device_model(),
             os_name: default_os_name(),
             os_version: default_os_version(),
+            user_agent: None,
+            client_screen: None,
+            android_sdk_version: None,
         }
     }
 }
 
 impl InnertubeClientConfig {
     pub fn to_player_context_value(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut client = serde_json::json!({
             "clientName": self.client_name,
             "clientVersion": self.client_version,
-            "deviceMake": self.device_make,
-            "deviceModel": self.device_model,
-            "osName": self.os_name,
-            "osVersion": self.os_version,
-        })
+        });
+        for (key, value) in [
+            ("deviceMake", &self.device_make),
+            ("deviceModel", &self.device_model),
+            ("osName", &self.os_name),
+            ("osVersion", &self.os_version),
+        ] {
+            if !value.is_empty() {
+                client[key] = serde_json::Value::String(value.clone());
+            }
+        }
+        if let Some(screen) = &self.client_screen {
+            client["clientScreen"] = serde_json::Value::String(screen.clone());
+        }
+        if let Some(sdk) = self.android_sdk_version {
+            client["androidSdkVersion"] = serde_json::json!(sdk);
+        }
+        client
+    }
+
+    fn preset(
+        client_name: &str,
+        client_version: &str,
+        device: (&str, &str, &str, &str),
+        user_agent: &str,
+    ) -> Self {
+        let (device_make, device_model, os_name, os_version) = device;
+        Self {
+            client_name: client_name.to_string(),
+            client_version: client_version.to_string(),
+            device_make: device_make.to_string(),
+            device_model: device_model.to_string(),
+            os_name: os_name.to_string(),
+            os_version: os_version.to_string(),
+            user_agent: Some(user_agent.to_string()),
+            client_screen: None,
+            android_sdk_version: None,
+        }
     }
 }
 
+/// Player clients tried after the primary one when YouTube refuses a video
+/// (LOGIN_REQUIRED, UNPLAYABLE or no streamingData).
+fn default_fallback_clients() -> Vec<InnertubeClientConfig> {
+    let mut android_vr = InnertubeClientConfig::preset(
+        "ANDROID_VR",
+        "1.62.27",
+        ("Oculus", "Quest 3", "Android", "12L"),
+        "com.google.android.apps.youtube.vr.oculus/1.62.27 (Linux; U; Android 12L; eureka-user Build/SQ3A.220605.009.A1) gzip",
+    );
+    android_vr.android_sdk_version = Some(32);
+    let mut embedded = InnertubeClientConfig::preset(
+        "WEB_EMBEDDED_PLAYER",
+        "1.20250219.01.00",
+        ("", "", "Windows", "10.0"),
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    );
+    embedded.client_screen = Some("EMBED".to_string());
+    vec![
+        android_vr,
+        InnertubeClientConfig::preset(
+            "TVHTML5",
+            "7.20250209.19.00",
+            ("Samsung", "SmartTV", "Tizen", "5.0"),
+            "Mozilla/5.0 (SMART-TV; Linux; Tizen 5.0) AppleWebKit/538.1 (KHTML, like Gecko) Version/5.0 TV Safari/538.1",
+        ),
+        embedded,
+        InnertubeClientConfig::preset(
+            "MWEB",
+            "2.20250219.01.00",
+            ("Apple", "iPhone", "iOS", "18.0"),
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 18_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.0 Mobile/15E148 Safari/604.1",
+        ),
+    ]
+}
+
+fn default_client_demote_after() -> u32 {
+    3
+}
+fn default_client_demote_secs() -> u64 {
+    1800
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct InnertubeConfig {
     #[serde(default)]
@@ -107,6 +222,17 @@ pub struct InnertubeConfig {
     pub user_agent: Option<String>,
     #[serde(default)]
     pub client: Option<InnertubeClientConfig>,
+    /// Player clients tried in order after `client`. Empty: ANDROID_VR, TVHTML5,
+    /// WEB_EMBEDDED_PLAYER, MWEB.
+    #[serde(default)]
+    pub fallback_clients: Vec<InnertubeClientConfig>,
+    /// Consecutive failures (while another client worked) before a client is moved
+    /// to the end of the chain.
+    #[serde(default = "default_client_demote_after")]
+    pub demote_after_failures: u32,
+    /// How long a demoted client stays at the end of the chain.
+    #[serde(default = "default_client_demote_secs")]
+    pub demote_secs: u64,
 }
 
 impl Default for InnertubeConfig {
@@ -115,6 +241,9 @@ impl Default for InnertubeConfig {
             key: None,
             user_agent: None,
             client: None,
+            fallback_clients: Vec::new(),
+            demote_after_failures: default_client_demote_after(),
+            demote_secs: default_client_demote_secs(),
         }
     }
 }
@@ -153,6 +282,10 @@ pub struct ApiConfig {
     pub innertube: InnertubeConfig,
     #[serde(default)]
     pub oauth: OAuthConfig,
+    /// Serve top videos, categories and playlists from innertube first and use
+    /// the Data API only as a fallback (the default is the other way round).
+    #[serde(default)]
+    pub prefer_innertube: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
@@ -167,6 +300,9 @@ pub struct VideoConfig {
     pub available_qualities: Vec<String>,
     #[serde(default = "default_count")]
     pub default_count: u32,
+    /// Сколько страниц загрузок канала (~30 видео каждая) собирать за один запрос; 0 — без ограничения.
+    #[serde(default = "default_channel_max_pages")]
+    pub channel_max_pages: u32,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
@@ -217,6 +353,24 @@ pub struct CacheConfig {
 
     #[serde(default)]
     pub temp_dir: Option<String>,
+
+    /// Image cache (thumbnails, channel icons) kept in memory, in MB.
+    #[serde(default = "image_memory_max_mb")]
+    pub image_memory_max_mb: u32,
+
+    /// Image cache on disk, in MB. 0 disables the disk tier.
+    #[serde(default = "image_disk_max_mb")]
+    pub image_disk_max_mb: u32,
+
+    /// Directory of the disk tier; defaults to `image_cache` inside `temp_dir`.
+    #[serde(default)]
+    pub image_dir: Option<String>,
+
+    #[serde(default = "thumbnail_ttl_secs")]
+    pub thumbnail_ttl_secs: u64,
+
+    #[serde(default = "channel_icon_ttl_secs")]
+    pub channel_icon_ttl_secs: u64,
 }
 
 impl Default for CacheConfig {
@@ -225,6 +379,188 @@ impl Default for CacheConfig {
             temp_folder_max_size_mb: temp_folder_max_size_mb(),
             cleanup_threshold_mb: cleanup_threshold_mb(),
             temp_dir: None,
+            image_memory_max_mb: image_memory_max_mb(),
+            image_disk_max_mb: image_disk_max_mb(),
+            image_dir: None,
+            thumbnail_ttl_secs: thumbnail_ttl_secs(),
+            channel_icon_ttl_secs: channel_icon_ttl_secs(),
+        }
+    }
+}
+
+/// Encoding preset for a class of legacy devices, used by `/direct_url?profile=`.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct DeviceProfile {
+    /// Container of the output: mp4, 3gp, flv, webm or ogg.
+    pub container: String,
+    /// `h264` serves YouTube's own H.264 stream; anything else is a `/direct_url` codec
+    /// (mpeg4, h263, flv, flv_h264, webm, theora).
+    pub codec: String,
+    pub max_height: u32,
+    /// Video bitrate in kbps.
+    #[serde(default)]
+    pub video_bitrate: Option<u32>,
+    /// Audio bitrate in kbps.
+    #[serde(default)]
+    pub audio_bitrate: Option<u32>,
+    #[serde(default)]
+    pub audio_channels: Option<u32>,
+    #[serde(default)]
+    pub audio_sample_rate: Option<u32>,
+    /// Case-insensitive User-Agent substrings for automatic selection.
+    #[serde(default)]
+    pub user_agents: Vec<String>,
+}
+
+impl DeviceProfile {
+    fn new(container: &str, codec: &str, max_height: u32, user_agents: &[&str]) -> Self {
+        Self {
+            container: container.to_string(),
+            codec: codec.to_string(),
+            max_height,
+            video_bitrate: None,
+            audio_bitrate: None,
+            audio_channels: None,
+            audio_sample_rate: None,
+            user_agents: user_agents.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Codec for `/direct_url?codec=`, or `None` when the native H.264 stream is used.
+    pub fn conversion_codec(&self) -> Option<&str> {
+        if self.codec.eq_ignore_ascii_case("h264") {
+            None
+        } else {
+            Some(self.codec.as_str())
+        }
+    }
+
+    fn matches_user_agent(&self, user_agent: &str) -> bool {
+        let ua = user_agent.to_lowercase();
+        self.user_agents
+            .iter()
+            .map(|p| p.trim().to_lowercase())
+            .any(|p| !p.is_empty() && ua.contains(&p))
+    }
+}
+
+fn default_device_profiles() -> BTreeMap<String, DeviceProfile> {
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        "nokia-s60".to_string(),
+        DeviceProfile {
+            video_bitrate: Some(256),
+            audio_bitrate: Some(12),
+            ..DeviceProfile::new("3gp", "h263", 144, &["SymbianOS", "Series60", "S60;"])
+        },
+    );
+    profiles.insert(
+        "iphone-3g".to_string(),
+        DeviceProfile::new("mp4", "h264", 360, &["iPhone OS 3_", "iPhone OS 4_"]),
+    );
+    profiles.insert(
+        "wii".to_string(),
+        DeviceProfile {
+            video_bitrate: Some(400),
+            audio_bitrate: Some(96),
+            ..DeviceProfile::new("flv", "flv", 240, &["Nintendo Wii"])
+        },
+    );
+    profiles.insert(
+        "psp".to_string(),
+        DeviceProfile {
+            video_bitrate: Some(384),
+            audio_bitrate: Some(128),
+            audio_channels: Some(2),
+            audio_sample_rate: Some(44100),
+            ..DeviceProfile::new("mp4", "mpeg4", 272, &["PlayStation Portable", "(PSP"])
+        },
+    );
+    profiles.insert(
+        "win-xp-ie6".to_string(),
+        DeviceProfile::new("flv", "flv_h264", 360, &["MSIE 6.0"]),
+    );
+    profiles
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct DeviceProfilesConfig {
+    /// Pick a profile by User-Agent when the request has no profile/codec/quality.
+    #[serde(default)]
+    pub auto_detect: bool,
+    #[serde(default = "default_device_profiles")]
+    pub profiles: BTreeMap<String, DeviceProfile>,
+}
+
+impl Default for DeviceProfilesConfig {
+    fn default() -> Self {
+        Self {
+            auto_detect: false,
+            profiles: default_device_profiles(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct VisitorDataConfig {
+    /// Send `X-Goog-Visitor-Id` / `context.client.visitorData` with innertube calls.
+    #[serde(default = "default_visitor_enabled")]
+    pub enabled: bool,
+    /// Visitor IDs kept in rotation for anonymous requests.
+    #[serde(default = "default_visitor_pool_size")]
+    pub pool_size: usize,
+    /// Visitor IDs are replaced after this many seconds.
+    #[serde(default = "default_visitor_max_age_secs")]
+    pub max_age_secs: u64,
+    /// Give each logged-in client session (`session_id` cookie) its own
+    /// visitor ID, so logged-out feeds stay consistent for that client.
+    #[serde(default)]
+    pub pin_sessions: bool,
+}
+
+fn default_visitor_enabled() -> bool {
+    true
+}
+fn default_visitor_pool_size() -> usize {
+    4
+}
+fn default_visitor_max_age_secs() -> u64 {
+    21600
+}
+
+impl Default for VisitorDataConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_visitor_enabled(),
+            pool_size: default_visitor_pool_size(),
+            max_age_secs: default_visitor_max_age_secs(),
+            pin_sessions: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct LocaleConfig {
+    /// Default interface language sent to YouTube (`hl`), e.g. "en", "ru", "pt-BR".
+    #[serde(default = "default_locale_hl")]
+    pub hl: String,
+    /// Default content region (`gl`), e.g. "US", "RU".
+    #[serde(default = "default_locale_gl")]
+    pub gl: String,
+}
+
+fn default_locale_hl() -> String {
+    "en".to_string()
+}
+fn default_locale_gl() -> String {
+    "US".to_string()
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            hl: default_locale_hl(),
+            gl: default_locale_gl(),
         }
     }
 }
@@ -245,6 +581,12 @@ pub struct Config {
     pub instants: Vec<InstantInstance>,
     #[serde(default)]
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub device_profiles: DeviceProfilesConfig,
+    #[serde(default)]
+    pub visitor_data: VisitorDataConfig,
+    #[serde(default)]
+    pub locale: LocaleConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, ToSchema, Default)]
@@ -281,6 +623,10 @@ fn default_count() -> u32 {
     50
 }
 
+fn default_channel_max_pages() -> u32 {
+    50
+}
+
 fn temp_folder_max_size_mb() -> u32 {
     5120
 }
@@ -289,6 +635,22 @@ fn cleanup_threshold_mb() -> u32 {
     100
 }
 
+fn image_memory_max_mb() -> u32 {
+    64
+}
+
+fn image_disk_max_mb() -> u32 {
+    512
+}
+
+fn thumbnail_ttl_secs() -> u64 {
+    86400
+}
+
+fn channel_icon_ttl_secs() -> u64 {
+    604800
+}
+
 fn normalize_url(input: &str) -> String {
     input.trim().trim_end_matches('/').to_lowercase()
 }
@@ -363,9 +725,20 @@ impl Config {
             })
     }
 
-    pub fn get_api_key_rotated(&self) -> &str {
-        let bad: HashSet<&str> = self.api.keys.disabled.iter().map(|s| s.as_str()).collect();
-        let good_keys: Vec<&str> = self
+    /// A Data API key with at least `cost` units left today (see `api_quota::unit_cost`),
+    /// or an empty string when none has.
+    pub fn get_api_key_rotated(&self, cost: u64) -> String {
+        // Ключи, включённые в /admin после запуска, есть только в api_quota
+        let enabled = crate::api_quota::runtime_enabled();
+        let bad: HashSet<&str> = self
+            .api
+            .keys
+            .disabled
+            .iter()
+            .map(|s| s.as_str())
+            .filter(|k| !enabled.iter().any(|e| e == k.trim()))
+            .collect();
+        let mut good_keys: Vec<&str> = self
             .api
             .keys
             .active
@@ -373,6 +746,11 @@ impl Config {
             .map(|s| s.as_str())
             .filter(|k| !k.is_empty() && !bad.contains(k))
             .collect();
+        for key in &enabled {
+            if !good_keys.iter().any(|k| k.trim() == key) {
+                good_keys.push(key);
+            }
+        }
 
         if good_keys.is_empty() {
             return self
@@ -380,11 +758,20 @@ impl Config {
                 .keys
                 .active
                 .first()
-                .map(|s| s.as_str())
-                .unwrap_or("");
+                .cloned()
+                .unwrap_or_default();
+        }
+        // Ключи в карантине (quotaExceeded / keyInvalid) или исчерпавшие
+        // daily_quota пропускаем до сброса квоты
+        let available: Vec<&str> = good_keys
+            .into_iter()
+            .filter(|k| crate::api_quota::is_available(k, cost, self))
+            .collect();
+        if available.is_empty() {
+            return String::new();
         }
-        let index = API_KEY_COUNTER.fetch_add(1, Ordering::Relaxed) % good_keys.len();
-        good_keys[index]
+        let index = API_KEY_COUNTER.fetch_add(1, Ordering::Relaxed) % available.len();
+        available[index].to_string()
     }
 
     pub fn get_innertube_key(&self) -> Option<&str> {
@@ -407,6 +794,38 @@ impl Config {
             .to_string()
     }
 
+    /// Resolves an explicit `profile` parameter, or the User-Agent when auto-detection
+    /// is enabled. `profile=none` turns auto-detection off for the request.
+    pub fn select_device_profile(
+        &self,
+        requested: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<Option<(String, DeviceProfile)>, String> {
+        let profiles = &self.device_profiles.profiles;
+        match requested.map(|r| r.trim().to_lowercase()) {
+            Some(name) if name == "none" || name.is_empty() => Ok(None),
+            // Имена профилей в config.yml могут быть с заглавными буквами
+            Some(name) => profiles
+                .iter()
+                .find(|(key, _)| key.to_lowercase() == name)
+                .map(|(key, p)| Some((key.clone(), p.clone())))
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown device profile '{}'. Available: {}",
+                        name,
+                        profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                    )
+                }),
+            None if self.device_profiles.auto_detect => Ok(user_agent.and_then(|ua| {
+                profiles
+                    .iter()
+                    .find(|(_, p)| p.matches_user_agent(ua))
+                    .map(|(name, p)| (name.clone(), p.clone()))
+            })),
+            None => Ok(None),
+        }
+    }
+
     pub fn get_innertube_player_client(&self) -> InnertubeClientConfig {
         self.api
             .innertube
@@ -414,4 +833,31 @@ impl Config {
             .clone()
             .unwrap_or_default()
     }
+
+    /// Primary player client followed by the fallback chain, without duplicates.
+    pub fn get_innertube_player_clients(&self) -> Vec<InnertubeClientConfig> {
+        let fallback = if self.api.innertube.fallback_clients.is_empty() {
+            default_fallback_clients()
+        } else {
+            self.api.innertube.fallback_clients.clone()
+        };
+        let mut clients = vec![self.get_innertube_player_client()];
+        for client in fallback {
+            if !clients.iter().any(|c| c.client_name == client.client_name) {
+                clients.push(client);
+            }
+        }
+        clients
+    }
+
+    /// User-Agent for a player client: its own, else the global innertube one.
+    pub fn get_innertube_client_user_agent(&self, client: &InnertubeClientConfig) -> String {
+        client
+            .user_agent
+            .as_deref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| self.get_innertube_user_agent())
+    }
 }
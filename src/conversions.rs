@@ -0,0 +1,144 @@
+//! Registry of ffmpeg conversions waiting for or holding a codec semaphore
+//! permit, so the admin dashboard can show what the server is busy with.
+//! Also locks cache files being produced, so parallel first requests for the
+//! same output run one ffmpeg job and the rest wait for its result.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug, Clone)]
+struct Conversion {
+    label: String,
+    queued_at: u64,
+    started_at: Option<u64>,
+}
+
+static ACTIVE: Mutex<Option<HashMap<u64, Conversion>>> = Mutex::new(None);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static OUTPUTS: Mutex<Option<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+pub struct ConversionStatus {
+    pub label: String,
+    pub queued_at: u64,
+    /// None while still waiting for a permit.
+    pub started_at: Option<u64>,
+}
+
+/// Semaphore permit plus registry entry; dropping it ends the conversion.
+#[derive(Debug)]
+pub struct ConversionPermit {
+    id: u64,
+    _permit: Option<OwnedSemaphorePermit>,
+    _output: Option<OutputLock>,
+}
+
+impl ConversionPermit {
+    /// Keeps the output locked until the conversion ends.
+    pub fn with_output(mut self, lock: OutputLock) -> Self {
+        self._output = Some(lock);
+        self
+    }
+}
+
+impl Drop for ConversionPermit {
+    fn drop(&mut self) {
+        if let Some(active) = ACTIVE.lock().unwrap().as_mut() {
+            active.remove(&self.id);
+        }
+    }
+}
+
+/// Exclusive right to produce one cache file; see `lock_output`.
+#[derive(Debug)]
+pub struct OutputLock {
+    path: PathBuf,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        let mut outputs = OUTPUTS.lock().unwrap();
+        if let Some(map) = outputs.as_mut() {
+            // Остались только карта и этот guard — больше никто не ждёт
+            if map.get(&self.path).is_some_and(|m| Arc::strong_count(m) <= 2) {
+                map.remove(&self.path);
+            }
+        }
+    }
+}
+
+/// Waits until no other request is producing `path`, then locks it. Callers
+/// check the cache again afterwards: the request they waited for may have
+/// written the file already.
+pub async fn lock_output(path: &Path) -> OutputLock {
+    let mutex = OUTPUTS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .entry(path.to_path_buf())
+        .or_default()
+        .clone();
+    OutputLock {
+        path: path.to_path_buf(),
+        _guard: mutex.lock_owned().await,
+    }
+}
+
+/// Whether a conversion is currently producing (or about to produce) `path`.
+pub fn is_output_locked(path: &Path) -> bool {
+    OUTPUTS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|map| map.contains_key(path))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Waits for a codec permit while the conversion shows up as queued.
+/// If the request is dropped while waiting, the entry goes away with it.
+pub async fn acquire(semaphore: &Arc<Semaphore>, label: String) -> Option<ConversionPermit> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    ACTIVE.lock().unwrap().get_or_insert_with(HashMap::new).insert(
+        id,
+        Conversion {
+            label,
+            queued_at: now_secs(),
+            started_at: None,
+        },
+    );
+    let mut guard = ConversionPermit { id, _permit: None, _output: None };
+
+    let permit = semaphore.clone().acquire_owned().await.ok()?;
+    guard._permit = Some(permit);
+    if let Some(entry) = ACTIVE.lock().unwrap().as_mut().and_then(|m| m.get_mut(&id)) {
+        entry.started_at = Some(now_secs());
+    }
+    Some(guard)
+}
+
+/// Running conversions first, then the queue, oldest first within each.
+pub fn snapshot() -> Vec<ConversionStatus> {
+    let guard = ACTIVE.lock().unwrap();
+    let mut list: Vec<ConversionStatus> = guard
+        .iter()
+        .flat_map(|m| m.values())
+        .map(|c| ConversionStatus {
+            label: c.label.clone(),
+            queued_at: c.queued_at,
+            started_at: c.started_at,
+        })
+        .collect();
+    list.sort_by_key(|c| (c.started_at.is_none(), c.started_at.unwrap_or(c.queued_at)));
+    list
+}
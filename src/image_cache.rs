@@ -0,0 +1,486 @@
+//! Two-tier (memory + disk) cache for proxied images: thumbnails and channel icons.
+
+use actix_web::http::header::{self, HttpDate};
+use actix_web::{HttpRequest, HttpResponse};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tokio::task;
+
+use crate::config::CacheConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedImage {
+    #[serde(skip)]
+    pub data: Arc<Vec<u8>>,
+    pub content_type: String,
+    /// Unix time the image was fetched; also served as Last-Modified.
+    pub created: u64,
+    pub etag: String,
+    /// Cache key, stored on disk to detect hash collisions.
+    pub key: String,
+}
+
+impl CachedImage {
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn is_fresh(&self, ttl: u64) -> bool {
+        now_secs().saturating_sub(self.created) < ttl
+    }
+}
+
+/// Sizes of both tiers, for the admin dashboard.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageCacheUsage {
+    pub memory_entries: usize,
+    pub memory_bytes: u64,
+    pub memory_max_bytes: u64,
+    pub disk_dir: Option<String>,
+    pub disk_bytes: u64,
+    pub disk_max_bytes: u64,
+}
+
+/// One cached image without its data.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageCacheEntry {
+    pub key: String,
+    pub content_type: String,
+    pub size: u64,
+    pub created: u64,
+    pub in_memory: bool,
+}
+
+#[derive(Debug)]
+struct MemoryTier {
+    entries: LruCache<String, CachedImage>,
+    bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct ImageCache {
+    memory: Mutex<MemoryTier>,
+    memory_max_bytes: u64,
+    disk_dir: Option<PathBuf>,
+    disk_max_bytes: u64,
+    disk_bytes: Arc<AtomicU64>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Stable across builds and toolchains, so disk entries survive upgrades.
+fn hash_hex(data: &[u8]) -> String {
+    Sha256::digest(data)[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Writes via a `.part` file and a rename, so a crash never leaves a truncated entry.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let part_path = path.with_extension(format!(
+        "{}.part",
+        path.extension().and_then(|e| e.to_str()).unwrap_or("")
+    ));
+    let result = fs::write(&part_path, contents).and_then(|_| fs::rename(&part_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&part_path);
+    }
+    result
+}
+
+impl ImageCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        let disk_dir = if config.image_disk_max_mb == 0 {
+            None
+        } else {
+            let dir = config
+                .image_dir
+                .as_deref()
+                .filter(|s| !s.trim().is_empty())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| {
+                    config
+                        .temp_dir
+                        .as_deref()
+                        .filter(|s| !s.trim().is_empty())
+                        .map(PathBuf::from)
+                        .unwrap_or_else(std::env::temp_dir)
+                        .join("image_cache")
+                });
+            match fs::create_dir_all(&dir) {
+                Ok(()) => {
+                    remove_part_files(&dir);
+                    Some(dir)
+                }
+                Err(e) => {
+                    log::warn!("Image cache: cannot create {}: {}, disk tier disabled", dir.display(), e);
+                    None
+                }
+            }
+        };
+        let disk_bytes = disk_dir.as_deref().map(disk_usage).unwrap_or(0);
+        if let Some(dir) = &disk_dir {
+            log::info!(
+                "Image cache: {} MB on disk in {}",
+                disk_bytes / (1024 * 1024),
+                dir.display()
+            );
+        }
+
+        Self {
+            memory: Mutex::new(MemoryTier {
+                entries: LruCache::unbounded(),
+                bytes: 0,
+            }),
+            memory_max_bytes: config.image_memory_max_mb as u64 * 1024 * 1024,
+            disk_dir,
+            disk_max_bytes: config.image_disk_max_mb as u64 * 1024 * 1024,
+            disk_bytes: Arc::new(AtomicU64::new(disk_bytes)),
+        }
+    }
+
+    fn disk_paths(&self, key: &str) -> Option<(PathBuf, PathBuf)> {
+        let dir = self.disk_dir.as_ref()?;
+        let name = hash_hex(key.as_bytes());
+        Some((dir.join(format!("{}.img", name)), dir.join(format!("{}.json", name))))
+    }
+
+    /// Looks the key up in memory, then on disk (promoting disk hits to memory).
+    /// Entries older than `ttl` seconds count as misses.
+    pub async fn get(&self, key: &str, ttl: u64) -> Option<CachedImage> {
+        {
+            let mut memory = self.memory.lock().await;
+            if let Some(image) = memory.entries.get(key) {
+                if image.is_fresh(ttl) {
+                    return Some(image.clone());
+                }
+                if let Some(stale) = memory.entries.pop(key) {
+                    memory.bytes -= stale.size();
+                }
+            }
+        }
+
+        let (data_path, meta_path) = self.disk_paths(key)?;
+        let key_owned = key.to_string();
+        let image = task::spawn_blocking(move || -> Option<CachedImage> {
+            let meta: CachedImage = serde_json::from_slice(&fs::read(&meta_path).ok()?).ok()?;
+            if meta.key != key_owned {
+                return None;
+            }
+            let data = fs::read(&data_path).ok()?;
+            Some(CachedImage {
+                data: Arc::new(data),
+                ..meta
+            })
+        })
+        .await
+        .ok()
+        .flatten()?;
+
+        if !image.is_fresh(ttl) {
+            return None;
+        }
+        self.put_memory(image.clone()).await;
+        Some(image)
+    }
+
+    /// Stores an image in both tiers and returns the cached entry.
+    pub async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> CachedImage {
+        let image = CachedImage {
+            etag: format!("\"{}-{:x}\"", hash_hex(&data), data.len()),
+            data: Arc::new(data),
+            content_type: content_type.to_string(),
+            created: now_secs(),
+            key: key.to_string(),
+        };
+        self.put_memory(image.clone()).await;
+
+        if let Some((data_path, meta_path)) = self.disk_paths(key) {
+            let entry = image.clone();
+            let dir = self.disk_dir.clone();
+            let disk_bytes = self.disk_bytes.clone();
+            let disk_max = self.disk_max_bytes;
+            task::spawn_blocking(move || {
+                let previous = fs::metadata(&data_path).map(|m| m.len()).unwrap_or(0);
+                let meta = match serde_json::to_vec(&entry) {
+                    Ok(m) => m,
+                    Err(_) => return,
+                };
+                if write_atomic(&data_path, entry.data.as_slice()).is_err()
+                    || write_atomic(&meta_path, &meta).is_err()
+                {
+                    let _ = fs::remove_file(&data_path);
+                    let _ = fs::remove_file(&meta_path);
+                    return;
+                }
+                // Только дельты: параллельные put/remove меняют счётчик одновременно
+                let total = if entry.size() >= previous {
+                    let delta = entry.size() - previous;
+                    disk_bytes.fetch_add(delta, Ordering::SeqCst).saturating_add(delta)
+                } else {
+                    subtract_bytes(&disk_bytes, previous - entry.size())
+                };
+                if total > disk_max {
+                    if let Some(dir) = dir {
+                        subtract_bytes(&disk_bytes, evict_disk(&dir, disk_max));
+                    }
+                }
+            });
+        }
+
+        image
+    }
+
+    async fn put_memory(&self, image: CachedImage) {
+        if image.size() > self.memory_max_bytes / 4 {
+            return;
+        }
+        let mut memory = self.memory.lock().await;
+        let size = image.size();
+        if let Some(old) = memory.entries.put(image.key.clone(), image) {
+            memory.bytes -= old.size();
+        }
+        memory.bytes += size;
+        while memory.bytes > self.memory_max_bytes {
+            match memory.entries.pop_lru() {
+                Some((_, evicted)) => memory.bytes -= evicted.size(),
+                None => break,
+            }
+        }
+    }
+}
+
+impl ImageCache {
+    pub async fn usage(&self) -> ImageCacheUsage {
+        let memory = self.memory.lock().await;
+        ImageCacheUsage {
+            memory_entries: memory.entries.len(),
+            memory_bytes: memory.bytes,
+            memory_max_bytes: self.memory_max_bytes,
+            disk_dir: self.disk_dir.as_ref().map(|d| d.display().to_string()),
+            disk_bytes: self.disk_bytes.load(Ordering::SeqCst),
+            disk_max_bytes: self.disk_max_bytes,
+        }
+    }
+
+    /// Up to `limit` entries, newest first: everything in memory plus disk-only
+    /// entries read from their metadata files.
+    pub async fn entries(&self, limit: usize) -> Vec<ImageCacheEntry> {
+        let mut entries: Vec<ImageCacheEntry> = {
+            let memory = self.memory.lock().await;
+            memory
+                .entries
+                .iter()
+                .map(|(key, image)| ImageCacheEntry {
+                    key: key.clone(),
+                    content_type: image.content_type.clone(),
+                    size: image.size(),
+                    created: image.created,
+                    in_memory: true,
+                })
+                .collect()
+        };
+
+        if let Some(dir) = self.disk_dir.clone() {
+            let disk_entries = task::spawn_blocking(move || read_disk_entries(&dir))
+                .await
+                .unwrap_or_default();
+            for entry in disk_entries {
+                if !entries.iter().any(|e| e.key == entry.key) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        entries.sort_by_key(|e| std::cmp::Reverse(e.created));
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Drops one key from both tiers. Returns whether anything was removed.
+    pub async fn remove(&self, key: &str) -> bool {
+        let mut removed = false;
+        {
+            let mut memory = self.memory.lock().await;
+            if let Some(old) = memory.entries.pop(key) {
+                memory.bytes -= old.size();
+                removed = true;
+            }
+        }
+        if let Some((data_path, meta_path)) = self.disk_paths(key) {
+            let disk_bytes = self.disk_bytes.clone();
+            let removed_from_disk = task::spawn_blocking(move || {
+                let size = fs::metadata(&data_path).map(|m| m.len()).unwrap_or(0);
+                let removed = fs::remove_file(&data_path).is_ok();
+                if removed {
+                    subtract_bytes(&disk_bytes, size);
+                }
+                let _ = fs::remove_file(&meta_path);
+                removed
+            })
+            .await
+            .unwrap_or(false);
+            removed |= removed_from_disk;
+        }
+        removed
+    }
+
+    /// Empties both tiers. Returns the number of bytes freed.
+    pub async fn purge(&self) -> u64 {
+        let memory_bytes = {
+            let mut memory = self.memory.lock().await;
+            let bytes = memory.bytes;
+            memory.entries.clear();
+            memory.bytes = 0;
+            bytes
+        };
+        let disk_bytes = match self.disk_dir.clone() {
+            Some(dir) => {
+                let freed = task::spawn_blocking(move || evict_disk(&dir, 0))
+                    .await
+                    .unwrap_or(0);
+                subtract_bytes(&self.disk_bytes, freed);
+                freed
+            }
+            None => 0,
+        };
+        memory_bytes + disk_bytes
+    }
+}
+
+fn read_disk_entries(dir: &Path) -> Vec<ImageCacheEntry> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().map(|x| x == "json").unwrap_or(false))
+                .filter_map(|e| {
+                    let meta: CachedImage = serde_json::from_slice(&fs::read(e.path()).ok()?).ok()?;
+                    let size = fs::metadata(e.path().with_extension("img")).ok()?.len();
+                    Some(ImageCacheEntry {
+                        key: meta.key,
+                        content_type: meta.content_type,
+                        size,
+                        created: meta.created,
+                        in_memory: false,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Leftovers of writes interrupted by a crash or restart.
+fn remove_part_files(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.path().extension().map(|x| x == "part").unwrap_or(false) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+fn disk_usage(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().map(|x| x == "img").unwrap_or(false))
+                .filter_map(|e| e.metadata().ok())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Lowers the disk tier counter by `bytes` (not below zero); returns the new value.
+fn subtract_bytes(counter: &AtomicU64, bytes: u64) -> u64 {
+    let previous = counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| Some(v.saturating_sub(bytes)))
+        .unwrap_or(0);
+    previous.saturating_sub(bytes)
+}
+
+/// Removes the oldest images until the disk tier is under 90% of `max_bytes`.
+/// Returns the number of bytes freed.
+fn evict_disk(dir: &Path, max_bytes: u64) -> u64 {
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|e| e.path().extension().map(|x| x == "img").unwrap_or(false))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some((e.path(), meta.len(), meta.modified().unwrap_or(UNIX_EPOCH)))
+            })
+            .collect(),
+        Err(_) => return 0,
+    };
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let target = max_bytes / 10 * 9;
+    let mut freed = 0;
+    for (path, size, _) in files {
+        if total <= target {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            let _ = fs::remove_file(path.with_extension("json"));
+            total -= size;
+            freed += size;
+        }
+    }
+    freed
+}
+
+/// 200 with ETag / Last-Modified / Cache-Control, or 304 when the client's
+/// If-None-Match / If-Modified-Since still matches.
+pub fn image_response(req: &HttpRequest, image: &CachedImage, max_age: u64) -> HttpResponse {
+    let last_modified = HttpDate::from(UNIX_EPOCH + Duration::from_secs(image.created));
+    let cache_control = format!("public, max-age={}", max_age);
+
+    let etag_matches = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').any(|t| t.trim() == image.etag || t.trim() == "*"));
+    let not_modified = match etag_matches {
+        Some(matches) => matches,
+        None => req
+            .headers()
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<HttpDate>().ok())
+            .map(|since| SystemTime::from(since) >= SystemTime::from(last_modified))
+            .unwrap_or(false),
+    };
+
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, image.etag.clone()))
+            .insert_header((header::LAST_MODIFIED, last_modified.to_string()))
+            .insert_header((header::CACHE_CONTROL, cache_control))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .content_type(image.content_type.as_str())
+        .insert_header((header::ETAG, image.etag.clone()))
+        .insert_header((header::LAST_MODIFIED, last_modified.to_string()))
+        .insert_header((header::CACHE_CONTROL, cache_control))
+        .body(image.data.as_ref().clone())
+}
@@ -30,6 +30,14 @@ pub fn load_blocked_ips(filepath: &str) {
     *store = Some(blocked);
 }
 
+/// Currently blocked addresses, sorted.
+pub fn blocked_ips() -> Vec<String> {
+    let store = BLOCKED_IPS.lock().unwrap();
+    let mut ips: Vec<String> = store.iter().flatten().cloned().collect();
+    ips.sort();
+    ips
+}
+
 pub fn is_ip_blocked(ip: &str) -> bool {
     let store = BLOCKED_IPS.lock().unwrap();
     if let Some(ref blocked) = *store {
@@ -0,0 +1,435 @@
+//! Interface language (`hl`) and content region (`gl`) for scraping requests, plus
+//! parsers for the localized strings YouTube sends back ("1,2 млн просмотров",
+//! "vor 3 Tagen", "3日前"). Clients get counts as plain numbers and relative
+//! dates in English, whatever language the page was fetched in.
+
+use actix_web::HttpRequest;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::RequestBuilder;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    pub hl: String,
+    pub gl: String,
+}
+
+lazy_static! {
+    static ref HL_RE: Regex = Regex::new(r"^[a-zA-Z]{2,3}([-_][a-zA-Z0-9]{2,4})?$").unwrap();
+    static ref NUMBER_RE: Regex =
+        Regex::new(r"\d(?:[\d.,'\u{a0}\u{202f} ]*\d)?").unwrap();
+}
+
+fn valid_hl(value: &str) -> Option<String> {
+    let value = value.trim();
+    HL_RE.is_match(value).then(|| value.replace('_', "-"))
+}
+
+fn valid_gl(value: &str) -> Option<String> {
+    let value = value.trim();
+    (value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| value.to_ascii_uppercase())
+}
+
+impl Locale {
+    /// Config defaults, falling back to en/US if those are malformed.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            hl: valid_hl(&config.locale.hl).unwrap_or_else(|| "en".to_string()),
+            gl: valid_gl(&config.locale.gl).unwrap_or_else(|| "US".to_string()),
+        }
+    }
+
+    /// `hl` / `gl` from the query string, then the `hl` / `gl` cookies (set by
+    /// the frontend), then config defaults. Malformed values are ignored.
+    pub fn from_request(req: &HttpRequest, config: &Config) -> Self {
+        let query = actix_web::web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .map(|q| q.into_inner())
+            .unwrap_or_default();
+        let pick = |name: &str, check: fn(&str) -> Option<String>| {
+            query
+                .get(name)
+                .and_then(|v| check(v))
+                .or_else(|| req.cookie(name).and_then(|c| check(c.value())))
+        };
+        let defaults = Self::from_config(config);
+        Self {
+            hl: pick("hl", valid_hl).unwrap_or(defaults.hl),
+            gl: pick("gl", valid_gl).unwrap_or(defaults.gl),
+        }
+    }
+
+    /// Language without region: "pt-BR" -> "pt".
+    pub fn language(&self) -> &str {
+        self.hl.split('-').next().unwrap_or(&self.hl)
+    }
+
+    /// `Accept-Language` for HTML scraping, e.g. "ru-RU,ru;q=0.9,en;q=0.8".
+    pub fn accept_language(&self) -> String {
+        let full = if self.hl.contains('-') {
+            self.hl.clone()
+        } else {
+            format!("{}-{}", self.hl, self.gl)
+        };
+        if self.language() == "en" {
+            format!("{},en;q=0.9", full)
+        } else {
+            format!("{},{};q=0.9,en;q=0.8", full, self.language())
+        }
+    }
+
+    /// `hl=..&gl=..` for Data API and internal URLs.
+    pub fn query(&self) -> String {
+        format!(
+            "hl={}&gl={}",
+            urlencoding::encode(&self.hl),
+            urlencoding::encode(&self.gl)
+        )
+    }
+
+    /// Overrides `context.client.hl` / `gl` of an innertube payload.
+    pub fn apply(&self, payload: &mut Value) {
+        if let Some(context) = payload.get_mut("context") {
+            self.apply_context(context);
+        }
+    }
+
+    /// Same for a bare innertube context (`{"client": {...}}`), e.g. INNERTUBE_CONTEXT from ytcfg.
+    pub fn apply_context(&self, context: &mut Value) {
+        if let Some(client) = context.get_mut("client").filter(|c| c.is_object()) {
+            client["hl"] = Value::String(self.hl.clone());
+            client["gl"] = Value::String(self.gl.clone());
+        }
+    }
+
+    pub fn with_header(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder.header("Accept-Language", self.accept_language())
+    }
+}
+
+// Множители сокращений: "1.2K", "1,2 тыс.", "1,5 Mio.", "3,4 mil", "12万", "1.2억".
+// Turkish "B" (bin) is a thousand, not a billion; see parse_count.
+const MULTIPLIERS: &[(&str, f64)] = &[
+    ("млрд", 1e9),
+    ("mlrd", 1e9),
+    ("mrd", 1e9),
+    ("mld", 1e9),
+    ("md", 1e9),
+    ("bn", 1e9),
+    ("tỷ", 1e9),
+    ("mr", 1e9),
+    ("b", 1e9),
+    ("млн", 1e6),
+    ("mio", 1e6),
+    ("mln", 1e6),
+    ("mn", 1e6),
+    ("mi", 1e6),
+    ("tr", 1e6),
+    ("jt", 1e6),
+    ("m", 1e6),
+    ("тыс", 1e3),
+    ("тис", 1e3),
+    ("tys", 1e3),
+    ("tsd", 1e3),
+    ("mil", 1e3),
+    ("rb", 1e3),
+    ("k", 1e3),
+    ("億", 1e8),
+    ("亿", 1e8),
+    ("억", 1e8),
+    ("萬", 1e4),
+    ("万", 1e4),
+    ("만", 1e4),
+    ("千", 1e3),
+    ("천", 1e3),
+];
+
+/// Parses a localized view / like / subscriber count into a plain number.
+/// None when the text contains no digits.
+pub fn parse_count(text: &str) -> Option<u64> {
+    let m = NUMBER_RE.find(text)?;
+    let number = m.as_str();
+    let rest = text[m.end()..].trim_start().to_lowercase();
+    // "mil millones" (es) / "mil milhões" (pt) = billion
+    let multiplier = if rest.starts_with("mil m") {
+        1e9
+    } else {
+        MULTIPLIERS
+            .iter()
+            .find(|(suffix, _)| {
+                rest.starts_with(suffix)
+                    && !rest[suffix.len()..]
+                        .chars()
+                        .next()
+                        .map(|c| c.is_alphabetic() && c.is_ascii())
+                        .unwrap_or(false)
+            })
+            .map(|(_, m)| *m)
+            .unwrap_or(1.0)
+    };
+    let multiplier = if multiplier == 1e9 && rest.starts_with('b') && is_turkish(&rest) {
+        1e3
+    } else {
+        multiplier
+    };
+
+    let compact: String = number
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        .collect();
+    if multiplier == 1.0 {
+        // Полное число: все разделители — групповые
+        return compact.chars().filter(|c| c.is_ascii_digit()).collect::<String>().parse().ok();
+    }
+    // Сокращённое: единственный разделитель — десятичный ("1,2" и "1.2")
+    let value: f64 = compact.replace(',', ".").parse().ok()?;
+    Some((value * multiplier).round() as u64)
+}
+
+fn is_turkish(rest: &str) -> bool {
+    ["görüntüleme", "izlenme", "abone", "beğeni", "yorum"]
+        .iter()
+        .any(|w| rest.contains(w))
+}
+
+/// `parse_count` as the string the API responses carry; `fallback` when no number.
+pub fn count_string(text: &str, fallback: &str) -> String {
+    parse_count(text)
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeUnit {
+    fn english(self) -> &'static str {
+        match self {
+            TimeUnit::Second => "second",
+            TimeUnit::Minute => "minute",
+            TimeUnit::Hour => "hour",
+            TimeUnit::Day => "day",
+            TimeUnit::Week => "week",
+            TimeUnit::Month => "month",
+            TimeUnit::Year => "year",
+        }
+    }
+
+    fn seconds(self) -> i64 {
+        match self {
+            TimeUnit::Second => 1,
+            TimeUnit::Minute => 60,
+            TimeUnit::Hour => 3600,
+            TimeUnit::Day => 86400,
+            TimeUnit::Week => 7 * 86400,
+            TimeUnit::Month => 30 * 86400,
+            TimeUnit::Year => 365 * 86400,
+        }
+    }
+}
+
+// Начала слов-единиц для en, ru, uk, de, fr, es, pt, it, pl, nl, tr, id.
+// First match wins, so a stem must come before any shorter stem it starts
+// with ("minggu" before "min").
+const WORD_UNITS: &[(&str, TimeUnit)] = &[
+    ("sec", TimeUnit::Second),
+    ("sek", TimeUnit::Second),
+    ("seg", TimeUnit::Second),
+    ("saniye", TimeUnit::Second),
+    ("detik", TimeUnit::Second),
+    ("секунд", TimeUnit::Second),
+    ("minggu", TimeUnit::Week),
+    ("min", TimeUnit::Minute),
+    ("dakika", TimeUnit::Minute),
+    ("menit", TimeUnit::Minute),
+    ("минут", TimeUnit::Minute),
+    ("хвилин", TimeUnit::Minute),
+    ("hour", TimeUnit::Hour),
+    ("hora", TimeUnit::Hour),
+    ("heure", TimeUnit::Hour),
+    ("ora", TimeUnit::Hour),
+    ("ore", TimeUnit::Hour),
+    ("stunde", TimeUnit::Hour),
+    ("godzin", TimeUnit::Hour),
+    ("uur", TimeUnit::Hour),
+    ("saat", TimeUnit::Hour),
+    ("jam", TimeUnit::Hour),
+    ("час", TimeUnit::Hour),
+    ("годин", TimeUnit::Hour),
+    ("day", TimeUnit::Day),
+    ("día", TimeUnit::Day),
+    ("dia", TimeUnit::Day),
+    ("jour", TimeUnit::Day),
+    ("giorn", TimeUnit::Day),
+    ("tag", TimeUnit::Day),
+    ("dzień", TimeUnit::Day),
+    ("dni", TimeUnit::Day),
+    ("dag", TimeUnit::Day),
+    ("gün", TimeUnit::Day),
+    ("hari", TimeUnit::Day),
+    ("день", TimeUnit::Day),
+    ("дня", TimeUnit::Day),
+    ("дней", TimeUnit::Day),
+    ("дн", TimeUnit::Day),
+    ("week", TimeUnit::Week),
+    ("weken", TimeUnit::Week),
+    ("semaine", TimeUnit::Week),
+    ("semana", TimeUnit::Week),
+    ("settiman", TimeUnit::Week),
+    ("woche", TimeUnit::Week),
+    ("tydzień", TimeUnit::Week),
+    ("tygod", TimeUnit::Week),
+    ("hafta", TimeUnit::Week),
+    ("недел", TimeUnit::Week),
+    ("тиж", TimeUnit::Week),
+    ("month", TimeUnit::Month),
+    ("mois", TimeUnit::Month),
+    ("mês", TimeUnit::Month),
+    ("meses", TimeUnit::Month),
+    ("mesi", TimeUnit::Month),
+    ("mese", TimeUnit::Month),
+    ("mes", TimeUnit::Month),
+    ("monat", TimeUnit::Month),
+    ("miesiąc", TimeUnit::Month),
+    ("miesięcy", TimeUnit::Month),
+    ("maand", TimeUnit::Month),
+    ("bulan", TimeUnit::Month),
+    ("ay", TimeUnit::Month),
+    ("месяц", TimeUnit::Month),
+    ("місяц", TimeUnit::Month),
+    ("year", TimeUnit::Year),
+    ("año", TimeUnit::Year),
+    ("ano", TimeUnit::Year),
+    ("an", TimeUnit::Year),
+    ("jahr", TimeUnit::Year),
+    ("rok", TimeUnit::Year),
+    ("lat", TimeUnit::Year),
+    ("jaar", TimeUnit::Year),
+    ("yıl", TimeUnit::Year),
+    ("tahun", TimeUnit::Year),
+    ("год", TimeUnit::Year),
+    ("лет", TimeUnit::Year),
+    ("рік", TimeUnit::Year),
+    ("рок", TimeUnit::Year),
+];
+
+// ja / zh / ko: единица идёт сразу за числом, без пробела.
+const CJK_UNITS: &[(&str, TimeUnit)] = &[
+    ("秒", TimeUnit::Second),
+    ("초", TimeUnit::Second),
+    ("分", TimeUnit::Minute),
+    ("분", TimeUnit::Minute),
+    ("時間", TimeUnit::Hour),
+    ("小时", TimeUnit::Hour),
+    ("小時", TimeUnit::Hour),
+    ("시간", TimeUnit::Hour),
+    ("日", TimeUnit::Day),
+    ("天", TimeUnit::Day),
+    ("일", TimeUnit::Day),
+    ("週", TimeUnit::Week),
+    ("周", TimeUnit::Week),
+    ("주", TimeUnit::Week),
+    ("か月", TimeUnit::Month),
+    ("ヶ月", TimeUnit::Month),
+    ("个月", TimeUnit::Month),
+    ("個月", TimeUnit::Month),
+    ("개월", TimeUnit::Month),
+    ("年", TimeUnit::Year),
+    ("년", TimeUnit::Year),
+];
+
+const JUST_NOW: &[&str] = &[
+    "just now",
+    "только что",
+    "щойно",
+    "gerade eben",
+    "à l'instant",
+    "justo ahora",
+    "agora mesmo",
+    "proprio ora",
+    "przed chwilą",
+    "az önce",
+    "たった今",
+    "刚刚",
+    "방금",
+];
+
+/// Amount and unit of a localized relative date ("3 дня назад", "vor 2 Wochen",
+/// "hace 1 año", "5日前"). Prefixes like "Streamed" / "Трансляция закончилась"
+/// are skipped. A unit without a number ("a day ago", "вчера") counts as 1.
+pub fn parse_relative_time(text: &str) -> Option<(u64, TimeUnit)> {
+    let lower = text.to_lowercase();
+    if JUST_NOW.iter().any(|w| lower.contains(w)) {
+        return Some((0, TimeUnit::Second));
+    }
+    if ["yesterday", "вчера", "учора", "gestern", "hier", "ayer", "ontem", "ieri", "wczoraj", "dün", "昨日", "昨天", "어제"]
+        .iter()
+        .any(|w| lower.contains(w))
+    {
+        return Some((1, TimeUnit::Day));
+    }
+
+    let (amount, rest) = match NUMBER_RE.find(&lower) {
+        Some(m) => (
+            m.as_str()
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse::<u64>()
+                .ok()?,
+            &lower[m.end()..],
+        ),
+        None => (1, lower.as_str()),
+    };
+
+    let trimmed = rest.trim_start();
+    if let Some((_, unit)) = CJK_UNITS.iter().find(|(u, _)| trimmed.starts_with(u)) {
+        return Some((amount, *unit));
+    }
+    // После числа единица — первое слово; без числа ищем по всей строке
+    let has_number = rest.len() != lower.len();
+    let mut words = rest.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty());
+    let unit_of = |word: &str| {
+        WORD_UNITS
+            .iter()
+            .find(|(stem, _)| word.starts_with(stem))
+            .map(|(_, unit)| *unit)
+    };
+    if has_number {
+        words.next().and_then(unit_of).map(|unit| (amount, unit))
+    } else {
+        words.find_map(unit_of).map(|unit| (amount, unit))
+    }
+}
+
+/// Relative date in English ("3 days ago"), as the legacy clients expect.
+/// Unrecognized text is returned unchanged.
+pub fn normalize_relative_time(text: &str) -> String {
+    match parse_relative_time(text) {
+        Some((0, TimeUnit::Second)) => "just now".to_string(),
+        Some((1, unit)) => format!("1 {} ago", unit.english()),
+        Some((n, unit)) => format!("{} {}s ago", n, unit.english()),
+        None => text.to_string(),
+    }
+}
+
+/// Approximate ISO-8601 timestamp ("2023-05-01T12:00:00Z", like the Data API's
+/// `publishedAt`) for a localized relative date. None when it is not recognized.
+pub fn relative_time_to_iso(text: &str) -> Option<String> {
+    let (amount, unit) = parse_relative_time(text)?;
+    let ago = i64::try_from(amount).ok()?.checked_mul(unit.seconds())?;
+    let at = chrono::Utc::now().checked_sub_signed(chrono::Duration::try_seconds(ago)?)?;
+    Some(at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
@@ -9,10 +9,17 @@ use std::future::{ready, Ready};
 use std::io::Write;
 use std::task::{Context, Poll};
 use std::fs::{OpenOptions, create_dir_all};
+use std::collections::VecDeque;
 use std::sync::Mutex;
 
 // Global file logger
 static FILE_LOGGER: Mutex<Option<FileLogger>> = Mutex::new(None);
+// Audit trail for /admin requests, kept in its own file next to the regular log
+static AUDIT_LOGGER: Mutex<Option<FileLogger>> = Mutex::new(None);
+
+// Last warnings and errors for the admin dashboard
+static RECENT_ERRORS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+const RECENT_ERRORS_MAX: usize = 200;
 
 struct FileLogger {
     file: std::fs::File,
@@ -30,16 +37,19 @@ pub fn init_logger() {
 }
 
 pub fn init_file_logger(enabled: bool, directory: &str) {
-    if !enabled {
-        return;
-    }
-
     // Create logs directory if it doesn't exist
     if let Err(e) = create_dir_all(directory) {
         eprintln!("Failed to create log directory '{}': {}", directory, e);
         return;
     }
 
+    // admin-audit.log is kept even when file logging is off
+    open_audit_log(directory);
+
+    if !enabled {
+        return;
+    }
+
     // Generate filename with exact startup date and time
     let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
     let filename = format!("{}/yt-api-{}.log", directory, timestamp);
@@ -60,6 +70,34 @@ pub fn init_file_logger(enabled: bool, directory: &str) {
     }
 }
 
+fn open_audit_log(directory: &str) {
+    let audit_filename = format!("{}/admin-audit.log", directory);
+    match OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&audit_filename)
+    {
+        Ok(file) => {
+            let mut logger = AUDIT_LOGGER.lock().unwrap();
+            *logger = Some(FileLogger { file });
+        }
+        Err(e) => {
+            eprintln!("Failed to open audit log '{}': {}", audit_filename, e);
+        }
+    }
+}
+
+/// Writes an admin audit record to the console/main log and to admin-audit.log.
+pub fn audit(message: &str) {
+    log::warn!("[AUDIT] {}", message);
+    let mut logger = AUDIT_LOGGER.lock().unwrap();
+    if let Some(ref mut audit_logger) = *logger {
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
+        let _ = writeln!(audit_logger.file, "{} {}", timestamp, message);
+        let _ = audit_logger.file.flush();
+    }
+}
+
 fn write_to_file(message: &str) {
     let mut logger = FILE_LOGGER.lock().unwrap();
     if let Some(ref mut file_logger) = *logger {
@@ -88,15 +126,28 @@ fn format_log(buf: &mut env_logger::fmt::Formatter, record: &log::Record) -> std
         record.args()
     )?;
 
+    let plain_level = match record.level() {
+        log::Level::Error => "[ERROR]",
+        log::Level::Warn => "[WARN]",
+        log::Level::Info => "[INFO]",
+        log::Level::Debug => "[DEBUG]",
+        log::Level::Trace => "[TRACE]",
+    };
+
+    if record.level() <= log::Level::Warn {
+        let message = record.args().to_string();
+        // Аудит и так пишется в отдельный файл, в списке ошибок он только мешает
+        if !message.starts_with("[AUDIT]") {
+            let mut recent = RECENT_ERRORS.lock().unwrap();
+            if recent.len() >= RECENT_ERRORS_MAX {
+                recent.pop_front();
+            }
+            recent.push_back(format!("{} {} {}", timestamp, plain_level, message));
+        }
+    }
+
     // Write to file (without colors)
     if FILE_LOGGER.lock().unwrap().is_some() {
-        let plain_level = match record.level() {
-            log::Level::Error => "[ERROR]",
-            log::Level::Warn => "[WARN]",
-            log::Level::Info => "[INFO]",
-            log::Level::Debug => "[DEBUG]",
-            log::Level::Trace => "[TRACE]",
-        };
         let log_line = format!("{} {} {}", timestamp, plain_level, record.args());
         write_to_file(&log_line);
     }
@@ -104,6 +155,12 @@ fn format_log(buf: &mut env_logger::fmt::Formatter, record: &log::Record) -> std
     Ok(())
 }
 
+/// Most recent warnings and errors, newest first.
+pub fn recent_errors(limit: usize) -> Vec<String> {
+    let recent = RECENT_ERRORS.lock().unwrap();
+    recent.iter().rev().take(limit).cloned().collect()
+}
+
 pub use log::info;
 
 #[derive(Default)]
@@ -11,6 +11,12 @@ mod check;
 mod log;
 mod routes;
 mod ip_blocker;
+mod image_cache;
+mod player_clients;
+mod visitor_data;
+mod locale;
+mod api_quota;
+mod conversions;
 
 use routes::auth::{AuthConfig, TokenStore};
 
@@ -37,6 +43,8 @@ use routes::auth::{AuthConfig, TokenStore};
         routes::search::get_playlist_videos,
         routes::channel::get_author_videos,
         routes::channel::get_author_videos_by_id,
+        routes::channel::get_channel_tab,
+        routes::channel::get_channel_about,
         routes::channel::get_channel_thumbnail_api,
         routes::video::get_ytvideo_info,
         routes::video::get_related_videos,
@@ -44,20 +52,28 @@ use routes::auth::{AuthConfig, TokenStore};
         routes::video::direct_audio_url,
         routes::video::get_direct_video_url,
         routes::video::hls_manifest_url,
+        routes::video::player_clients_status,
         routes::video::video_proxy,
+        routes::video::hls_proxy,
         routes::video::download_video,
+        routes::captions::get_captions,
+        routes::storyboard::get_storyboard,
+        routes::storyboard::storyboard_sheet,
+        routes::live_chat::live_chat,
         routes::additional::get_recommendations,
         routes::additional::get_subscriptions,
         routes::additional::get_history,
         routes::additional::mark_video_watched,
         routes::additional::get_instants,
         routes::additional::check_api_keys,
+        routes::additional::api_keys_status,
         routes::actions::subscribe,
         routes::actions::unsubscribe,
         routes::actions::rate,
         routes::actions::check_rating,
         routes::actions::check_subscription,
         routes::additional::check_failed_api_keys,
+        routes::additional::reload_instants,
     ),
     components(
         schemas(
@@ -70,18 +86,44 @@ use routes::auth::{AuthConfig, TokenStore};
             routes::auth_routes::OAuth2UserInfoResponse,
             routes::search::TopVideo,
             routes::search::SearchResult,
+            routes::search::SearchPage,
+            routes::search::SearchItem,
+            routes::search::ShortResult,
+            routes::search::ChannelResult,
+            routes::search::PlaylistResult,
             routes::search::CategoryItem,
             routes::search::PlaylistInfo,
+            routes::search_filters::SearchSort,
+            routes::search_filters::UploadDate,
+            routes::search_filters::SearchDuration,
+            routes::search_filters::SearchFeature,
             routes::search::PlaylistVideo,
             routes::search::PlaylistResponse,
             routes::channel::ChannelInfo,
             routes::channel::ChannelVideo,
             routes::channel::ChannelVideosResponse,
+            routes::channel::ChannelTab,
+            routes::channel::ChannelTabItem,
+            routes::channel::ChannelTabPage,
+            routes::channel::CommunityPost,
+            routes::channel::ChannelAbout,
+            routes::channel::ChannelLink,
             routes::video::VideoInfoResponse,
+            routes::video::Chapter,
             routes::video::Comment,
             routes::video::RelatedVideo,
             routes::video::DirectUrlResponse,
             routes::video::HlsManifestUrlResponse,
+            routes::captions::CaptionsListResponse,
+            routes::captions::CaptionTrack,
+            routes::captions::TranslationLanguage,
+            routes::storyboard::StoryboardResponse,
+            routes::storyboard::StoryboardLevelInfo,
+            routes::live_chat::LiveChatMessage,
+            routes::live_chat::LiveChatResponse,
+            player_clients::PlayerClientStatus,
+            api_quota::ApiQuotaStatus,
+            api_quota::ApiKeyStatus,
             routes::additional::RecommendationItem,
             routes::additional::HistoryItem,
             routes::additional::SubscriptionsResponse,
@@ -100,10 +142,30 @@ use routes::auth::{AuthConfig, TokenStore};
     ),
     tags(
         (name = "YouTube Legacy API", description = "API server created to support YouTube clients for old devices")
-    )
+    ),
+    modifiers(&AdminSecurity)
 )]
 struct ApiDoc;
 
+/// Security schemes referenced by the `/admin` endpoints.
+struct AdminSecurity;
+
+impl utoipa::Modify for AdminSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "admin_basic",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Basic).build()),
+        );
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct AppState {
     config: Config,
@@ -111,6 +173,10 @@ struct AppState {
     /// Limits concurrent codec conversions (mpeg4/h263) for /direct_url.
     #[serde(skip)]
     codec_semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+
+    /// Memory + disk cache for thumbnails and channel icons.
+    #[serde(skip)]
+    image_cache: std::sync::Arc<image_cache::ImageCache>,
 }
 
 #[utoipa::path(
@@ -179,9 +245,11 @@ async fn main() -> std::io::Result<()> {
     log::info!("Starting YouTube API Legacy server on port {}...", port);
 
     let codec_semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(4));
+    let image_cache = std::sync::Arc::new(image_cache::ImageCache::new(&config.cache));
     let app_state = web::Data::new(AppState {
         config,
         codec_semaphore,
+        image_cache,
     });
 
     let openapi = ApiDoc::openapi();
@@ -202,6 +270,7 @@ async fn main() -> std::io::Result<()> {
             .route("/watch", web::get().to(routes::frontend::page_watch))
             .route("/channel", web::get().to(routes::frontend::page_channel))
             .route("/logout", web::get().to(routes::frontend::page_logout))
+            .route("/locale", web::get().to(routes::frontend::page_set_locale))
             .route("/embed/{video_id}", web::get().to(routes::frontend::page_embed))
             .route("/health", web::get().to(health_check))
             .route("/auth", web::get().to(routes::auth::auth_handler))
@@ -276,6 +345,14 @@ async fn main() -> std::io::Result<()> {
                 "/get_author_videos_by_id.php",
                 web::get().to(routes::channel::get_author_videos_by_id),
             )
+            .route(
+                "/get_channel_tab.php",
+                web::get().to(routes::channel::get_channel_tab),
+            )
+            .route(
+                "/get_channel_about.php",
+                web::get().to(routes::channel::get_channel_about),
+            )
             .route(
                 "/get_channel_thumbnail.php",
                 web::get().to(routes::channel::get_channel_thumbnail_api),
@@ -298,6 +375,13 @@ async fn main() -> std::io::Result<()> {
                     .route(web::get().to(routes::video::direct_audio_url))
                     .route(web::head().to(routes::video::direct_audio_url)),
             )
+            .route("/get_captions.php", web::get().to(routes::captions::get_captions))
+            .route("/storyboard/{video_id}", web::get().to(routes::storyboard::get_storyboard))
+            .route(
+                "/storyboard/{video_id}/{level}/{sheet}",
+                web::get().to(routes::storyboard::storyboard_sheet),
+            )
+            .route("/live_chat/{video_id}", web::get().to(routes::live_chat::live_chat))
             .service(
                 web::resource("/hls_manifest_url")
                     .route(web::get().to(routes::video::hls_manifest_url)),
@@ -306,6 +390,9 @@ async fn main() -> std::io::Result<()> {
                 "/get-direct-video-url.php",
                 web::get().to(routes::video::get_direct_video_url),
             )
+            .service(
+                web::resource("/hls.proxy").route(web::get().to(routes::video::hls_proxy)),
+            )
             .service(
                 web::resource("/video.proxy")
                     .route(web::get().to(routes::video::video_proxy))
@@ -347,11 +434,43 @@ async fn main() -> std::io::Result<()> {
             )
             .route(
                 "/check_api_keys",
-                web::get().to(routes::additional::check_api_keys),
+                web::get().to(routes::additional::moved_to_admin),
             )
             .route(
                 "/check_failed_api_keys",
-                web::get().to(routes::additional::check_failed_api_keys),
+                web::get().to(routes::additional::moved_to_admin),
+            )
+            .service(
+                web::scope("/admin")
+                    .wrap(routes::middleware::AdminAuth)
+                    .route("", web::get().to(routes::admin::page_admin))
+                    .route("/", web::get().to(routes::admin::page_admin))
+                    .service(
+                        web::resource("/cache/purge")
+                            .route(web::post().to(routes::admin::admin_cache_purge)),
+                    )
+                    .service(
+                        web::resource("/keys/toggle")
+                            .route(web::post().to(routes::admin::admin_toggle_key)),
+                    )
+                    .service(
+                        web::resource("/instances")
+                            .route(web::post().to(routes::admin::admin_save_instances)),
+                    )
+                    .service(
+                        web::resource("/check_api_keys")
+                            .route(web::post().to(routes::additional::check_api_keys)),
+                    )
+                    .service(
+                        web::resource("/check_failed_api_keys")
+                            .route(web::post().to(routes::additional::check_failed_api_keys)),
+                    )
+                    .route("/api_keys_status", web::get().to(routes::additional::api_keys_status))
+                    .route("/player_clients", web::get().to(routes::video::player_clients_status))
+                    .service(
+                        web::resource("/instants/reload")
+                            .route(web::post().to(routes::additional::reload_instants)),
+                    ),
             )
 			.route(
                 "/get_shorts.php",
@@ -0,0 +1,125 @@
+//! Per-client statistics for the innertube player fallback chain. A client that
+//! keeps failing while a later one succeeds is moved to the end of the chain for
+//! a while, so the next requests do not waste a round trip on it.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+use crate::config::{Config, InnertubeClientConfig};
+
+#[derive(Debug, Default, Clone)]
+struct ClientStats {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    demoted_until: Option<u64>,
+    last_error: Option<String>,
+}
+
+static STATS: Mutex<Option<HashMap<String, ClientStats>>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PlayerClientStatus {
+    pub client_name: String,
+    /// Position in the chain the next request will use (0 = tried first).
+    pub position: usize,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub demoted: bool,
+    /// Unix time the demotion ends.
+    pub demoted_until: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_demoted(stats: Option<&ClientStats>, now: u64) -> bool {
+    stats
+        .and_then(|s| s.demoted_until)
+        .map(|until| until > now)
+        .unwrap_or(false)
+}
+
+/// Configured chain with currently demoted clients moved to the end (keeping
+/// their relative order).
+pub fn ordered_clients(config: &Config) -> Vec<InnertubeClientConfig> {
+    let clients = config.get_innertube_player_clients();
+    let guard = STATS.lock().unwrap();
+    let now = now_secs();
+    let (mut active, demoted): (Vec<_>, Vec<_>) = clients
+        .into_iter()
+        .partition(|c| !is_demoted(guard.as_ref().and_then(|m| m.get(&c.client_name)), now));
+    active.extend(demoted);
+    active
+}
+
+pub fn record_success(client_name: &str) {
+    let mut guard = STATS.lock().unwrap();
+    let stats = guard
+        .get_or_insert_with(HashMap::new)
+        .entry(client_name.to_string())
+        .or_default();
+    stats.successes += 1;
+    stats.consecutive_failures = 0;
+    stats.demoted_until = None;
+}
+
+/// Counts a failure. Only called when a later client played the same video,
+/// so videos that are unavailable everywhere do not demote anyone.
+pub fn record_failure(client_name: &str, reason: &str, config: &Config) {
+    let mut guard = STATS.lock().unwrap();
+    let stats = guard
+        .get_or_insert_with(HashMap::new)
+        .entry(client_name.to_string())
+        .or_default();
+    stats.failures += 1;
+    stats.consecutive_failures += 1;
+    stats.last_error = Some(reason.to_string());
+    let threshold = config.api.innertube.demote_after_failures.max(1);
+    if stats.consecutive_failures >= threshold && !is_demoted(Some(stats), now_secs()) {
+        stats.demoted_until = Some(now_secs() + config.api.innertube.demote_secs);
+        log::warn!(
+            "Player client {} failed {} times in a row ({}), demoting for {}s",
+            client_name,
+            stats.consecutive_failures,
+            reason,
+            config.api.innertube.demote_secs
+        );
+    }
+}
+
+pub fn status(config: &Config) -> Vec<PlayerClientStatus> {
+    let ordered = ordered_clients(config);
+    let guard = STATS.lock().unwrap();
+    let now = now_secs();
+    ordered
+        .iter()
+        .enumerate()
+        .map(|(position, client)| {
+            let stats = guard
+                .as_ref()
+                .and_then(|m| m.get(&client.client_name))
+                .cloned()
+                .unwrap_or_default();
+            PlayerClientStatus {
+                client_name: client.client_name.clone(),
+                position,
+                successes: stats.successes,
+                failures: stats.failures,
+                consecutive_failures: stats.consecutive_failures,
+                demoted: is_demoted(Some(&stats), now),
+                demoted_until: stats.demoted_until.filter(|until| *until > now),
+                last_error: stats.last_error,
+            }
+        })
+        .collect()
+}
@@ -7,10 +7,11 @@ use std::collections::{HashMap, HashSet};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::config::Config;
+use crate::api_quota::mask_key;
+use crate::config::{Config, InstantInstance};
 use crate::routes::auth::{AuthConfig, TokenStore};
 use crate::routes::oauth::refresh_access_token;
-use std::fs;
+use std::sync::RwLock;
 fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
     if !config.server.main_url.is_empty() {
         return config.server.main_url.clone();
@@ -21,16 +22,6 @@ fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
     format!("{}://{}/", scheme, host.trim_end_matches('/'))
 }
 
-fn mask_key(key: &str) -> String {
-    let trimmed = key.trim();
-    if trimmed.len() <= 6 {
-        return "***".to_string();
-    }
-    let (start, end) = trimmed.split_at(3);
-    let suffix = &end[end.len().saturating_sub(2)..];
-    format!("{}***{}", start, suffix)
-}
-
 fn clean_text(input: &str) -> String {
     let decoded = decode_html_entities(input).to_string();
     let collapsed = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
@@ -63,15 +54,35 @@ async fn is_key_valid(client: &Client, key: &str) -> bool {
         trimmed
     );
 
-    matches!(client.get(&url).send().await, Ok(resp) if resp.status().is_success())
+    crate::api_quota::record_call(trimmed, "GET", &url);
+    match client.get(&url).send().await {
+        Ok(resp) if resp.status().is_success() => true,
+        Ok(resp) => {
+            // quotaExceeded / keyInvalid — ключ уходит в карантин до сброса квоты
+            if let Some(reason) = resp
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .as_ref()
+                .and_then(crate::api_quota::error_reason)
+            {
+                crate::api_quota::record_error(trimmed, &reason);
+            }
+            false
+        }
+        Err(_) => false,
+    }
 }
 
 #[utoipa::path(
-    get,
-    path = "/check_api_keys",
+    post,
+    path = "/admin/check_api_keys",
     responses(
-        (status = 200, description = "API key health check")
-    )
+        (status = 200, description = "API key health check; failing keys are moved to disabled in config.yml"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "Admin access is not configured")
+    ),
+    security(("admin_token" = []), ("admin_basic" = []))
 )]
 pub async fn check_api_keys() -> impl Responder {
     let path = "config.yml";
@@ -145,11 +156,14 @@ pub async fn check_api_keys() -> impl Responder {
 }
 
 #[utoipa::path(
-    get,
-    path = "/check_failed_api_keys",
+    post,
+    path = "/admin/check_failed_api_keys",
     responses(
-        (status = 200, description = "Re-check non-working API keys")
-    )
+        (status = 200, description = "Re-check non-working API keys; revived keys are moved back to active in config.yml"),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "Admin access is not configured")
+    ),
+    security(("admin_token" = []), ("admin_basic" = []))
 )]
 pub async fn check_failed_api_keys() -> impl Responder {
     let path = "config.yml";
@@ -182,6 +196,7 @@ pub async fn check_failed_api_keys() -> impl Responder {
         }
 
         if is_key_valid(&client, &normalized).await {
+            crate::api_quota::release(&normalized);
             revived_keys.push(normalized);
         } else {
             still_failed_keys.push(normalized);
@@ -212,6 +227,28 @@ pub async fn check_failed_api_keys() -> impl Responder {
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/admin/api_keys_status",
+    responses(
+        (status = 200, description = "Data API units used per key today, quarantined keys and the next quota reset (midnight Pacific time)", body = ApiQuotaStatus),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "Admin access is not configured")
+    ),
+    security(("admin_token" = []), ("admin_basic" = []))
+)]
+pub async fn api_keys_status(data: web::Data<crate::AppState>) -> impl Responder {
+    HttpResponse::Ok().json(crate::api_quota::status(&data.config))
+}
+
+/// Old public GET routes of the maintenance endpoints, kept to tell clients where they went.
+pub async fn moved_to_admin(req: HttpRequest) -> impl Responder {
+    HttpResponse::Gone().json(serde_json::json!({
+        "error": "This endpoint has moved",
+        "details": format!("Use POST /admin{} with admin credentials", req.path())
+    }))
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct RecommendationItem {
     pub title: String,
@@ -272,31 +309,8 @@ pub struct InstantsResponse {
 
 // Умный парсер просмотров. Превращает "217K views" или "41 тыс. просмотров" в "217000"
 fn parse_view_count_safe(text: &str) -> String {
-    let lower = text.to_lowercase();
-    let mut multiplier = 1.0;
-    
-    if lower.contains('k') || lower.contains("тыс") {
-        multiplier = 1000.0;
-    } else if lower.contains('m') || lower.contains("млн") {
-        multiplier = 1000000.0;
-    } else if lower.contains('b') || lower.contains("млрд") {
-        multiplier = 1000000000.0;
-    }
-
-    // Вытаскиваем только цифры и разделители
-    let num_str: String = text.chars().filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',').collect();
-    let num_str = num_str.replace(",", "."); // Нормализуем запятую в точку для f64
-
-    if let Ok(num) = num_str.parse::<f64>() {
-        let result = ((num * multiplier) as u64).to_string();
-        // Возвращаем распарсенное число, только если это не случайный ноль от букв
-        if result != "0" || num_str == "0" || num_str == "0.0" {
-            return result;
-        }
-    }
-    
     // Если это не число (например, "50 видео" для плейлиста), отдаем как есть
-    text.to_string()
+    crate::locale::count_string(text, text)
 }
 
 // Универсальный извлекатель текста, игнорирующий любой мусор
@@ -542,9 +556,11 @@ fn parse_recommendations(
 }
 
 async fn fetch_history_page(
+    refresh_token: &str,
     access_token: &str,
     continuation: Option<String>,
     config: &crate::config::Config,
+    locale: &crate::locale::Locale,
 ) -> Option<serde_json::Value> {
     let client = Client::new();
     let mut payload = serde_json::json!({
@@ -562,12 +578,15 @@ async fn fetch_history_page(
     if let Some(cont) = continuation {
         payload["continuation"] = serde_json::Value::String(cont);
     }
+    locale.apply(&mut payload);
+    let innertube_key = config.get_innertube_key()?;
+    let visitor = crate::visitor_data::for_account(config, refresh_token).await;
+    crate::visitor_data::attach(&mut payload, visitor.as_deref());
     let url = format!(
         "https://www.youtube.com/youtubei/v1/browse?key={}",
-        config.get_api_key_rotated()
+        innertube_key
     );
-    let res = client
-        .post(&url)
+    let res = crate::visitor_data::with_header(client.post(&url), visitor.as_deref())
         .header("Authorization", format!("Bearer {}", access_token))
         .json(&payload)
         .send()
@@ -747,6 +766,7 @@ pub async fn fetch_history_for_token(
     refresh_token: &str,
     auth_config: &AuthConfig,
     config: &crate::config::Config,
+    locale: &crate::locale::Locale,
     base_trimmed: &str,
     count: usize,
 ) -> Vec<HistoryItem> {
@@ -758,7 +778,7 @@ pub async fn fetch_history_for_token(
     let mut continuation: Option<String> = None;
 
     while videos.len() < count {
-        let page = fetch_history_page(&access_token, continuation.clone(), config).await;
+        let page = fetch_history_page(refresh_token, &access_token, continuation.clone(), config, locale).await;
         if page.is_none() {
             break;
         }
@@ -781,6 +801,7 @@ pub async fn fetch_recommendations_for_token(
     refresh_token: &str,
     auth_config: &AuthConfig,
     config: &crate::config::Config,
+    locale: &crate::locale::Locale,
     base_trimmed: &str,
     count: usize,
     page_token: Option<String>, 
@@ -792,7 +813,7 @@ pub async fn fetch_recommendations_for_token(
     let mut payload = serde_json::json!({
         "context": {
             "client": {
-                "hl": "ru", "gl": "US", "deviceMake": "Samsung", "deviceModel": "SmartTV",
+                "deviceMake": "Samsung", "deviceModel": "SmartTV",
                 "userAgent": "Mozilla/5.0 (SMART-TV; Linux; Tizen 5.0) AppleWebKit/538.1",
                 "clientName": "TVHTML5", "clientVersion": "7.20250209.19.00",
                 "osName": "Tizen", "osVersion": "5.0", "platform": "TV",
@@ -805,9 +826,12 @@ pub async fn fetch_recommendations_for_token(
     if let Some(token) = page_token {
         payload["continuation"] = serde_json::Value::String(token);
     }
+    locale.apply(&mut payload);
+    let visitor = crate::visitor_data::for_account(config, refresh_token).await;
+    crate::visitor_data::attach(&mut payload, visitor.as_deref());
 
     let url = format!("https://www.youtube.com/youtubei/v1/browse?key={}", api_key);
-    let response = client.post(&url)
+    let response = crate::visitor_data::with_header(client.post(&url), visitor.as_deref())
         .header("Authorization", format!("Bearer {}", access_token))
         .json(&payload).send().await.ok()?;
         
@@ -834,7 +858,9 @@ pub async fn fetch_recommendations_for_token(
     params(
         ("token" = String, Query, description = "Refresh token"),
         ("pageToken" = Option<String>, Query, description = "Continuation token for next page"),
-        ("count" = Option<i32>, Query, description = "How many recommendations to return")
+        ("count" = Option<i32>, Query, description = "How many recommendations to return"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses((status = 200, description = "Recommendations list", body = RecommendationsResponse))
 )]
@@ -851,8 +877,9 @@ pub async fn get_recommendations(
 
     let count: usize = query_params.get("count").and_then(|c| c.parse().ok()).unwrap_or(20); // Ограничим до 20 за раз для скорости
     let page_token = query_params.get("pageToken").cloned();
+    let locale = crate::locale::Locale::from_request(&req, &data.config);
 
-    match fetch_recommendations_for_token(&refresh_token, &auth_config, &data.config, &base_trimmed, count, page_token).await {
+    match fetch_recommendations_for_token(&refresh_token, &auth_config, &data.config, &locale, &base_trimmed, count, page_token).await {
         Some(response) => HttpResponse::Ok().json(response),
         None => HttpResponse::InternalServerError().json(serde_json::json!({"error": "Failed to get recommendations"})),
     }
@@ -966,6 +993,7 @@ pub async fn fetch_subscriptions_for_token(
     refresh_token: &str,
     auth_config: &AuthConfig,
     config: &crate::config::Config,
+    locale: &crate::locale::Locale,
     base_trimmed: &str,
 ) -> Vec<SubscriptionItem> {
     let access_token = match refresh_access_token(refresh_token, auth_config).await {
@@ -973,7 +1001,7 @@ pub async fn fetch_subscriptions_for_token(
         Err(_) => return Vec::new(),
     };
     let client = Client::new();
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "context": {
             "client": {
                 "hl": "en", "gl": "US", "deviceMake": "Samsung", "deviceModel": "SmartTV",
@@ -985,12 +1013,17 @@ pub async fn fetch_subscriptions_for_token(
         },
         "browseId": "FEsubscriptions"
     });
+    locale.apply(&mut payload);
+    let Some(innertube_key) = config.get_innertube_key() else {
+        return Vec::new();
+    };
+    let visitor = crate::visitor_data::for_account(config, refresh_token).await;
+    crate::visitor_data::attach(&mut payload, visitor.as_deref());
     let url = format!(
         "https://www.youtube.com/youtubei/v1/browse?key={}",
-        config.get_api_key_rotated()
+        innertube_key
     );
-    let Ok(response) = client
-        .post(&url)
+    let Ok(response) = crate::visitor_data::with_header(client.post(&url), visitor.as_deref())
         .header("Authorization", format!("Bearer {}", access_token))
         .json(&payload)
         .send()
@@ -1008,7 +1041,9 @@ pub async fn fetch_subscriptions_for_token(
     get,
     path = "/get_subscriptions.php",
     params(
-        ("token" = String, Query, description = "Refresh token")
+        ("token" = String, Query, description = "Refresh token"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Subscriptions list", body = SubscriptionsResponse),
@@ -1041,7 +1076,7 @@ pub async fn get_subscriptions(
     };
 
     let client = reqwest::Client::new();
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "context": {
             "client": {
                 "hl": "en", "gl": "US", "deviceMake": "Samsung", "deviceModel": "SmartTV",
@@ -1053,14 +1088,20 @@ pub async fn get_subscriptions(
         },
         "browseId": "FEsubscriptions"
     });
+    crate::locale::Locale::from_request(&req, &data.config).apply(&mut payload);
+    let visitor = crate::visitor_data::for_account(&data.config, &refresh_token).await;
+    crate::visitor_data::attach(&mut payload, visitor.as_deref());
 
+    let innertube_key = match data.config.get_innertube_key() {
+        Some(key) => key,
+        None => return HttpResponse::InternalServerError().json(serde_json::json!({"error": "Missing innertube_key in config.yml"})),
+    };
     let url = format!(
         "https://www.youtube.com/youtubei/v1/browse?key={}",
-        data.config.get_api_key_rotated()
+        innertube_key
     );
 
-    let res = client
-        .post(url)
+    let res = crate::visitor_data::with_header(client.post(url), visitor.as_deref())
         .header("Authorization", format!("Bearer {}", access_token))
         .json(&payload)
         .send()
@@ -1117,7 +1158,8 @@ pub async fn get_subscriptions_session(
     
     let subscriptions = match refresh_token {
         Some(ref token) => {
-            fetch_subscriptions_for_token(token, &auth_config, &data.config, base_trimmed).await
+            let locale = crate::locale::Locale::from_request(&req, &data.config);
+            fetch_subscriptions_for_token(token, &auth_config, &data.config, &locale, base_trimmed).await
         }
         None => Vec::new(),
     };
@@ -1132,7 +1174,9 @@ pub async fn get_subscriptions_session(
     path = "/get_history.php",
     params(
         ("token" = String, Query, description = "Refresh token"),
-        ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)")
+        ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Watch history", body = [HistoryItem]),
@@ -1178,10 +1222,11 @@ pub async fn get_history(
         }
     };
 
+    let locale = crate::locale::Locale::from_request(&req, &data.config);
     let mut videos: Vec<HistoryItem> = Vec::new();
     let mut continuation: Option<String> = None;
     while videos.len() < count {
-        let page = fetch_history_page(&access_token, continuation.clone(), &data.config).await;
+        let page = fetch_history_page(&refresh_token, &access_token, continuation.clone(), &data.config, &locale).await;
         if page.is_none() {
             break;
         }
@@ -1297,19 +1342,20 @@ pub async fn mark_video_watched(
     let cpn = generate_cpn();
     let user_agent = "com.google.android.youtube/19.14.37";
 
-    let context = serde_json::json!({
+    let mut context = serde_json::json!({
         "context": {
             "client": {
                 "clientName": "ANDROID",
                 "clientVersion": "19.14.37",
-                "hl": "en",
-                "gl": "US",
                 "osName": "Android",
                 "osVersion": "13",
                 "platform": "MOBILE"
             }
         }
     });
+    crate::locale::Locale::from_request(&req, &data.config).apply(&mut context);
+    let visitor = crate::visitor_data::for_account(&data.config, &refresh_token).await;
+    crate::visitor_data::attach(&mut context, visitor.as_deref());
 
     let build_payload = |include_params: bool| {
         let mut payload = serde_json::json!({
@@ -1330,11 +1376,13 @@ pub async fn mark_video_watched(
 
     for include_params in [false, true] {
         let player_payload = build_payload(include_params);
-        let resp = client
-            .post(&format!(
+        let resp = crate::visitor_data::with_header(
+            client.post(&format!(
                 "https://www.youtube.com/youtubei/v1/player?key={}",
                 api_key
-            ))
+            )),
+            visitor.as_deref(),
+        )
             .header("Authorization", format!("Bearer {}", access_token))
             .header("Content-Type", "application/json")
             .header("User-Agent", user_agent)
@@ -1391,11 +1439,13 @@ pub async fn mark_video_watched(
         "feedbackTokens": [feedback_token]
     });
 
-    let feedback_resp = client
-        .post(&format!(
+    let feedback_resp = crate::visitor_data::with_header(
+        client.post(&format!(
             "https://www.youtube.com/youtubei/v1/feedback?key={}",
             api_key
-        ))
+        )),
+        visitor.as_deref(),
+    )
         .header("Authorization", format!("Bearer {}", access_token))
         .header("Content-Type", "application/json")
         .header("User-Agent", user_agent)
@@ -1424,6 +1474,23 @@ pub async fn mark_video_watched(
     }
 }
 
+// Список инстансов держим в памяти; config.yml перечитывается только через /admin/instants/reload
+static INSTANCES: RwLock<Option<Vec<InstantInstance>>> = RwLock::new(None);
+
+/// Replaces the served instance list, e.g. after the admin dashboard saved config.yml.
+pub(crate) fn set_instants(instants: Vec<InstantInstance>) {
+    *INSTANCES.write().unwrap() = Some(instants);
+}
+
+fn instants_response(instants: Vec<InstantInstance>) -> InstantsResponse {
+    InstantsResponse {
+        instants: instants
+            .into_iter()
+            .map(|i| InstantItem { url: i.0 })
+            .collect(),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/get-instants",
@@ -1432,23 +1499,38 @@ pub async fn mark_video_watched(
     )
 )]
 pub async fn get_instants(data: web::Data<crate::AppState>) -> impl Responder {
-    let instants = match fs::read_to_string("config.yml") {
-        Ok(contents) => {
-            if let Ok(parsed) = serde_yaml::from_str::<Config>(&contents) {
-                parsed.instants
-            } else {
-                data.config.instants.clone()
-            }
-        }
-        Err(_) => data.config.instants.clone(),
-    };
+    let instants = INSTANCES
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| data.config.instants.clone());
 
-    let response = InstantsResponse {
-        instants: instants
-            .into_iter()
-            .map(|i| InstantItem { url: i.0 })
-            .collect(),
+    HttpResponse::Ok().json(instants_response(instants))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/instants/reload",
+    responses(
+        (status = 200, description = "Instances re-read from config.yml", body = InstantsResponse),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "Admin access is not configured"),
+        (status = 500, description = "config.yml could not be read")
+    ),
+    security(("admin_token" = []), ("admin_basic" = []))
+)]
+pub async fn reload_instants() -> impl Responder {
+    let config = match Config::from_file("config.yml") {
+        Ok(c) => c,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to load config",
+                "details": e.to_string()
+            }));
+        }
     };
 
-    HttpResponse::Ok().json(response)
+    crate::log::info!("Reloaded {} instances from config.yml", config.instants.len());
+    set_instants(config.instants.clone());
+    HttpResponse::Ok().json(instants_response(config.instants))
 }
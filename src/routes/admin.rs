@@ -0,0 +1,597 @@
+//! Admin dashboard at `/admin`: API keys, caches, conversions, blocked IPs,
+//! recent errors, sessions and player clients on one page, plus the forms that
+//! change them. Everything here sits behind `AdminAuth`; the POST handlers
+//! redirect back to the dashboard with a status message.
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
+use html_escape::encode_double_quoted_attribute;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{Config, InstantInstance};
+use crate::routes::auth::TokenStore;
+use crate::routes::frontend::{h, load_template, render_navbar};
+
+const CONFIG_PATH: &str = "config.yml";
+const CACHE_LIST_LIMIT: usize = 50;
+const ERRORS_LIST_LIMIT: usize = 50;
+
+fn base_url(req: &HttpRequest, config: &Config) -> String {
+    if !config.server.main_url.is_empty() {
+        return config.server.main_url.trim_end_matches('/').to_string();
+    }
+    let info = req.connection_info();
+    let scheme = info.scheme();
+    let host = info.host();
+    format!("{}://{}", scheme, host.trim_end_matches('/'))
+}
+
+fn attr(s: &str) -> String {
+    encode_double_quoted_attribute(s).to_string()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_age(timestamp: u64) -> String {
+    let secs = now_secs().saturating_sub(timestamp);
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn format_unix(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// config.yml as it is on disk now; the in-memory config only reflects startup.
+fn load_disk_config(data: &crate::AppState) -> Config {
+    Config::from_file(CONFIG_PATH).unwrap_or_else(|_| data.config.clone())
+}
+
+/// Temp dir shared by muxed downloads and codec conversions.
+fn media_cache_dir(config: &Config) -> PathBuf {
+    config
+        .cache
+        .temp_dir
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+}
+
+struct MediaFile {
+    name: String,
+    size: u64,
+    modified: u64,
+}
+
+fn media_cache_files(dir: &Path) -> Vec<MediaFile> {
+    let mut files: Vec<MediaFile> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    if !crate::routes::video::is_direct_url_temp_file(&name) {
+                        return None;
+                    }
+                    let meta = e.metadata().ok()?;
+                    let modified = meta
+                        .modified()
+                        .ok()
+                        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    Some(MediaFile {
+                        name,
+                        size: meta.len(),
+                        modified,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|f| std::cmp::Reverse(f.modified));
+    files
+}
+
+fn redirect(main_url: &str, message: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            header::LOCATION,
+            format!("{}/admin?msg={}", main_url, urlencoding::encode(message)),
+        ))
+        .finish()
+}
+
+fn section(title: &str, body: &str) -> String {
+    format!(
+        "<div class=\"admin-section\"><h3 class=\"admin-section-title\">{}</h3>{}</div>\n",
+        h(title),
+        body
+    )
+}
+
+fn post_button(main_url: &str, action: &str, fields: &[(&str, &str)], label: &str) -> String {
+    let inputs: String = fields
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "<input type=\"hidden\" name=\"{}\" value=\"{}\">",
+                attr(name),
+                attr(value)
+            )
+        })
+        .collect();
+    format!(
+        "<form class=\"admin-inline-form\" method=\"post\" action=\"{}/admin/{}\">{}<button type=\"submit\" class=\"yt-uix-button yt-uix-button-size-small yt-uix-button-default\"><span class=\"yt-uix-button-content\">{}</span></button></form>",
+        main_url,
+        action,
+        inputs,
+        h(label)
+    )
+}
+
+fn render_keys(config: &Config, main_url: &str) -> String {
+    let mut rows = String::new();
+    let lists = [("active", &config.api.keys.active), ("disabled", &config.api.keys.disabled)];
+    for (list, keys) in lists {
+        for (index, key) in keys.iter().enumerate() {
+            if key.trim().is_empty() {
+                continue;
+            }
+            let status = crate::api_quota::key_status(key, list, config);
+            let quarantine = match (status.quarantined_until, &status.quarantine_reason) {
+                (Some(until), Some(reason)) => format!("{} until {}", h(reason), format_unix(until)),
+                (Some(until), None) => format!("until {}", format_unix(until)),
+                _ => String::new(),
+            };
+            let index = index.to_string();
+            let toggle_label = if list == "active" { "Disable" } else { "Enable" };
+            rows.push_str(&format!(
+                "<tr><td><code>{}</code></td><td>{}</td><td>{} / {}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                h(&status.key),
+                list,
+                status.units_used,
+                status.daily_quota,
+                status.calls,
+                status.errors,
+                quarantine,
+                h(status.last_error.as_deref().unwrap_or("")),
+                post_button(
+                    main_url,
+                    "keys/toggle",
+                    &[("list", list), ("index", &index), ("key", &status.key)],
+                    toggle_label
+                )
+            ));
+        }
+    }
+    if rows.is_empty() {
+        return "<p class=\"admin-empty\">No Data API keys configured.</p>".to_string();
+    }
+    let actions = format!(
+        "<p>{} {} <a href=\"{}/admin/api_keys_status\">JSON</a></p>",
+        post_button(main_url, "check_api_keys", &[], "Check active keys"),
+        post_button(main_url, "check_failed_api_keys", &[], "Re-check disabled keys"),
+        main_url
+    );
+    format!(
+        "<table class=\"admin-table\"><tr><th>Key</th><th>List</th><th>Units today</th><th>Calls</th><th>Errors</th><th>Quarantine</th><th>Last error</th><th></th></tr>{}</table>\
+         <p class=\"admin-note\">Quota resets at {}.</p>{}",
+        rows,
+        format_unix(crate::api_quota::next_reset()),
+        actions
+    )
+}
+
+async fn render_cache(data: &crate::AppState, config: &Config, main_url: &str) -> String {
+    let usage = data.image_cache.usage().await;
+    let entries = data.image_cache.entries(CACHE_LIST_LIMIT).await;
+
+    let mut out = format!(
+        "<p>Images in memory: <b>{}</b> entries, {} of {}. On disk: {} of {}{}.</p>",
+        usage.memory_entries,
+        format_bytes(usage.memory_bytes),
+        format_bytes(usage.memory_max_bytes),
+        format_bytes(usage.disk_bytes),
+        format_bytes(usage.disk_max_bytes),
+        usage
+            .disk_dir
+            .as_deref()
+            .map(|d| format!(" in <code>{}</code>", h(d)))
+            .unwrap_or_else(|| " (disk tier disabled)".to_string())
+    );
+    out.push_str(&post_button(main_url, "cache/purge", &[("target", "images")], "Purge all images"));
+
+    if !entries.is_empty() {
+        out.push_str("<table class=\"admin-table\"><tr><th>Key</th><th>Type</th><th>Size</th><th>Cached</th><th>Tier</th><th></th></tr>");
+        for entry in &entries {
+            out.push_str(&format!(
+                "<tr><td class=\"admin-wrap\">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                h(&entry.key),
+                h(&entry.content_type),
+                format_bytes(entry.size),
+                format_age(entry.created),
+                if entry.in_memory { "memory" } else { "disk" },
+                post_button(main_url, "cache/purge", &[("target", "image"), ("key", &entry.key)], "Remove")
+            ));
+        }
+        out.push_str("</table>");
+    }
+
+    let dir = media_cache_dir(config);
+    let files = media_cache_files(&dir);
+    let total: u64 = files.iter().map(|f| f.size).sum();
+    out.push_str(&format!(
+        "<p>Converted and muxed media in <code>{}</code>: <b>{}</b> files, {}.</p>",
+        h(&dir.display().to_string()),
+        files.len(),
+        format_bytes(total)
+    ));
+    if !files.is_empty() {
+        out.push_str(&post_button(main_url, "cache/purge", &[("target", "media")], "Delete all media files"));
+        out.push_str("<table class=\"admin-table\"><tr><th>File</th><th>Size</th><th>Modified</th><th></th></tr>");
+        for file in files.iter().take(CACHE_LIST_LIMIT) {
+            out.push_str(&format!(
+                "<tr><td class=\"admin-wrap\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                h(&file.name),
+                format_bytes(file.size),
+                format_age(file.modified),
+                post_button(main_url, "cache/purge", &[("target", "media_file"), ("name", &file.name)], "Delete")
+            ));
+        }
+        out.push_str("</table>");
+    }
+    out
+}
+
+fn render_conversions(data: &crate::AppState) -> String {
+    let conversions = crate::conversions::snapshot();
+    let running = conversions.iter().filter(|c| c.started_at.is_some()).count();
+    let mut out = format!(
+        "<p>Running: <b>{}</b>, queued: <b>{}</b>, free codec slots: <b>{}</b>.</p>",
+        running,
+        conversions.len() - running,
+        data.codec_semaphore.available_permits()
+    );
+    if !conversions.is_empty() {
+        out.push_str("<table class=\"admin-table\"><tr><th>Conversion</th><th>State</th><th>Queued</th><th>Started</th></tr>");
+        for c in &conversions {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                h(&c.label),
+                if c.started_at.is_some() { "running" } else { "waiting" },
+                format_age(c.queued_at),
+                c.started_at.map(format_age).unwrap_or_default()
+            ));
+        }
+        out.push_str("</table>");
+    }
+    out
+}
+
+fn render_blocked_ips() -> String {
+    let ips = crate::ip_blocker::blocked_ips();
+    if ips.is_empty() {
+        return "<p class=\"admin-empty\">No blocked IPs (robots.txt).</p>".to_string();
+    }
+    let list: String = ips.iter().map(|ip| format!("<li><code>{}</code></li>", h(ip))).collect();
+    format!("<p>{} address(es) from robots.txt:</p><ul class=\"admin-list\">{}</ul>", ips.len(), list)
+}
+
+fn render_errors() -> String {
+    let errors = crate::log::recent_errors(ERRORS_LIST_LIMIT);
+    if errors.is_empty() {
+        return "<p class=\"admin-empty\">No warnings or errors since startup.</p>".to_string();
+    }
+    let lines: Vec<String> = errors.iter().map(|line| h(line)).collect();
+    format!("<pre class=\"admin-log\">{}</pre>", lines.join("\n"))
+}
+
+fn render_sessions(token_store: &TokenStore) -> String {
+    let mut out = format!(
+        "<p>Signed-in web sessions: <b>{}</b>, pending device sign-ins: <b>{}</b>.</p>",
+        token_store.session_count(),
+        token_store.device_flow_count()
+    );
+    let devices = crate::routes::auth_routes::linked_devices();
+    if devices.is_empty() {
+        out.push_str("<p class=\"admin-empty\">No linked devices.</p>");
+        return out;
+    }
+    out.push_str("<table class=\"admin-table\"><tr><th>Device</th><th>Username</th><th>Linked</th><th>Refresh token</th></tr>");
+    for device in &devices {
+        out.push_str(&format!(
+            "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            h(&crate::api_quota::mask_key(&device.device_id)),
+            h(&device.username),
+            if device.is_linked { "yes" } else { "no" },
+            if device.has_refresh_token { "yes" } else { "no" }
+        ));
+    }
+    out.push_str("</table>");
+    out
+}
+
+fn render_player_clients(config: &Config) -> String {
+    let mut out = String::from(
+        "<table class=\"admin-table\"><tr><th>#</th><th>Client</th><th>OK</th><th>Failed</th><th>In a row</th><th>Demoted until</th><th>Last error</th></tr>",
+    );
+    for client in crate::player_clients::status(config) {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            client.position,
+            h(&client.client_name),
+            client.successes,
+            client.failures,
+            client.consecutive_failures,
+            client
+                .demoted_until
+                .map(|until| format_unix(until as i64))
+                .unwrap_or_default(),
+            h(client.last_error.as_deref().unwrap_or(""))
+        ));
+    }
+    out.push_str("</table>");
+    out
+}
+
+fn render_instances(config: &Config, main_url: &str) -> String {
+    let list: Vec<&str> = config.instants.iter().map(|i| i.0.as_str()).collect();
+    format!(
+        "<form method=\"post\" action=\"{}/admin/instances\">\
+         <p>One URL per line, served by <a href=\"{}/get-instants\">/get-instants</a>.</p>\
+         <textarea name=\"instances\" rows=\"8\" class=\"admin-textarea\">{}</textarea>\
+         <p><button type=\"submit\" class=\"yt-uix-button yt-uix-button-size-default yt-uix-button-primary\"><span class=\"yt-uix-button-content\">Save instances</span></button></p>\
+         </form>",
+        main_url,
+        main_url,
+        h(&list.join("\n"))
+    )
+}
+
+pub async fn page_admin(
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+    token_store: web::Data<TokenStore>,
+    query: web::Query<HashMap<String, String>>,
+) -> impl Responder {
+    let main_url = base_url(&req, &data.config);
+    let config = load_disk_config(&data);
+
+    let message = query
+        .get("msg")
+        .filter(|m| !m.is_empty())
+        .map(|m| format!("<div class=\"admin-message\">{}</div>", h(m)))
+        .unwrap_or_default();
+
+    let mut sections = String::new();
+    sections.push_str(&section("Data API keys", &render_keys(&config, &main_url)));
+    sections.push_str(&section("Cache", &render_cache(&data, &config, &main_url).await));
+    sections.push_str(&section("Conversions", &render_conversions(&data)));
+    sections.push_str(&section("Player clients", &render_player_clients(&data.config)));
+    sections.push_str(&section("Sessions", &render_sessions(&token_store)));
+    sections.push_str(&section("Blocked IPs", &render_blocked_ips()));
+    sections.push_str(&section("Recent errors", &render_errors()));
+    sections.push_str(&section("Instances", &render_instances(&config, &main_url)));
+
+    let html = load_template("admin")
+        .replace("{{NAVBAR}}", &render_navbar(&main_url, ""))
+        .replace("{{MAIN_URL}}", &main_url)
+        .replace("{{ADMIN_MESSAGE}}", &message)
+        .replace("{{ADMIN_SECTIONS}}", &sections);
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .body(html)
+}
+
+/// `target`: `images`, `image` (+ `key`), `media` or `media_file` (+ `name`).
+pub async fn admin_cache_purge(
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+    form: web::Form<HashMap<String, String>>,
+) -> impl Responder {
+    let main_url = base_url(&req, &data.config);
+    let target = form.get("target").map(|s| s.as_str()).unwrap_or("");
+
+    let message = match target {
+        "images" => {
+            let freed = data.image_cache.purge().await;
+            format!("Image cache purged, {} freed", format_bytes(freed))
+        }
+        "image" => {
+            let key = form.get("key").map(|s| s.as_str()).unwrap_or("");
+            if data.image_cache.remove(key).await {
+                format!("Removed {} from the image cache", key)
+            } else {
+                format!("{} is not in the image cache", key)
+            }
+        }
+        "media" => {
+            let dir = media_cache_dir(&data.config);
+            let mut removed = 0;
+            let mut skipped = 0;
+            let mut freed = 0;
+            for file in media_cache_files(&dir) {
+                // Файлы, которые ещё пишутся, не трогаем — их удаление ломает конвертацию
+                if crate::routes::video::is_direct_url_temp_file_in_use(&dir, &file.name) {
+                    skipped += 1;
+                    continue;
+                }
+                if fs::remove_file(dir.join(&file.name)).is_ok() {
+                    removed += 1;
+                    freed += file.size;
+                }
+            }
+            if skipped > 0 {
+                format!(
+                    "Deleted {} media file(s), {} freed; {} still being written were kept",
+                    removed,
+                    format_bytes(freed),
+                    skipped
+                )
+            } else {
+                format!("Deleted {} media file(s), {} freed", removed, format_bytes(freed))
+            }
+        }
+        "media_file" => {
+            let name = form.get("name").map(|s| s.as_str()).unwrap_or("");
+            // Только имя файла из временной папки, никаких путей
+            let valid = crate::routes::video::is_direct_url_temp_file(name)
+                && !name.contains(['/', '\\'])
+                && !name.contains("..");
+            if !valid {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Invalid media file name",
+                    "details": name
+                }));
+            }
+            let dir = media_cache_dir(&data.config);
+            if crate::routes::video::is_direct_url_temp_file_in_use(&dir, name) {
+                format!("{} is still being written, not deleted", name)
+            } else {
+                match fs::remove_file(dir.join(name)) {
+                    Ok(()) => format!("Deleted {}", name),
+                    Err(e) => format!("Failed to delete {}: {}", name, e),
+                }
+            }
+        }
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid target parameter",
+                "details": "Must be one of: images, image, media, media_file"
+            }));
+        }
+    };
+
+    crate::log::audit(&format!("cache purge ({}): {}", target, message));
+    redirect(&main_url, &message)
+}
+
+/// Moves a key between `api.keys.active` and `api.keys.disabled` in config.yml.
+/// The dashboard identifies keys by list and position; `key` is the masked key
+/// it showed, so a config edited in the meantime is not toggled blindly.
+pub async fn admin_toggle_key(
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+    form: web::Form<HashMap<String, String>>,
+) -> impl Responder {
+    let main_url = base_url(&req, &data.config);
+
+    let list = form.get("list").map(|s| s.as_str()).unwrap_or("");
+    let index = form.get("index").and_then(|s| s.parse::<usize>().ok());
+    let masked = form.get("key").map(|s| s.as_str()).unwrap_or("");
+    if !matches!(list, "active" | "disabled") || index.is_none() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid key parameters",
+            "details": "list must be active or disabled, index must be a number"
+        }));
+    }
+    let index = index.unwrap_or_default();
+
+    let mut config = match Config::from_file(CONFIG_PATH) {
+        Ok(c) => c,
+        Err(e) => return redirect(&main_url, &format!("Failed to load config: {}", e)),
+    };
+    let keys = &mut config.api.keys;
+    let (from, to) = if list == "active" {
+        (&mut keys.active, &mut keys.disabled)
+    } else {
+        (&mut keys.disabled, &mut keys.active)
+    };
+    let key = match from.get(index) {
+        Some(k) if crate::api_quota::mask_key(k) == masked => from.remove(index),
+        _ => return redirect(&main_url, "Key list changed on disk, nothing toggled"),
+    };
+    let key = key.trim().to_string();
+    if !to.iter().any(|k| k.trim() == key) {
+        to.push(key.clone());
+    }
+
+    if let Err(e) = config.persist(CONFIG_PATH) {
+        return redirect(&main_url, &e);
+    }
+
+    let disabling = list == "active";
+    crate::api_quota::set_disabled(&key, disabling);
+    if !disabling {
+        crate::api_quota::release(&key);
+    }
+    let message = format!(
+        "Key {} {}",
+        crate::api_quota::mask_key(&key),
+        if disabling { "disabled" } else { "enabled" }
+    );
+    crate::log::audit(&message);
+    redirect(&main_url, &message)
+}
+
+/// Replaces `instances` in config.yml with the textarea contents and serves the
+/// new list from /get-instants right away.
+pub async fn admin_save_instances(
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+    form: web::Form<HashMap<String, String>>,
+) -> impl Responder {
+    let main_url = base_url(&req, &data.config);
+
+    let lines: Vec<String> = form
+        .get("instances")
+        .map(|s| s.as_str())
+        .unwrap_or("")
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if let Some(bad) = lines
+        .iter()
+        .find(|l| !(l.starts_with("http://") || l.starts_with("https://")))
+    {
+        return redirect(&main_url, &format!("Not an http(s) URL: {}", bad));
+    }
+
+    let mut config = match Config::from_file(CONFIG_PATH) {
+        Ok(c) => c,
+        Err(e) => return redirect(&main_url, &format!("Failed to load config: {}", e)),
+    };
+    config.instants = lines.into_iter().map(InstantInstance).collect();
+    if let Err(e) = config.persist(CONFIG_PATH) {
+        return redirect(&main_url, &e);
+    }
+    crate::routes::additional::set_instants(config.instants.clone());
+
+    let message = format!("Saved {} instance(s)", config.instants.len());
+    crate::log::audit(&message);
+    redirect(&main_url, &message)
+}
@@ -44,6 +44,14 @@ impl TokenStore {
         tokens.remove(session_id)
     }
 
+    pub fn session_count(&self) -> usize {
+        self.tokens.lock().unwrap().len()
+    }
+
+    pub fn device_flow_count(&self) -> usize {
+        self.device_flows.lock().unwrap().len()
+    }
+
     pub fn store_device_flow(&self, session_id: String, data: DeviceFlowData) {
         let mut flows = self.device_flows.lock().unwrap();
         flows.insert(session_id, data);
@@ -920,13 +928,11 @@ pub async fn account_info(
     };
     
     // Запрос к YouTubei API для получения информации об аккаунте
-    let body = serde_json::json!({
+    let mut body = serde_json::json!({
         "context": {
             "client": {
                 "clientName": "TVHTML5",
                 "clientVersion": "7.20251217.19.00",
-                "hl": "ru",
-                "gl": "RU",
                 "platform": "TV"
             },
             "user": {
@@ -941,6 +947,9 @@ pub async fn account_info(
             "returnFamilyMembersAccounts": false
         }
     });
+    if let Some(state) = req.app_data::<web::Data<crate::AppState>>() {
+        crate::locale::Locale::from_request(&req, &state.config).apply(&mut body);
+    }
 
     let accounts_res = client
         .post("https://www.youtube.com/youtubei/v1/account/accounts_list?prettyPrint=false")
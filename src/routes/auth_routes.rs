@@ -62,6 +62,26 @@ fn save_sessions(sessions: &Vec<YouTubeSession>) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+/// Devices linked through `/link_device_token`, without their credentials.
+pub struct LinkedDevice {
+    pub device_id: String,
+    pub username: String,
+    pub is_linked: bool,
+    pub has_refresh_token: bool,
+}
+
+pub fn linked_devices() -> Vec<LinkedDevice> {
+    load_sessions()
+        .into_iter()
+        .map(|s| LinkedDevice {
+            device_id: s.device_id,
+            username: s.username,
+            is_linked: s.is_linked,
+            has_refresh_token: !s.refresh_token.is_empty(),
+        })
+        .collect()
+}
+
 fn is_username_taken(username: &str) -> bool {
     let sessions = load_sessions();
     sessions.iter().any(|s| s.username == username)
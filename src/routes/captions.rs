@@ -0,0 +1,536 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use lazy_static::lazy_static;
+use lru::LruCache;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::routes::video::fetch_player_metadata;
+
+/// Cues with the time they were fetched.
+type CachedCues = (Vec<CaptionCue>, u64);
+
+lazy_static! {
+    static ref CAPTION_CACHE: Arc<Mutex<LruCache<String, CachedCues>>> = Arc::new(
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(200).unwrap()))
+    );
+}
+
+const CAPTION_CACHE_DURATION: u64 = 3600;
+
+fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
+    if !config.server.main_url.is_empty() {
+        return config.server.main_url.clone();
+    }
+    let info = req.connection_info();
+    let scheme = info.scheme();
+    let host = info.host();
+    format!("{}://{}/", scheme, host.trim_end_matches('/'))
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct CaptionTrack {
+    pub language_code: String,
+    pub name: String,
+    /// `standard` for uploaded captions, `asr` for auto-generated ones.
+    pub kind: String,
+    pub vss_id: String,
+    pub is_translatable: bool,
+    /// `/get_captions.php` URL returning this track (add `format=` / `tlang=`).
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct TranslationLanguage {
+    pub language_code: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CaptionsListResponse {
+    pub video_id: String,
+    pub tracks: Vec<CaptionTrack>,
+    pub translation_languages: Vec<TranslationLanguage>,
+}
+
+/// One subtitle cue, times in milliseconds.
+#[derive(Debug, Clone)]
+pub(crate) struct CaptionCue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Caption track as listed in `captions.playerCaptionsTracklistRenderer`.
+struct PlayerCaptionTrack {
+    base_url: String,
+    language_code: String,
+    name: String,
+    is_asr: bool,
+    vss_id: String,
+    is_translatable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptionFormat {
+    Srt,
+    Vtt,
+    Ttml,
+    /// `<transcript><text start dur>` as returned by the old `/api/timedtext`.
+    Timedtext,
+}
+
+impl CaptionFormat {
+    const SUPPORTED: [&'static str; 4] = ["srt", "vtt", "ttml", "timedtext"];
+
+    fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" | "webvtt" => Some(Self::Vtt),
+            "ttml" | "dfxp" => Some(Self::Ttml),
+            "timedtext" | "xml" | "srv1" => Some(Self::Timedtext),
+            _ => None,
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::Srt => "application/x-subrip; charset=utf-8",
+            Self::Vtt => "text/vtt; charset=utf-8",
+            Self::Ttml => "application/ttml+xml; charset=utf-8",
+            Self::Timedtext => "text/xml; charset=utf-8",
+        }
+    }
+
+    fn render(&self, cues: &[CaptionCue], language: &str) -> String {
+        match self {
+            Self::Srt => cues_to_srt(cues),
+            Self::Vtt => cues_to_vtt(cues),
+            Self::Ttml => cues_to_ttml(cues, language),
+            Self::Timedtext => cues_to_timedtext(cues),
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn format_timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+pub(crate) fn cues_to_srt(cues: &[CaptionCue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start_ms, ','),
+            format_timestamp(cue.end_ms, ','),
+            cue.text
+        ));
+    }
+    out
+}
+
+fn cues_to_vtt(cues: &[CaptionCue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start_ms, '.'),
+            format_timestamp(cue.end_ms, '.'),
+            // Пустая строка внутри cue завершила бы его раньше времени
+            cue.text.replace("\n\n", "\n")
+        ));
+    }
+    out
+}
+
+fn cues_to_ttml(cues: &[CaptionCue], language: &str) -> String {
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<tt xmlns=\"http://www.w3.org/ns/ttml\" xml:lang=\"{}\"><body><div>\n",
+        xml_escape(language)
+    );
+    for cue in cues {
+        out.push_str(&format!(
+            "<p begin=\"{}\" end=\"{}\">{}</p>\n",
+            format_timestamp(cue.start_ms, '.'),
+            format_timestamp(cue.end_ms, '.'),
+            xml_escape(&cue.text).replace('\n', "<br/>")
+        ));
+    }
+    out.push_str("</div></body></tt>\n");
+    out
+}
+
+fn cues_to_timedtext(cues: &[CaptionCue]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\" ?><transcript>");
+    for cue in cues {
+        out.push_str(&format!(
+            "<text start=\"{:.3}\" dur=\"{:.3}\">{}</text>",
+            cue.start_ms as f64 / 1000.0,
+            cue.end_ms.saturating_sub(cue.start_ms) as f64 / 1000.0,
+            xml_escape(&cue.text)
+        ));
+    }
+    out.push_str("</transcript>");
+    out
+}
+
+fn text_from_runs(value: Option<&Value>) -> String {
+    let Some(v) = value else {
+        return String::new();
+    };
+    if let Some(s) = v.get("simpleText").and_then(|s| s.as_str()) {
+        return s.to_string();
+    }
+    v.get("runs")
+        .and_then(|r| r.as_array())
+        .map(|runs| {
+            runs.iter()
+                .filter_map(|r| r.get("text").and_then(|t| t.as_str()))
+                .collect::<String>()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_caption_tracks(player_response: &Value) -> (Vec<PlayerCaptionTrack>, Vec<TranslationLanguage>) {
+    let renderer = player_response
+        .get("captions")
+        .and_then(|c| c.get("playerCaptionsTracklistRenderer"));
+    let Some(renderer) = renderer else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let tracks = renderer
+        .get("captionTracks")
+        .and_then(|t| t.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| {
+                    let base_url = t.get("baseUrl").and_then(|u| u.as_str())?;
+                    let language_code = t.get("languageCode").and_then(|l| l.as_str())?;
+                    Some(PlayerCaptionTrack {
+                        base_url: base_url.to_string(),
+                        language_code: language_code.to_string(),
+                        name: text_from_runs(t.get("name")),
+                        is_asr: t.get("kind").and_then(|k| k.as_str()) == Some("asr"),
+                        vss_id: t.get("vssId").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                        is_translatable: t
+                            .get("isTranslatable")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let translation_languages = renderer
+        .get("translationLanguages")
+        .and_then(|t| t.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|l| {
+                    Some(TranslationLanguage {
+                        language_code: l.get("languageCode").and_then(|c| c.as_str())?.to_string(),
+                        name: text_from_runs(l.get("languageName")),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    (tracks, translation_languages)
+}
+
+/// Picks a track by language; uploaded captions win over auto-generated ones
+/// unless `asr` explicitly asks for one kind.
+fn select_track<'a>(
+    tracks: &'a [PlayerCaptionTrack],
+    lang: &str,
+    asr: Option<bool>,
+) -> Option<&'a PlayerCaptionTrack> {
+    let lang = lang.to_lowercase();
+    let matching: Vec<&PlayerCaptionTrack> = tracks
+        .iter()
+        .filter(|t| t.language_code.to_lowercase() == lang || t.vss_id.to_lowercase() == lang)
+        .filter(|t| asr.is_none_or(|a| t.is_asr == a))
+        .collect();
+    matching
+        .iter()
+        .find(|t| !t.is_asr)
+        .or_else(|| matching.first())
+        .copied()
+}
+
+/// Parses the `fmt=json3` timedtext format into cues.
+fn parse_json3_events(data: &Value) -> Vec<CaptionCue> {
+    let Some(events) = data.get("events").and_then(|e| e.as_array()) else {
+        return Vec::new();
+    };
+    events
+        .iter()
+        .filter_map(|event| {
+            let segs = event.get("segs").and_then(|s| s.as_array())?;
+            let text: String = segs
+                .iter()
+                .filter_map(|s| s.get("utf8").and_then(|u| u.as_str()))
+                .collect();
+            let text = text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            let start_ms = event.get("tStartMs").and_then(|t| t.as_u64()).unwrap_or(0);
+            let duration_ms = event.get("dDurationMs").and_then(|d| d.as_u64()).unwrap_or(0);
+            Some(CaptionCue {
+                start_ms,
+                end_ms: start_ms + duration_ms,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+async fn download_track_cues(
+    track: &PlayerCaptionTrack,
+    tlang: Option<&str>,
+    config: &crate::config::Config,
+) -> Result<Vec<CaptionCue>, String> {
+    let mut url = format!("{}&fmt=json3", track.base_url);
+    if let Some(t) = tlang {
+        url.push_str(&format!("&tlang={}", urlencoding::encode(t)));
+    }
+    let resp = Client::new()
+        .get(&url)
+        .header("User-Agent", config.get_innertube_user_agent())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("timedtext HTTP {}", resp.status()));
+    }
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    if body.trim().is_empty() {
+        return Err("YouTube returned an empty caption track".to_string());
+    }
+    let data: Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    Ok(parse_json3_events(&data))
+}
+
+/// Cues of a caption track (optionally machine-translated), cached for an hour.
+/// `asr`: `Some(true)` for auto-generated only, `Some(false)` for uploaded only.
+pub(crate) async fn fetch_caption_cues(
+    video_id: &str,
+    lang: &str,
+    asr: Option<bool>,
+    tlang: Option<&str>,
+    config: &crate::config::Config,
+) -> Result<Vec<CaptionCue>, String> {
+    let cache_key = format!(
+        "{}:{}:{}:{}",
+        video_id,
+        lang.to_lowercase(),
+        asr.map(|a| if a { "asr" } else { "standard" }).unwrap_or("any"),
+        tlang.unwrap_or("")
+    );
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    {
+        let mut cache = CAPTION_CACHE.lock().await;
+        if let Some((cues, timestamp)) = cache.get(&cache_key) {
+            if now - *timestamp < CAPTION_CACHE_DURATION {
+                return Ok(cues.clone());
+            }
+        }
+    }
+
+    let player_response = fetch_player_metadata(video_id, config).await?;
+    let (tracks, translation_languages) = parse_caption_tracks(&player_response);
+    let track = select_track(&tracks, lang, asr)
+        .ok_or_else(|| format!("No caption track for language '{}'", lang))?;
+    if let Some(t) = tlang {
+        if !track.is_translatable {
+            return Err(format!("Caption track '{}' cannot be translated", lang));
+        }
+        if !translation_languages.iter().any(|l| l.language_code.eq_ignore_ascii_case(t)) {
+            return Err(format!("Translation to '{}' is not available", t));
+        }
+    }
+
+    let cues = download_track_cues(track, tlang, config).await?;
+    CAPTION_CACHE.lock().await.put(cache_key, (cues.clone(), now));
+    Ok(cues)
+}
+
+/// `<transcript_list>` for the old player's `ttsurl` (`type=list`).
+fn legacy_track_list(
+    video_id: &str,
+    tracks: &[PlayerCaptionTrack],
+    translation_languages: &[TranslationLanguage],
+    with_targets: bool,
+) -> String {
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\" ?><transcript_list docid=\"{}\">",
+        xml_escape(video_id)
+    );
+    for (i, t) in tracks.iter().enumerate() {
+        out.push_str(&format!(
+            "<track id=\"{}\" name=\"\" lang_code=\"{}\" lang_original=\"{}\" lang_translated=\"{}\"{}{}/>",
+            i,
+            xml_escape(&t.language_code),
+            xml_escape(&t.name),
+            xml_escape(&t.name),
+            if t.is_asr { " kind=\"asr\"" } else { "" },
+            if i == 0 { " lang_default=\"true\"" } else { "" }
+        ));
+    }
+    if with_targets {
+        for (i, l) in translation_languages.iter().enumerate() {
+            out.push_str(&format!(
+                "<target id=\"{}\" lang_code=\"{}\" lang_original=\"{}\" lang_translated=\"{}\"/>",
+                i,
+                xml_escape(&l.language_code),
+                xml_escape(&l.name),
+                xml_escape(&l.name)
+            ));
+        }
+    }
+    out.push_str("</transcript_list>");
+    out
+}
+
+#[utoipa::path(
+    get,
+    path = "/get_captions.php",
+    params(
+        ("video_id" = String, Query, description = "YouTube video ID"),
+        ("lang" = Option<String>, Query, description = "Language code (or vss_id) of the track to return; without it the available tracks are listed"),
+        ("kind" = Option<String>, Query, description = "asr for auto-generated captions, standard for uploaded ones"),
+        ("tlang" = Option<String>, Query, description = "Translate the track to this language (see translation_languages)"),
+        ("format" = Option<String>, Query, description = "Track format: srt, vtt, ttml or timedtext (old YouTube XML, default)"),
+        ("type" = Option<String>, Query, description = "list: track list as old timedtext <transcript_list> XML (tlangs=1 adds translation targets)")
+    ),
+    responses(
+        (status = 200, description = "Caption track list or caption track", body = CaptionsListResponse),
+        (status = 400, description = "Missing video_id or unsupported format"),
+        (status = 404, description = "Caption track not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_captions(req: HttpRequest, data: web::Data<crate::AppState>) -> impl Responder {
+    let config = &data.config;
+    let base = base_url(&req, config);
+    let base_trimmed = base.trim_end_matches('/');
+
+    let mut query_params: HashMap<String, String> = HashMap::new();
+    for pair in req.query_string().split('&') {
+        let mut parts = pair.split('=');
+        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            query_params.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let video_id = match query_params.get("video_id").or_else(|| query_params.get("v")) {
+        Some(id) => id.clone(),
+        None => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "video_id parameter is required"
+            }));
+        }
+    };
+
+    let format = match query_params.get("format").or_else(|| query_params.get("fmt")) {
+        Some(f) => match CaptionFormat::from_param(f) {
+            Some(format) => format,
+            None => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Unsupported format",
+                    "details": format!("Format '{}' is not supported. Available: {}", f, CaptionFormat::SUPPORTED.join(", ")),
+                    "supported_formats": CaptionFormat::SUPPORTED
+                }));
+            }
+        },
+        None => CaptionFormat::Timedtext,
+    };
+    let asr = query_params.get("kind").map(|k| k == "asr");
+    let tlang = query_params.get("tlang").filter(|t| !t.is_empty()).map(|t| t.as_str());
+
+    if let Some(lang) = query_params.get("lang").filter(|l| !l.is_empty()) {
+        return match fetch_caption_cues(&video_id, lang, asr, tlang, config).await {
+            Ok(cues) => HttpResponse::Ok()
+                .content_type(format.content_type())
+                .insert_header(("Cache-Control", "public, max-age=3600"))
+                .body(format.render(&cues, tlang.unwrap_or(lang))),
+            Err(e) if e.starts_with("No caption track") => {
+                HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Caption track not found",
+                    "details": e
+                }))
+            }
+            Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch caption track",
+                "details": e
+            })),
+        };
+    }
+
+    let player_response = match fetch_player_metadata(&video_id, config).await {
+        Ok(data) => data,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch player response",
+                "details": e
+            }));
+        }
+    };
+    let (tracks, translation_languages) = parse_caption_tracks(&player_response);
+
+    if query_params.get("type").map(|t| t == "list").unwrap_or(false) {
+        let with_targets = query_params.get("tlangs").map(|t| t == "1").unwrap_or(false);
+        return HttpResponse::Ok()
+            .content_type("text/xml; charset=utf-8")
+            .body(legacy_track_list(&video_id, &tracks, &translation_languages, with_targets));
+    }
+
+    let tracks = tracks
+        .iter()
+        .map(|t| CaptionTrack {
+            language_code: t.language_code.clone(),
+            name: t.name.clone(),
+            kind: if t.is_asr { "asr" } else { "standard" }.to_string(),
+            vss_id: t.vss_id.clone(),
+            is_translatable: t.is_translatable,
+            url: format!(
+                "{}/get_captions.php?video_id={}&lang={}&kind={}",
+                base_trimmed,
+                video_id,
+                urlencoding::encode(&t.language_code),
+                if t.is_asr { "asr" } else { "standard" }
+            ),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(CaptionsListResponse {
+        video_id,
+        tracks,
+        translation_languages,
+    })
+}
@@ -1,10 +1,28 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use lazy_static::lazy_static;
+use lru::LruCache;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use tokio::sync::Mutex;
 use urlencoding;
 use utoipa::ToSchema;
 
+use crate::routes::search::{
+    best_thumbnail, decode_label, find_search_continuation, find_value, non_empty,
+    parse_channel_renderer, parse_lockup_view_model, parse_playlist_renderer, parse_reel_item_renderer,
+    parse_shorts_lockup, simplify_text, ChannelResult, PlaylistResult, SearchItem, ShortResult,
+};
+
+lazy_static! {
+    /// Channel names seen on first tab pages, for the `author` of continuation pages.
+    static ref CHANNEL_TITLES: Mutex<LruCache<String, String>> =
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(500).unwrap()));
+    /// Channel headers from first Videos pages, so upload continuations skip that browse.
+    static ref CHANNEL_HEADERS: Mutex<LruCache<String, ChannelInfo>> =
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(500).unwrap()));
+}
+
 fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
     if !config.server.main_url.is_empty() {
         return config.server.main_url.clone();
@@ -16,31 +34,10 @@ fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
 }
 
 fn parse_number(text: &str) -> String {
-    let lower_text = text.trim().to_lowercase();
-    let mut multiplier = 1.0;
-    let clean_text = if lower_text.contains('k') {
-        multiplier = 1000.0;
-        lower_text.replace('k', "")
-    } else if lower_text.contains('m') {
-        multiplier = 1000000.0;
-        lower_text.replace('m', "")
-    } else if lower_text.contains('b') {
-        multiplier = 1000000000.0;
-        lower_text.replace('b', "")
-    } else {
-        lower_text
-    };
-    
-    // Extract digits and decimal points
-    let num_str: String = clean_text.chars().filter(|c| c.is_ascii_digit() || *c == '.').collect();
-    
-    match num_str.parse::<f64>() {
-        Ok(num) => ((num * multiplier) as u64).to_string(),
-        Err(_) => "0".to_string(),
-    }
+    crate::locale::count_string(text, "0")
 }
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
 pub struct ChannelInfo {
     pub title: String,
     pub description: String,
@@ -66,6 +63,241 @@ pub struct ChannelVideo {
 pub struct ChannelVideosResponse {
     pub channel_info: ChannelInfo,
     pub videos: Vec<ChannelVideo>,
+    /// Передаётся как `continuation` (с тем же `sort`) для следующих видео; нет — список закончился.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+/// Порядок загрузок на вкладке «Видео» (чипы Latest / Popular / Oldest).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChannelSort {
+    Newest,
+    Popular,
+    Oldest,
+}
+
+impl ChannelSort {
+    fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "newest" | "latest" => Some(Self::Newest),
+            "popular" => Some(Self::Popular),
+            "oldest" => Some(Self::Oldest),
+            _ => None,
+        }
+    }
+
+    /// Чипы всегда идут в этом порядке, а их подписи зависят от языка.
+    fn chip_index(self) -> usize {
+        match self {
+            Self::Newest => 0,
+            Self::Popular => 1,
+            Self::Oldest => 2,
+        }
+    }
+}
+
+/// Параметры выборки загрузок: `count`, `sort`, `continuation`, `all`.
+struct UploadsQuery {
+    count: usize,
+    sort: ChannelSort,
+    continuation: Option<String>,
+    all: bool,
+}
+
+impl UploadsQuery {
+    /// Err — описание ошибки для ответа 400.
+    fn from_params(query_params: &HashMap<String, String>, config: &crate::config::Config) -> Result<Self, &'static str> {
+        let count: i32 = query_params
+            .get("count")
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(config.video.default_count as i32);
+        let sort = match query_params.get("sort").filter(|s| !s.is_empty()) {
+            Some(value) => match ChannelSort::from_param(value) {
+                Some(sort) => sort,
+                None => return Err("Expected one of: newest, popular, oldest"),
+            },
+            None => ChannelSort::Newest,
+        };
+        let continuation = query_params
+            .get("continuation")
+            .filter(|c| !c.is_empty())
+            .map(|c| urlencoding::decode(c).map(|d| d.to_string()).unwrap_or_else(|_| c.clone()));
+        let all = matches!(query_params.get("all").map(|a| a.as_str()), Some("1") | Some("true"));
+        // count=0 дал бы курсор, указывающий на ту же страницу, — бесконечный цикл
+        Ok(Self {
+            count: count.max(1) as usize,
+            sort,
+            continuation,
+            all,
+        })
+    }
+}
+
+/// Курсор `next`: токен страницы YouTube и сколько видео с неё уже отдано.
+/// Если страница отдана целиком, курсор — сам токен; `N:` без токена — первая страница.
+fn encode_cursor(token: Option<&str>, skip: usize) -> String {
+    match (token, skip) {
+        (Some(token), 0) => token.to_string(),
+        (token, skip) => format!("{}:{}", skip, token.unwrap_or("")),
+    }
+}
+
+fn decode_cursor(cursor: &str) -> (Option<String>, usize) {
+    // В base64-токенах YouTube двоеточий не бывает
+    if let Some((skip, token)) = cursor.split_once(':') {
+        if let Ok(skip) = skip.parse() {
+            return (non_empty(token.to_string()), skip);
+        }
+    }
+    (Some(cursor.to_string()), 0)
+}
+
+/// Вкладки канала, доступные через `/get_channel_tab.php`.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelTab {
+    Videos,
+    Shorts,
+    Streams,
+    Playlists,
+    Community,
+    Channels,
+}
+
+impl ChannelTab {
+    pub const ALL: [ChannelTab; 6] = [
+        Self::Videos,
+        Self::Shorts,
+        Self::Streams,
+        Self::Playlists,
+        Self::Community,
+        Self::Channels,
+    ];
+
+    /// `params` запроса /browse, открывающий вкладку.
+    fn params(self) -> &'static str {
+        match self {
+            Self::Videos => "EgZ2aWRlb3PyBgQKAjoA",
+            Self::Shorts => "EgZzaG9ydHPyBgUKA5oBAA==",
+            Self::Streams => "EgdzdHJlYW1z8gYECgJ6AA==",
+            Self::Playlists => "EglwbGF5bGlzdHPyBgQKAkIA",
+            Self::Community => "Egljb21tdW5pdHnyBgQKAkoA",
+            Self::Channels => "EghjaGFubmVsc_IGBAoCUgA=",
+        }
+    }
+
+    /// Окончания URL вкладки в tabRenderer (community переименовали в posts).
+    fn url_suffixes(self) -> &'static [&'static str] {
+        match self {
+            Self::Videos => &["/videos"],
+            Self::Shorts => &["/shorts"],
+            Self::Streams => &["/streams"],
+            Self::Playlists => &["/playlists"],
+            Self::Community => &["/community", "/posts"],
+            Self::Channels => &["/channels"],
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Videos => "videos",
+            Self::Shorts => "shorts",
+            Self::Streams => "streams",
+            Self::Playlists => "playlists",
+            Self::Community => "community",
+            Self::Channels => "channels",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Videos => "Videos",
+            Self::Shorts => "Shorts",
+            Self::Streams => "Live",
+            Self::Playlists => "Playlists",
+            Self::Community => "Community",
+            Self::Channels => "Channels",
+        }
+    }
+
+    pub fn from_param(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|t| t.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+const ABOUT_PARAMS: &str = "EgVhYm91dPIGBAoCEgA=";
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CommunityPost {
+    pub post_id: String,
+    pub author: String,
+    pub author_thumbnail: String,
+    pub content: String,
+    pub published: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub likes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<String>,
+    /// Первая картинка поста (через /channel_icon).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Видео, прикреплённое к посту.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_id: Option<String>,
+}
+
+/// Элемент вкладки канала; `type` указывает вид, как у результатов поиска.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelTabItem {
+    Video(ChannelVideo),
+    Short(ShortResult),
+    Playlist(PlaylistResult),
+    Channel(ChannelResult),
+    Post(CommunityPost),
+}
+
+/// Страница вкладки канала. `continuation` передаётся обратно для следующей страницы.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChannelTabPage {
+    pub channel_id: String,
+    pub tab: ChannelTab,
+    /// Только на первой странице.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_info: Option<ChannelInfo>,
+    pub items: Vec<ChannelTabItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChannelLink {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChannelAbout {
+    pub channel_id: String,
+    pub title: String,
+    pub description: String,
+    pub thumbnail: String,
+    pub banner: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    pub subscriber_count: String,
+    pub video_count: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_views: Option<String>,
+    /// Как показывает YouTube, например "Joined Mar 5, 2011".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub joined_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    pub links: Vec<ChannelLink>,
 }
 
 #[utoipa::path(
@@ -73,11 +305,16 @@ pub struct ChannelVideosResponse {
     path = "/get_author_videos.php",
     params(
         ("author" = String, Query, description = "Channel username/search query"),
-        ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)")
+        ("count" = Option<i32>, Query, description = "Number of videos to return (at least 1), following continuations as needed (default: 50)"),
+        ("sort" = Option<String>, Query, description = "newest (default), popular or oldest"),
+        ("continuation" = Option<String>, Query, description = "`next` from the previous response; pass the same sort"),
+        ("all" = Option<bool>, Query, description = "Ignore count and collect the whole upload list (up to video.channel_max_pages pages)"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Videos for the author", body = ChannelVideosResponse),
-        (status = 400, description = "Missing author parameter")
+        (status = 400, description = "Missing author parameter or invalid sort")
     )
 )]
 pub async fn get_author_videos(
@@ -103,10 +340,12 @@ pub async fn get_author_videos(
     let decoded_once = urlencoding::decode(&author_raw).unwrap_or(std::borrow::Cow::Borrowed(&author_raw)).to_string();
     let author = urlencoding::decode(&decoded_once).unwrap_or(std::borrow::Cow::Borrowed(&decoded_once)).to_string();
 
-    let count: i32 = query_params
-        .get("count")
-        .and_then(|c| c.parse().ok())
-        .unwrap_or(config.video.default_count as i32);
+    let uploads = match UploadsQuery::from_params(&query_params, config) {
+        Ok(q) => q,
+        Err(details) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid sort parameter", "details": details}))
+        }
+    };
 
     let innertube_key = match config.get_innertube_key() {
         Some(key) => key,
@@ -114,11 +353,12 @@ pub async fn get_author_videos(
     };
 
     let client = Client::new();
+    let visitor = crate::visitor_data::visitor_data(config, crate::visitor_data::session_key(&req).as_deref()).await;
 
     let channel_id = if author.starts_with("UC") && author.len() == 24 {
         Some(author.clone())
     } else {
-        resolve_handle_to_channel_id(&author, &client, &innertube_key, &base).await
+        resolve_handle_to_channel_id(&author, &client, &innertube_key, &base, visitor.as_deref()).await
     };
 
     let channel_id = match channel_id {
@@ -126,7 +366,8 @@ pub async fn get_author_videos(
         None => return HttpResponse::BadRequest().json(serde_json::json!({"error": "Channel not found"})),
     };
 
-    get_author_videos_by_id_internal(&channel_id, count, config, &base).await
+    let locale = crate::locale::Locale::from_request(&req, config);
+    get_author_videos_by_id_internal(&channel_id, &uploads, config, &base, visitor.as_deref(), &locale).await
 }
 
 #[utoipa::path(
@@ -134,11 +375,16 @@ pub async fn get_author_videos(
     path = "/get_author_videos_by_id.php",
     params(
         ("channel_id" = String, Query, description = "YouTube channel ID"),
-        ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)")
+        ("count" = Option<i32>, Query, description = "Number of videos to return (at least 1), following continuations as needed (default: 50)"),
+        ("sort" = Option<String>, Query, description = "newest (default), popular or oldest"),
+        ("continuation" = Option<String>, Query, description = "`next` from the previous response; pass the same sort"),
+        ("all" = Option<bool>, Query, description = "Ignore count and collect the whole upload list (up to video.channel_max_pages pages)"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Videos for channel", body = ChannelVideosResponse),
-        (status = 400, description = "Missing channel_id parameter")
+        (status = 400, description = "Missing channel_id parameter or invalid sort")
     )
 )]
 pub async fn get_author_videos_by_id(
@@ -160,32 +406,46 @@ pub async fn get_author_videos_by_id(
         None => return HttpResponse::BadRequest().json(serde_json::json!({"error": "Channel ID parameter is required"})),
     };
 
-    let count: i32 = query_params
-        .get("count")
-        .and_then(|c| c.parse().ok())
-        .unwrap_or(config.video.default_count as i32);
+    let uploads = match UploadsQuery::from_params(&query_params, config) {
+        Ok(q) => q,
+        Err(details) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid sort parameter", "details": details}))
+        }
+    };
 
-    get_author_videos_by_id_internal(&channel_id, count, config, &base).await
+    let visitor = crate::visitor_data::visitor_data(config, crate::visitor_data::session_key(&req).as_deref()).await;
+    let locale = crate::locale::Locale::from_request(&req, config);
+    get_author_videos_by_id_internal(&channel_id, &uploads, config, &base, visitor.as_deref(), &locale).await
 }
 
 async fn get_author_videos_by_id_internal(
     channel_id: &str,
-    count: i32,
+    uploads: &UploadsQuery,
     config: &crate::config::Config,
     base: &str,
+    visitor: Option<&str>,
+    locale: &crate::locale::Locale,
 ) -> HttpResponse {
     let innertube_key = match config.get_innertube_key() {
         Some(key) => key,
         None => return HttpResponse::InternalServerError().json(serde_json::json!({"error": "Missing innertube_key"})),
     };
 
-    let (videos, channel_info) = fetch_channel_videos_inner_tube(channel_id, count, &innertube_key, base).await;
+    let max_pages = config.video.channel_max_pages as usize;
+    let (videos, channel_info, next) =
+        fetch_channel_videos_inner_tube(channel_id, uploads, max_pages, &innertube_key, base, visitor, locale).await;
 
-    let response = ChannelVideosResponse { channel_info, videos };
+    let response = ChannelVideosResponse { channel_info, videos, next };
     HttpResponse::Ok().json(response)
 }
 
-async fn resolve_handle_to_channel_id(handle: &str, client: &Client, innertube_key: &str, _base: &str) -> Option<String> {
+async fn resolve_handle_to_channel_id(
+    handle: &str,
+    client: &Client,
+    innertube_key: &str,
+    _base: &str,
+    visitor: Option<&str>,
+) -> Option<String> {
     let clean_handle = handle.trim().trim_start_matches('@');
     
     // ИСПРАВЛЕНИЕ: Был пустой URL, вставил правильный эндопоинт resolve_url
@@ -201,13 +461,13 @@ async fn resolve_handle_to_channel_id(handle: &str, client: &Client, innertube_k
     });
     
     // В InnerTube не нужно энкодить URL здесь, он справляется с Unicode сам
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "context": context,
         "url": format!("https://www.youtube.com/@{}", clean_handle),
     });
+    crate::visitor_data::attach(&mut payload, visitor);
     
-    match client
-        .post(&url)
+    match crate::visitor_data::with_header(client.post(&url), visitor)
         .header("Content-Type", "application/json")
         .json(&payload)
         .send()
@@ -229,83 +489,230 @@ async fn resolve_handle_to_channel_id(handle: &str, client: &Client, innertube_k
 
 async fn fetch_channel_videos_inner_tube(
     channel_id: &str,
-    count: i32,
+    uploads: &UploadsQuery,
+    max_pages: usize,
     innertube_key: &str,
     base: &str,
-) -> (Vec<ChannelVideo>, ChannelInfo) {
+    visitor: Option<&str>,
+    locale: &crate::locale::Locale,
+) -> (Vec<ChannelVideo>, ChannelInfo, Option<String>) {
     let client = Client::new();
-    
-    let url = format!("https://www.youtube.com/youtubei/v1/browse?key={}", innertube_key);
-    
-    let context = serde_json::json!({
-        "client": {
-            "clientName": "WEB",
-            "clientVersion": "2.20260220.00.00",
-            "hl": "en",
-            "gl": "US"
+    let base_trimmed = base.trim_end_matches('/');
+    let unknown = || ChannelInfo {
+        title: "Unknown".to_string(), description: "".to_string(), thumbnail: "".to_string(), banner: "".to_string(), subscriber_count: "0".to_string(), video_count: "0".to_string(),
+    };
+
+    let (mut page_token, mut skip) = match &uploads.continuation {
+        Some(cursor) => decode_cursor(cursor),
+        None => (None, 0),
+    };
+
+    // Шапка канала и первая страница вкладки «Видео» приходят одним ответом.
+    // Для продолжения он нужен только ради шапки: берём её из кэша, а без него
+    // запрашиваем параллельно со страницей.
+    let cached_info = match &page_token {
+        Some(_) => CHANNEL_HEADERS.lock().await.get(channel_id).cloned(),
+        None => None,
+    };
+    let first_body = serde_json::json!({ "browseId": channel_id, "params": ChannelTab::Videos.params() });
+    let continuation_body = |token: &str| serde_json::json!({ "continuation": token });
+    let (first, page) = match (&page_token, &cached_info) {
+        (Some(token), Some(_)) => (
+            None,
+            Some(channel_browse(&client, continuation_body(token), innertube_key, visitor, locale).await),
+        ),
+        (Some(token), None) => {
+            let (first, page) = tokio::join!(
+                channel_browse(&client, first_body, innertube_key, visitor, locale),
+                channel_browse(&client, continuation_body(token), innertube_key, visitor, locale)
+            );
+            (Some(first), Some(page))
         }
-    });
-    
-    let payload = serde_json::json!({
-        "context": context,
-        "browseId": channel_id,
-    });
-    
-    let response = match client.post(&url).header("Content-Type", "application/json").json(&payload).send().await {
-        Ok(resp) => resp,
-        Err(_) => return (Vec::new(), ChannelInfo {
-            title: "Unknown".to_string(), description: "".to_string(), thumbnail: "".to_string(), banner: "".to_string(), subscriber_count: "0".to_string(), video_count: "0".to_string(),
-        }),
+        (None, _) => (Some(channel_browse(&client, first_body, innertube_key, visitor, locale).await), None),
     };
-    
-    let data: serde_json::Value = match response.json().await {
-        Ok(json) => json,
-        Err(_) => return (Vec::new(), ChannelInfo {
-            title: "Unknown".to_string(), description: "".to_string(), thumbnail: "".to_string(), banner: "".to_string(), subscriber_count: "0".to_string(), video_count: "0".to_string(),
-        }),
+    let data = match first {
+        Some(Ok(json)) => Some(json),
+        Some(Err(e)) => {
+            crate::log::info!("Channel {} browse failed: {}", channel_id, e);
+            return (Vec::new(), unknown(), None);
+        }
+        None => None,
     };
-    
-    let channel_info = extract_channel_info(&data, base, channel_id).await;
-    
-    let tabs_array: Vec<serde_json::Value> = data.pointer("/contents/twoColumnBrowseResultsRenderer/tabs")
-        .and_then(|t| t.as_array())
-        .cloned()
-        .unwrap_or_default();
-    
-    let mut videos_content = None;
-    for tab in &tabs_array {
-        if let Some(tr) = tab.get("tabRenderer") {
-            if let Some(title) = tr.get("title").and_then(|t| t.as_str()) {
-                if title == "Videos" {
-                    videos_content = tr.get("content");
-                    break;
+    let channel_info = match &data {
+        Some(data) => {
+            let info = extract_channel_info(data, base, channel_id).await;
+            if data.pointer("/metadata/channelMetadataRenderer").is_some() {
+                CHANNEL_HEADERS.lock().await.put(channel_id.to_string(), info.clone());
+            }
+            info
+        }
+        // Без первой страницы идём только при шапке из кэша
+        None => cached_info.unwrap_or_else(unknown),
+    };
+
+    // Вкладки «Видео» может не быть — тогда то, что YouTube открыл (главная)
+    let videos_content = data.as_ref().and_then(|data| find_tab_content(data, ChannelTab::Videos).or_else(|| {
+        data.pointer("/contents/twoColumnBrowseResultsRenderer/tabs")
+            .and_then(|t| t.as_array())?
+            .iter()
+            .filter_map(|t| t.get("tabRenderer"))
+            .find(|tr| tr.get("selected").and_then(|s| s.as_bool()).unwrap_or(false))
+            .and_then(|tr| tr.get("content"))
+    }));
+
+    // Текущая страница: видео и токен следующей
+    let mut current = match page {
+        Some(Ok(json)) => Some(json),
+        Some(Err(e)) => {
+            crate::log::info!("Channel {} continuation failed: {}", channel_id, e);
+            let cursor = uploads.continuation.clone();
+            return (Vec::new(), channel_info, cursor);
+        }
+        None if uploads.sort != ChannelSort::Newest => {
+            // Другой порядок — это continuation из чипа над сеткой
+            match videos_content.and_then(|c| find_sort_chip_token(c, uploads.sort)) {
+                Some(token) => match channel_browse(&client, serde_json::json!({ "continuation": token }), innertube_key, visitor, locale).await {
+                    Ok(json) => {
+                        page_token = Some(token);
+                        Some(json)
+                    }
+                    Err(e) => {
+                        crate::log::info!("Channel {} sorted page failed: {}", channel_id, e);
+                        return (Vec::new(), channel_info, None);
+                    }
+                },
+                None => {
+                    crate::log::info!("Channel {}: no sort chips, falling back to newest", channel_id);
+                    None
                 }
             }
         }
-    }
-    
-    if videos_content.is_none() {
-        for tab in &tabs_array {
-            if let Some(tr) = tab.get("tabRenderer") {
-                if tr.get("selected").and_then(|s| s.as_bool()).unwrap_or(false) {
-                    videos_content = tr.get("content");
-                    break;
+        None => None,
+    };
+
+    let limit = if uploads.all { usize::MAX } else { uploads.count };
+    let mut videos: Vec<ChannelVideo> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pages = 0;
+    let next = loop {
+        let mut page_videos = Vec::new();
+        let mut page_seen = HashSet::new();
+        let next_token = match &current {
+            Some(json) => {
+                let mut token = None;
+                for actions in continuation_items(json) {
+                    extract_videos_recursively(actions, &mut page_videos, &channel_info, base_trimmed, &mut page_seen);
+                    token = token.or_else(|| find_search_continuation(actions));
                 }
+                token
+            }
+            None => videos_content.and_then(|content| {
+                extract_videos_recursively(content, &mut page_videos, &channel_info, base_trimmed, &mut page_seen);
+                find_search_continuation(content)
+            }),
+        };
+        pages += 1;
+
+        // Повторы между страницами отбрасываем, но skip считаем по самой странице,
+        // чтобы при повторном запросе той же страницы он указывал туда же
+        let room = limit - videos.len();
+        let remaining = page_videos.len().saturating_sub(skip);
+        for video in page_videos.into_iter().skip(skip).take(room) {
+            if seen.insert(video.video_id.clone()) {
+                videos.push(video);
+            }
+        }
+        if remaining > room {
+            break Some(encode_cursor(page_token.as_deref(), skip + room));
+        }
+
+        let token = match next_token {
+            Some(token) => token,
+            None => break None,
+        };
+        if videos.len() >= limit || (max_pages > 0 && pages >= max_pages) {
+            break Some(token);
+        }
+        match channel_browse(&client, serde_json::json!({ "continuation": token }), innertube_key, visitor, locale).await {
+            Ok(json) => current = Some(json),
+            Err(e) => {
+                crate::log::info!("Channel {} continuation failed: {}", channel_id, e);
+                break Some(token);
+            }
+        }
+        page_token = Some(token);
+        skip = 0;
+    };
+
+    (videos, channel_info, next)
+}
+
+/// Токен чипа сортировки (chipCloudChipRenderer или новый chipViewModel).
+fn find_sort_chip_token(content: &serde_json::Value, sort: ChannelSort) -> Option<String> {
+    fn collect<'a>(obj: &'a serde_json::Value, out: &mut Vec<&'a serde_json::Value>) {
+        if let Some(map) = obj.as_object() {
+            if let Some(chip) = map.get("chipCloudChipRenderer").or_else(|| map.get("chipViewModel")) {
+                out.push(chip);
+            } else {
+                for value in map.values() {
+                    collect(value, out);
+                }
+            }
+        } else if let Some(arr) = obj.as_array() {
+            for item in arr {
+                collect(item, out);
             }
         }
     }
-    
-    let mut videos = Vec::new();
-    let base_trimmed = base.trim_end_matches('/');
-    
-    // --- ИСПОЛЬЗУЕМ УМНЫЙ РЕКУРСИВНЫЙ ПАРСЕР ---
-    if let Some(content) = videos_content {
-        let mut seen = HashSet::new();
-        extract_videos_recursively(content, &mut videos, &channel_info, base_trimmed, &mut seen);
-    }
-    
-    videos.truncate(count as usize);
-    (videos, channel_info)
+    let mut chips = Vec::new();
+    collect(content, &mut chips);
+    chips
+        .get(sort.chip_index())
+        .and_then(|chip| find_value(chip, "continuationCommand"))
+        .and_then(|c| c.get("token"))
+        .and_then(|t| t.as_str())
+        .map(|t| t.to_string())
+}
+
+/// videoRenderer / gridVideoRenderer со страницы канала.
+fn parse_channel_video(vr: &serde_json::Value, channel_info: &ChannelInfo, base_trimmed: &str) -> Option<ChannelVideo> {
+    let video_id = vr.get("videoId").and_then(|id| id.as_str())?;
+
+    let title = vr.pointer("/title/simpleText")
+        .or_else(|| vr.pointer("/title/runs/0/text"))
+        .and_then(|t| t.as_str())
+        .unwrap_or("No title")
+        .to_string();
+        
+    let views_raw = vr.pointer("/viewCountText/simpleText")
+        .or_else(|| vr.pointer("/shortViewCountText/simpleText"))
+        .or_else(|| vr.pointer("/viewCountText/runs/0/text"))
+        .and_then(|t| t.as_str())
+        .unwrap_or("0");
+        
+    let duration = vr.pointer("/lengthText/simpleText")
+        .or_else(|| vr.pointer("/lengthText/runs/0/text"))
+        .or_else(|| vr.pointer("/thumbnailOverlays/0/thumbnailOverlayTimeStatusRenderer/text/simpleText"))
+        .and_then(|t| t.as_str())
+        .unwrap_or("0:00")
+        .to_string();
+        
+    let published_at = vr.pointer("/publishedTimeText/simpleText")
+        .or_else(|| vr.pointer("/publishedTimeText/runs/0/text"))
+        .and_then(|t| t.as_str())
+        .map(crate::locale::normalize_relative_time)
+        .unwrap_or_default();
+
+    Some(ChannelVideo {
+        title,
+        author: channel_info.title.clone(),
+        video_id: video_id.to_string(),
+        thumbnail: format!("{}/thumbnail/{}", base_trimmed, video_id),
+        channel_thumbnail: channel_info.thumbnail.clone(),
+        views: parse_number(views_raw),
+        published_at,
+        duration,
+    })
 }
 
 // --- УМНЫЙ РЕКУРСИВНЫЙ ПАРСЕР (заменяет старые функции) ---
@@ -319,45 +726,9 @@ fn extract_videos_recursively(
     if let Some(map) = obj.as_object() {
         // Ищем видео (поддерживает и старый, и новый дизайн YouTube)
         if let Some(vr) = map.get("videoRenderer").or_else(|| map.get("gridVideoRenderer")) {
-            if let Some(video_id) = vr.get("videoId").and_then(|id| id.as_str()) {
-                if !seen.contains(video_id) {
-                    seen.insert(video_id.to_string());
-                    
-                    let title = vr.pointer("/title/simpleText")
-                        .or_else(|| vr.pointer("/title/runs/0/text"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("No title")
-                        .to_string();
-                        
-                    let views_raw = vr.pointer("/viewCountText/simpleText")
-                        .or_else(|| vr.pointer("/shortViewCountText/simpleText"))
-                        .or_else(|| vr.pointer("/viewCountText/runs/0/text"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("0");
-                        
-                    let duration = vr.pointer("/lengthText/simpleText")
-                        .or_else(|| vr.pointer("/lengthText/runs/0/text"))
-                        .or_else(|| vr.pointer("/thumbnailOverlays/0/thumbnailOverlayTimeStatusRenderer/text/simpleText"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("0:00")
-                        .to_string();
-                        
-                    let published_at = vr.pointer("/publishedTimeText/simpleText")
-                        .or_else(|| vr.pointer("/publishedTimeText/runs/0/text"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("")
-                        .to_string();
-
-                    videos.push(ChannelVideo {
-                        title,
-                        author: channel_info.title.clone(),
-                        video_id: video_id.to_string(),
-                        thumbnail: format!("{}/thumbnail/{}", base_trimmed, video_id),
-                        channel_thumbnail: channel_info.thumbnail.clone(),
-                        views: parse_number(views_raw),
-                        published_at,
-                        duration,
-                    });
+            if let Some(video) = parse_channel_video(vr, channel_info, base_trimmed) {
+                if seen.insert(video.video_id.clone()) {
+                    videos.push(video);
                 }
             }
         } 
@@ -441,6 +812,546 @@ async fn extract_channel_info(data: &serde_json::Value, base: &str, channel_id:
     ChannelInfo { title, description, thumbnail: channel_icon, banner, subscriber_count, video_count }
 }
 
+/// Канал из `channel_id` или `author` (имя, @handle или UC...).
+async fn channel_id_from_query(
+    query_params: &HashMap<String, String>,
+    client: &Client,
+    innertube_key: &str,
+    base: &str,
+    visitor: Option<&str>,
+) -> Option<String> {
+    if let Some(id) = query_params.get("channel_id").filter(|id| !id.is_empty()) {
+        return Some(urlencoding::decode(id).map(|d| d.to_string()).unwrap_or_else(|_| id.clone()));
+    }
+    let author_raw = query_params.get("author").filter(|a| !a.is_empty())?;
+    let decoded_once = urlencoding::decode(author_raw).unwrap_or(std::borrow::Cow::Borrowed(author_raw)).to_string();
+    let author = urlencoding::decode(&decoded_once).unwrap_or(std::borrow::Cow::Borrowed(&decoded_once)).to_string();
+    if author.starts_with("UC") && author.len() == 24 {
+        return Some(author);
+    }
+    resolve_handle_to_channel_id(&author, client, innertube_key, base, visitor).await
+}
+
+/// POST /browse от имени WEB-клиента. `body` — `browseId`/`params` или `continuation`.
+async fn channel_browse(
+    client: &Client,
+    mut body: serde_json::Value,
+    innertube_key: &str,
+    visitor: Option<&str>,
+    locale: &crate::locale::Locale,
+) -> Result<serde_json::Value, String> {
+    let url = format!("https://www.youtube.com/youtubei/v1/browse?key={}", innertube_key);
+    body["context"] = serde_json::json!({
+        "client": {
+            "clientName": "WEB",
+            "clientVersion": "2.20260220.00.00"
+        }
+    });
+    locale.apply(&mut body);
+    crate::visitor_data::attach(&mut body, visitor);
+
+    let response = locale
+        .with_header(crate::visitor_data::with_header(client.post(&url), visitor))
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("InnerTube returned {}", response.status()));
+    }
+    response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+/// Содержимое вкладки по окончанию её URL. Если у канала такой вкладки нет,
+/// YouTube открывает главную — тогда возвращаем None.
+fn find_tab_content(data: &serde_json::Value, tab: ChannelTab) -> Option<&serde_json::Value> {
+    data.pointer("/contents/twoColumnBrowseResultsRenderer/tabs")
+        .and_then(|t| t.as_array())?
+        .iter()
+        .filter_map(|t| t.get("tabRenderer"))
+        .find(|tr| {
+            tr.pointer("/endpoint/commandMetadata/webCommandMetadata/url")
+                .and_then(|u| u.as_str())
+                .map(|u| tab.url_suffixes().iter().any(|suffix| u.ends_with(suffix)))
+                .unwrap_or(false)
+        })
+        .and_then(|tr| tr.get("content"))
+}
+
+/// Обходит содержимое вкладки (или continuation) и собирает элементы в порядке выдачи.
+fn collect_tab_items(
+    obj: &serde_json::Value,
+    channel_info: &ChannelInfo,
+    base_trimmed: &str,
+    out: &mut Vec<ChannelTabItem>,
+    seen: &mut HashSet<String>,
+) {
+    if let Some(map) = obj.as_object() {
+        let item = if let Some(vr) = map.get("videoRenderer").or_else(|| map.get("gridVideoRenderer")) {
+            Some(parse_channel_video(vr, channel_info, base_trimmed).map(ChannelTabItem::Video))
+        } else if let Some(rr) = map.get("reelItemRenderer") {
+            Some(parse_reel_item_renderer(rr, base_trimmed).map(ChannelTabItem::Short))
+        } else if let Some(sl) = map.get("shortsLockupViewModel") {
+            Some(parse_shorts_lockup(sl, base_trimmed).map(ChannelTabItem::Short))
+        } else if let Some(pr) = map.get("gridPlaylistRenderer").or_else(|| map.get("playlistRenderer")) {
+            Some(parse_playlist_renderer(pr, base_trimmed).map(ChannelTabItem::Playlist))
+        } else if let Some(cr) = map.get("gridChannelRenderer").or_else(|| map.get("channelRenderer")) {
+            Some(parse_channel_renderer(cr, base_trimmed).map(ChannelTabItem::Channel))
+        } else if let Some(lv) = map.get("lockupViewModel") {
+            Some(parse_lockup_view_model(lv, base_trimmed).and_then(|item| match item {
+                SearchItem::Playlist(p) | SearchItem::Mix(p) => Some(ChannelTabItem::Playlist(p)),
+                SearchItem::Video(v) | SearchItem::Movie(v) => v.video_id.map(|video_id| {
+                    ChannelTabItem::Video(ChannelVideo {
+                        title: v.title,
+                        author: channel_info.title.clone(),
+                        thumbnail: v.thumbnail,
+                        channel_thumbnail: channel_info.thumbnail.clone(),
+                        views: v.views.unwrap_or_else(|| "0".to_string()),
+                        published_at: v.published.unwrap_or_default(),
+                        duration: v.duration.unwrap_or_else(|| "0:00".to_string()),
+                        video_id,
+                    })
+                }),
+                SearchItem::Short(s) => Some(ChannelTabItem::Short(s)),
+                SearchItem::Channel(c) => Some(ChannelTabItem::Channel(c)),
+            }))
+        } else {
+            map.get("backstagePostRenderer")
+                .or_else(|| map.get("sharedPostRenderer"))
+                .map(|bp| parse_community_post(bp, base_trimmed).map(ChannelTabItem::Post))
+        };
+
+        match item {
+            Some(Some(item)) => {
+                let key = match &item {
+                    ChannelTabItem::Video(v) => format!("v:{}", v.video_id),
+                    ChannelTabItem::Short(s) => format!("v:{}", s.video_id),
+                    ChannelTabItem::Playlist(p) => format!("p:{}", p.playlist_id),
+                    ChannelTabItem::Channel(c) => format!("c:{}", c.channel_id),
+                    ChannelTabItem::Post(p) => format!("post:{}", p.post_id),
+                };
+                if seen.insert(key) {
+                    out.push(item);
+                }
+            }
+            Some(None) => {}
+            None => {
+                for value in map.values() {
+                    collect_tab_items(value, channel_info, base_trimmed, out, seen);
+                }
+            }
+        }
+    } else if let Some(arr) = obj.as_array() {
+        for item in arr {
+            collect_tab_items(item, channel_info, base_trimmed, out, seen);
+        }
+    }
+}
+
+/// backstagePostRenderer (пост сообщества).
+fn parse_community_post(pr: &serde_json::Value, base_trimmed: &str) -> Option<CommunityPost> {
+    let post_id = pr.get("postId").and_then(|p| p.as_str())?.to_string();
+    let author = simplify_text(pr.get("authorText").unwrap_or(&serde_json::Value::Null));
+    let author_id = pr
+        .pointer("/authorEndpoint/browseEndpoint/browseId")
+        .and_then(|b| b.as_str())
+        .unwrap_or("");
+    let content = simplify_text(pr.get("contentText").unwrap_or(&serde_json::Value::Null));
+    let published = crate::locale::normalize_relative_time(&simplify_text(
+        pr.get("publishedTimeText").unwrap_or(&serde_json::Value::Null),
+    ));
+    let likes = simplify_text(pr.get("voteCount").unwrap_or(&serde_json::Value::Null));
+    let comments = pr
+        .get("actionButtons")
+        .and_then(|a| find_value(a, "replyButton"))
+        .and_then(|b| b.pointer("/buttonRenderer/text"))
+        .map(simplify_text)
+        .unwrap_or_default();
+
+    let attachment = pr.get("backstageAttachment").unwrap_or(&serde_json::Value::Null);
+    let image = find_value(attachment, "backstageImageRenderer")
+        .and_then(|img| img.get("image"))
+        .map(best_thumbnail)
+        .filter(|url| !url.is_empty())
+        .map(|url| format!("{}/channel_icon/{}", base_trimmed, urlencoding::encode(&url)));
+    let video_id = find_value(attachment, "videoRenderer")
+        .and_then(|vr| vr.get("videoId"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+
+    Some(CommunityPost {
+        post_id,
+        author: decode_label(&author),
+        author_thumbnail: if author_id.is_empty() {
+            String::new()
+        } else {
+            format!("{}/channel_icon/{}", base_trimmed, author_id)
+        },
+        content,
+        published,
+        likes: non_empty(decode_label(&likes)),
+        comments: non_empty(decode_label(&comments)),
+        image,
+        video_id,
+    })
+}
+
+/// Элементы continuation-ответа (onResponseReceivedActions или ...Endpoints).
+fn continuation_items(data: &serde_json::Value) -> impl Iterator<Item = &serde_json::Value> {
+    ["onResponseReceivedActions", "onResponseReceivedEndpoints"]
+        .into_iter()
+        .filter_map(move |key| data.get(key))
+}
+
+#[utoipa::path(
+    get,
+    path = "/get_channel_tab.php",
+    params(
+        ("channel_id" = Option<String>, Query, description = "YouTube channel ID (or use author)"),
+        ("author" = Option<String>, Query, description = "Channel name or @handle, resolved when channel_id is absent"),
+        ("tab" = String, Query, description = "videos, shorts, streams, playlists, community or channels"),
+        ("continuation" = Option<String>, Query, description = "Token from the previous page"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
+    ),
+    responses(
+        (status = 200, description = "One page of the channel tab", body = ChannelTabPage),
+        (status = 400, description = "Missing channel or invalid tab"),
+        (status = 500, description = "InnerTube request failed")
+    )
+)]
+pub async fn get_channel_tab(
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+) -> impl Responder {
+    let config = &data.config;
+    let base = base_url(&req, config);
+    let base_trimmed = base.trim_end_matches('/');
+    let mut query_params: HashMap<String, String> = HashMap::new();
+    for pair in req.query_string().split('&') {
+        let mut parts = pair.split('=');
+        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            query_params.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let tab = match query_params.get("tab").and_then(|t| ChannelTab::from_param(t)) {
+        Some(tab) => tab,
+        None => {
+            let allowed: Vec<&str> = ChannelTab::ALL.iter().map(|t| t.as_str()).collect();
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid tab parameter",
+                "details": format!("Expected one of: {}", allowed.join(", "))
+            }));
+        }
+    };
+    let continuation = query_params
+        .get("continuation")
+        .filter(|c| !c.is_empty())
+        .map(|c| urlencoding::decode(c).map(|d| d.to_string()).unwrap_or_else(|_| c.clone()));
+
+    let innertube_key = match config.get_innertube_key() {
+        Some(key) => key,
+        None => return HttpResponse::InternalServerError().json(serde_json::json!({"error": "Missing innertube_key in config.yml"})),
+    };
+
+    let client = Client::new();
+    let visitor = crate::visitor_data::visitor_data(config, crate::visitor_data::session_key(&req).as_deref()).await;
+    let locale = crate::locale::Locale::from_request(&req, config);
+
+    let channel_id = match channel_id_from_query(&query_params, &client, innertube_key, &base, visitor.as_deref()).await {
+        Some(id) => id,
+        None => return HttpResponse::BadRequest().json(serde_json::json!({"error": "Channel not found"})),
+    };
+
+    let body = match &continuation {
+        Some(token) => serde_json::json!({ "continuation": token }),
+        None => serde_json::json!({ "browseId": channel_id, "params": tab.params() }),
+    };
+    let response = match channel_browse(&client, body, innertube_key, visitor.as_deref(), &locale).await {
+        Ok(json) => json,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch channel tab",
+                "details": e
+            }))
+        }
+    };
+
+    let mut items = Vec::new();
+    let mut seen = HashSet::new();
+    let (channel_info, next) = if continuation.is_some() {
+        // Шапки канала на страницах продолжения обычно нет: имя берём из ответа,
+        // из кэша первой страницы или, в крайнем случае, с главной канала
+        let title = match continuation_channel_title(&response) {
+            Some(title) => title,
+            None => {
+                let cached = CHANNEL_TITLES.lock().await.get(&channel_id).cloned();
+                match cached {
+                    Some(title) => title,
+                    None => {
+                        let home = serde_json::json!({ "browseId": channel_id });
+                        match channel_browse(&client, home, innertube_key, visitor.as_deref(), &locale).await {
+                            Ok(json) => extract_channel_info(&json, &base, &channel_id).await.title,
+                            Err(e) => {
+                                log::warn!("Channel name for {} continuation not found: {}", channel_id, e);
+                                String::new()
+                            }
+                        }
+                    }
+                }
+            }
+        };
+        if !title.is_empty() {
+            CHANNEL_TITLES.lock().await.put(channel_id.clone(), title.clone());
+        }
+        let info = ChannelInfo {
+            title,
+            description: String::new(),
+            thumbnail: format!("{}/channel_icon/{}", base_trimmed, channel_id),
+            banner: String::new(),
+            subscriber_count: "0".to_string(),
+            video_count: "0".to_string(),
+        };
+        let mut next = None;
+        for actions in continuation_items(&response) {
+            collect_tab_items(actions, &info, base_trimmed, &mut items, &mut seen);
+            next = next.or_else(|| find_search_continuation(actions));
+        }
+        (None, next)
+    } else {
+        let info = extract_channel_info(&response, &base, &channel_id).await;
+        CHANNEL_TITLES.lock().await.put(channel_id.clone(), info.title.clone());
+        let next = find_tab_content(&response, tab).and_then(|content| {
+            collect_tab_items(content, &info, base_trimmed, &mut items, &mut seen);
+            find_search_continuation(content)
+        });
+        (Some(info), next)
+    };
+
+    HttpResponse::Ok().json(ChannelTabPage {
+        channel_id,
+        tab,
+        channel_info,
+        items,
+        continuation: next,
+    })
+}
+
+/// Имя канала из `metadata`/`header`, если ответ продолжения их содержит.
+fn continuation_channel_title(response: &serde_json::Value) -> Option<String> {
+    response
+        .pointer("/metadata/channelMetadataRenderer/title")
+        .and_then(|t| t.as_str())
+        .map(|t| t.to_string())
+        .or_else(|| {
+            response
+                .pointer("/header/pageHeaderRenderer/pageTitle")
+                .and_then(|t| t.as_str())
+                .map(|t| t.to_string())
+        })
+        .or_else(|| {
+            response
+                .pointer("/header/c4TabbedHeaderRenderer/title")
+                .and_then(|t| t.as_str())
+                .map(|t| t.to_string())
+        })
+        .filter(|t| !t.is_empty())
+}
+
+/// Текст из view model: строка, `{content}` или обычные runs/simpleText.
+fn view_model_text(node: Option<&serde_json::Value>) -> String {
+    match node {
+        Some(v) => v
+            .as_str()
+            .or_else(|| v.get("content").and_then(|c| c.as_str()))
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| simplify_text(v)),
+        None => String::new(),
+    }
+}
+
+/// Ссылки в описании канала ведут через youtube.com/redirect?q=...
+fn unwrap_redirect(url: &str) -> String {
+    if url.contains("youtube.com/redirect") {
+        if let Some(query) = url.split_once('?').map(|(_, q)| q) {
+            for pair in query.split('&') {
+                if let Some(target) = pair.strip_prefix("q=") {
+                    return urlencoding::decode(target).map(|t| t.to_string()).unwrap_or_else(|_| target.to_string());
+                }
+            }
+        }
+    }
+    url.to_string()
+}
+
+fn parse_about_links(links: Option<&serde_json::Value>) -> Vec<ChannelLink> {
+    let mut out = Vec::new();
+    for link in links.and_then(|l| l.as_array()).into_iter().flatten() {
+        if let Some(vm) = link.get("channelExternalLinkViewModel") {
+            let title = view_model_text(vm.get("title"));
+            let url = vm
+                .get("link")
+                .and_then(|l| find_value(l, "urlEndpoint"))
+                .and_then(|u| u.get("url"))
+                .and_then(|u| u.as_str())
+                .map(unwrap_redirect)
+                .unwrap_or_else(|| {
+                    let shown = view_model_text(vm.get("link"));
+                    if shown.is_empty() || shown.contains("://") { shown } else { format!("https://{}", shown) }
+                });
+            if !url.is_empty() {
+                out.push(ChannelLink { title, url });
+            }
+        } else {
+            // channelAboutFullMetadataRenderer.primaryLinks
+            let title = simplify_text(link.get("title").unwrap_or(&serde_json::Value::Null));
+            if let Some(url) = link.pointer("/navigationEndpoint/urlEndpoint/url").and_then(|u| u.as_str()) {
+                out.push(ChannelLink { title, url: unwrap_redirect(url) });
+            }
+        }
+    }
+    out
+}
+
+#[utoipa::path(
+    get,
+    path = "/get_channel_about.php",
+    params(
+        ("channel_id" = Option<String>, Query, description = "YouTube channel ID (or use author)"),
+        ("author" = Option<String>, Query, description = "Channel name or @handle, resolved when channel_id is absent"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
+    ),
+    responses(
+        (status = 200, description = "Channel description, links, join date, total views and country", body = ChannelAbout),
+        (status = 400, description = "Missing channel"),
+        (status = 500, description = "InnerTube request failed")
+    )
+)]
+pub async fn get_channel_about(
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+) -> impl Responder {
+    let config = &data.config;
+    let base = base_url(&req, config);
+    let mut query_params: HashMap<String, String> = HashMap::new();
+    for pair in req.query_string().split('&') {
+        let mut parts = pair.split('=');
+        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            query_params.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let innertube_key = match config.get_innertube_key() {
+        Some(key) => key,
+        None => return HttpResponse::InternalServerError().json(serde_json::json!({"error": "Missing innertube_key in config.yml"})),
+    };
+
+    let client = Client::new();
+    let visitor = crate::visitor_data::visitor_data(config, crate::visitor_data::session_key(&req).as_deref()).await;
+    let locale = crate::locale::Locale::from_request(&req, config);
+
+    let channel_id = match channel_id_from_query(&query_params, &client, innertube_key, &base, visitor.as_deref()).await {
+        Some(id) => id,
+        None => return HttpResponse::BadRequest().json(serde_json::json!({"error": "Channel not found"})),
+    };
+
+    let body = serde_json::json!({ "browseId": channel_id, "params": ABOUT_PARAMS });
+    let response = match channel_browse(&client, body, innertube_key, visitor.as_deref(), &locale).await {
+        Ok(json) => json,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch channel about",
+                "details": e
+            }))
+        }
+    };
+
+    let info = extract_channel_info(&response, &base, &channel_id).await;
+
+    // Новый дизайн: aboutChannelViewModel приходит в панели, которую открывает
+    // continuation из onResponseReceivedEndpoints (или из описания в шапке).
+    let mut about_vm = find_value(&response, "aboutChannelViewModel").cloned();
+    if about_vm.is_none() {
+        let token = continuation_items(&response)
+            .chain(response.get("header"))
+            .find_map(|node| find_value(node, "continuationCommand"))
+            .and_then(|c| c.get("token"))
+            .and_then(|t| t.as_str())
+            .map(|t| t.to_string());
+        if let Some(token) = token {
+            match channel_browse(&client, serde_json::json!({ "continuation": token }), innertube_key, visitor.as_deref(), &locale).await {
+                Ok(panel) => about_vm = find_value(&panel, "aboutChannelViewModel").cloned(),
+                Err(e) => crate::log::info!("Channel about panel for {} failed: {}", channel_id, e),
+            }
+        }
+    }
+
+    let non_zero = |text: String| if text.is_empty() { None } else { Some(parse_number(&text)) };
+    let about = if let Some(vm) = about_vm {
+        let handle = view_model_text(vm.get("canonicalChannelUrl"))
+            .rsplit('/')
+            .next()
+            .filter(|h| h.starts_with('@'))
+            .map(|h| h.to_string());
+        let subscribers = view_model_text(vm.get("subscriberCountText"));
+        let videos = view_model_text(vm.get("videoCountText"));
+        ChannelAbout {
+            description: non_empty(view_model_text(vm.get("description"))).unwrap_or(info.description),
+            handle,
+            subscriber_count: non_zero(subscribers).unwrap_or(info.subscriber_count),
+            video_count: non_zero(videos).unwrap_or(info.video_count),
+            total_views: non_zero(view_model_text(vm.get("viewCountText"))),
+            joined_date: non_empty(view_model_text(vm.get("joinedDateText"))),
+            country: non_empty(view_model_text(vm.get("country"))),
+            links: parse_about_links(vm.get("links")),
+            channel_id,
+            title: info.title,
+            thumbnail: info.thumbnail,
+            banner: info.banner,
+        }
+    } else if let Some(meta) = find_value(&response, "channelAboutFullMetadataRenderer") {
+        ChannelAbout {
+            description: non_empty(simplify_text(meta.get("description").unwrap_or(&serde_json::Value::Null)))
+                .unwrap_or(info.description),
+            handle: None,
+            subscriber_count: info.subscriber_count,
+            video_count: info.video_count,
+            total_views: non_zero(simplify_text(meta.get("viewCountText").unwrap_or(&serde_json::Value::Null))),
+            joined_date: non_empty(simplify_text(meta.get("joinedDateText").unwrap_or(&serde_json::Value::Null))),
+            country: non_empty(simplify_text(meta.get("country").unwrap_or(&serde_json::Value::Null))),
+            links: parse_about_links(meta.get("primaryLinks")),
+            channel_id,
+            title: info.title,
+            thumbnail: info.thumbnail,
+            banner: info.banner,
+        }
+    } else {
+        // Хотя бы то, что есть в шапке и метаданных
+        ChannelAbout {
+            description: info.description,
+            handle: None,
+            subscriber_count: info.subscriber_count,
+            video_count: info.video_count,
+            total_views: None,
+            joined_date: None,
+            country: None,
+            links: Vec::new(),
+            channel_id,
+            title: info.title,
+            thumbnail: info.thumbnail,
+            banner: info.banner,
+        }
+    };
+
+    HttpResponse::Ok().json(about)
+}
+
 #[utoipa::path(
     get,
     path = "/get_channel_thumbnail.php",
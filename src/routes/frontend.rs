@@ -3,16 +3,31 @@
 
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use html_escape::encode_text;
+use lazy_static::lazy_static;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::fs;
 
-use crate::config::Config;
+use crate::config::{Config, DeviceProfile};
+use crate::locale::Locale;
 use crate::routes::additional::{HistoryItem, RecommendationItem, SubscriptionItem};
 use crate::routes::auth::{AuthConfig, TokenStore};
-use crate::routes::channel::{ChannelVideosResponse, ChannelVideo};
-use crate::routes::search::{SearchResult, TopVideo};
-use crate::routes::video::{RelatedVideo, VideoInfoResponse};
+use crate::routes::channel::{
+    ChannelAbout, ChannelInfo, ChannelTab, ChannelTabItem, ChannelTabPage, ChannelVideosResponse, ChannelVideo,
+    CommunityPost,
+};
+use crate::routes::search::{
+    ChannelResult, PlaylistResult, SearchItem, SearchPage, SearchResult, ShortResult, TopVideo, MAX_SEARCH_PAGE,
+};
+use crate::routes::search_filters::{SearchDuration, SearchFeature, SearchFilters, SearchSort, UploadDate};
+use crate::routes::video::{device_profile_stream_format, RelatedVideo, VideoInfoResponse};
+
+lazy_static! {
+    static ref URL_RE: regex::Regex = regex::Regex::new(r"https?://[^\s<>]+").unwrap();
+    static ref TIMESTAMP_RE: regex::Regex =
+        regex::Regex::new(r"(^|[\s(\[>])((?:\d{1,2}:)?\d{1,2}:\d{2})\b").unwrap();
+}
 
 fn base_url(req: &HttpRequest, config: &Config) -> String {
     if !config.server.main_url.is_empty() {
@@ -24,7 +39,7 @@ fn base_url(req: &HttpRequest, config: &Config) -> String {
     format!("{}://{}", scheme, host.trim_end_matches('/'))
 }
 
-fn load_template(name: &str) -> String {
+pub(crate) fn load_template(name: &str) -> String {
     let path = format!("assets/html/frontend/{}.html", name);
     fs::read_to_string(&path).unwrap_or_else(|_| format!("<!-- template {} not found -->", name))
 }
@@ -34,11 +49,14 @@ fn load_root_index() -> String {
         .unwrap_or_else(|_| "<!-- assets/html/index.html not found -->".to_string())
 }
 
+/// GET an API endpoint of this server, forwarding the page's hl / gl.
 async fn fetch_json<T: for<'de> Deserialize<'de>>(
     base: &str,
     path: &str,
+    locale: &Locale,
 ) -> Result<T, String> {
-    let url = format!("{}{}", base.trim_end_matches('/'), path);
+    let separator = if path.contains('?') { '&' } else { '?' };
+    let url = format!("{}{}{}{}", base.trim_end_matches('/'), path, separator, locale.query());
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
         .build()
@@ -54,7 +72,7 @@ async fn fetch_json<T: for<'de> Deserialize<'de>>(
     resp.json::<T>().await.map_err(|e| e.to_string())
 }
 
-fn h(s: &str) -> String {
+pub(crate) fn h(s: &str) -> String {
     encode_text(s).to_string()
 }
 
@@ -75,13 +93,96 @@ fn yt_legacy_direct_url(base_trimmed: &str, video_id: &str, quality: &str) -> St
     s
 }
 
+/// URL of an on-the-fly conversion (`/direct_url?codec=`) at a fixed quality.
+fn yt_legacy_converted_url(base_trimmed: &str, video_id: &str, codec: &str, quality: &str) -> String {
+    format!(
+        "{}&codec={}",
+        yt_legacy_direct_url(base_trimmed, video_id, quality),
+        urlencoding::encode(codec)
+    )
+}
+
+/// WebM formats served through `/direct_url?codec=webm`: (itag, quality, size for fmt_list).
+const YT_LEGACY_WEBM_FORMATS: [(u32, &str, &str); 3] = [
+    (45, "720", "1280x720"),
+    (44, "480", "854x480"),
+    (43, "360", "640x360"),
+];
+
+/// Device profile for a frontend request: `?profile=` or, with auto-detection on,
+/// the browser's User-Agent. Unknown profile names are ignored here.
+fn yt_legacy_device_profile(
+    req: &HttpRequest,
+    config: &Config,
+    requested: Option<&str>,
+) -> Option<(String, DeviceProfile)> {
+    let user_agent = req.headers().get("User-Agent").and_then(|h| h.to_str().ok());
+    config
+        .select_device_profile(requested, user_agent)
+        .ok()
+        .flatten()
+}
+
 /// Initial progressive formats: same URL (no `quality` param) so default load matches /direct_url?video_id= only.
-fn yt_legacy_url_encoded_fmt_stream_map(base_trimmed: &str, video_id: &str) -> String {
+/// WebM itags 43/44/45 follow for browsers that cannot play H.264 (old Firefox/Opera).
+/// A matched device profile goes first so the player picks it by default (unless its
+/// container has no legacy itag, then the player gets the usual 18/mp4).
+fn yt_legacy_url_encoded_fmt_stream_map(
+    base_trimmed: &str,
+    video_id: &str,
+    profile: Option<&(String, DeviceProfile)>,
+) -> String {
     let url = yt_legacy_direct_url(base_trimmed, video_id, "");
     let enc = urlencoding::encode(&url);
+    let mut entries = Vec::new();
+    let profile_format = profile.and_then(|(name, device)| Some((name, device_profile_stream_format(device)?)));
+    if let Some((name, (itag, mime))) = profile_format {
+        let profile_url = format!("{}&profile={}", url, urlencoding::encode(name));
+        entries.push(format!(
+            "url={}&itag={}&type={}&sig=sp",
+            urlencoding::encode(&profile_url),
+            itag,
+            urlencoding::encode(mime)
+        ));
+    }
+    entries.push(format!("url={}&itag=22&type=video%2Fmp4&sig=s0", enc));
+    entries.push(format!("url={}&itag=18&type=video%2Fmp4&sig=s1", enc));
+    for (i, (itag, quality, _)) in YT_LEGACY_WEBM_FORMATS.iter().enumerate() {
+        let webm_url = yt_legacy_converted_url(base_trimmed, video_id, "webm", quality);
+        entries.push(format!(
+            "url={}&itag={}&type={}&sig=s{}",
+            urlencoding::encode(&webm_url),
+            itag,
+            urlencoding::encode("video/webm; codecs=\"vp8.0, vorbis\""),
+            i + 2
+        ));
+    }
+    entries.join(",")
+}
+
+/// `fmt_list` matching `yt_legacy_url_encoded_fmt_stream_map`.
+fn yt_legacy_fmt_list(profile: Option<&(String, DeviceProfile)>) -> String {
+    let mut entries = Vec::new();
+    let profile_format = profile.and_then(|(_, device)| Some((device, device_profile_stream_format(device)?)));
+    if let Some((device, (itag, _))) = profile_format {
+        let width = (device.max_height * 16 / 9 + 1) & !1;
+        entries.push(format!("{}/{}x{}/9/0/115", itag, width, device.max_height));
+    }
+    entries.push("22/1280x720/9/0/115".to_string());
+    entries.push("18/640x360/9/0/115".to_string());
+    for (itag, _, size) in YT_LEGACY_WEBM_FORMATS.iter() {
+        entries.push(format!("{}/{}/99/0/0", itag, size));
+    }
+    entries.join(",")
+}
+
+/// Timedtext endpoint for the player (`ttsurl`): `/get_captions.php` speaks the old
+/// `type=list` / `lang=` protocol.
+fn yt_legacy_tts_url(base_trimmed: &str, video_id: &str) -> String {
     format!(
-        "url={}&itag=22&type=video%2Fmp4&sig=s0,url={}&itag=18&type=video%2Fmp4&sig=s1",
-        enc, enc
+        "{}/get_captions.php?video_id={}",
+        base_trimmed.trim_end_matches('/'),
+        urlencoding::encode(video_id)
     )
 }
 
@@ -90,7 +191,9 @@ fn yt_legacy_ytplayer_config(
     title: &str,
     length_seconds: u64,
     stream_map: &str,
+    fmt_list: &str,
     loader_url: &str,
+    tts_url: &str,
 ) -> Value {
     json!({
         "args": {
@@ -106,7 +209,8 @@ fn yt_legacy_ytplayer_config(
             "ssl": 1,
             "hl": "en_US",
             "c": "WEB",
-            "fmt_list": "43/640x360/99/0/0,18/640x360/9/0/115,5/320x240/7/0/0",
+            "fmt_list": fmt_list,
+            "ttsurl": tts_url,
             "show_content_thumbnail": true,
             "host_language": "en",
             "enablesizebutton": 1,
@@ -195,8 +299,7 @@ fn make_clickable(text: &str) -> String {
     let escaped = h(text);
     let with_br = escaped.replace("\n", "<br>");
     // Very simple URL detection
-    let url_regex = regex::Regex::new(r"https?://[^\s<>]+").unwrap();
-    url_regex
+    URL_RE
         .replace_all(&with_br, |caps: &regex::Captures| {
             let u = &caps[0];
             format!(r#"<a href="{}" target="_blank" rel="noopener">{}</a>"#, u, u)
@@ -204,17 +307,83 @@ fn make_clickable(text: &str) -> String {
         .to_string()
 }
 
+fn format_timestamp(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn seek_link(seconds: u64, label: &str) -> String {
+    format!(
+        r##"<a href="#t={}" onclick="return window.__YT_LEGACY_SEEK__ ? window.__YT_LEGACY_SEEK__({}) : true;">{}</a>"##,
+        seconds, seconds, label
+    )
+}
+
+/// make_clickable + "1:23" / "1:02:03" timestamps turned into seek links
+fn make_description_html(text: &str) -> String {
+    let html = make_clickable(text);
+    TIMESTAMP_RE
+        .replace_all(&html, |caps: &regex::Captures| {
+            let ts = &caps[2];
+            let seconds = ts
+                .split(':')
+                .try_fold(0u64, |acc, part| part.parse::<u64>().ok().map(|n| acc * 60 + n));
+            match seconds {
+                Some(sec) => format!("{}{}", &caps[1], seek_link(sec, ts)),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+fn render_chapters(chapters: &[crate::routes::video::Chapter]) -> String {
+    if chapters.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from(
+        r#"<div id="watch-chapters" class="watch-chapters"><h4 class="watch-chapters-heading">Chapters</h4><ul class="watch-chapters-list">"#,
+    );
+    for c in chapters {
+        let thumb = c
+            .thumbnail
+            .as_deref()
+            .map(|t| format!(r#"<img src="{}" width="64" height="36" alt="" class="watch-chapter-thumb">"#, h(t)))
+            .unwrap_or_default();
+        let label = format!(
+            "{}<b>{}</b> {}",
+            thumb,
+            format_timestamp(c.start_seconds),
+            h(&c.title)
+        );
+        out.push_str(&format!(
+            r#"<li class="watch-chapter">{}</li>"#,
+            seek_link(c.start_seconds, &label)
+        ));
+    }
+    out.push_str("</ul></div>");
+    out
+}
+
 // ---- Navbar (included in every page) ----
-fn render_navbar(main_url: &str, search_query: &str) -> String {
+pub(crate) fn render_navbar(main_url: &str, search_query: &str) -> String {
     let t = load_template("partials/navbar");
     t.replace("{{MAIN_URL}}", main_url)
         .replace("{{SEARCH_QUERY}}", &h(search_query))
 }
 
 // ---- Sidebar (guide) - separate partial; tech section only on root page
-fn render_sidebar(main_url: &str, tech_section: Option<&str>, subscriptions_html: Option<&str>) -> String {
+fn render_sidebar(
+    main_url: &str,
+    tech_section: Option<&str>,
+    subscriptions_html: Option<&str>,
+    locale: &Locale,
+) -> String {
     let t = load_template("partials/sidebar");
     let t = t.replace("{{MAIN_URL}}", main_url);
+    let t = t.replace("{{LOCALE_SECTION}}", &render_sidebar_locale_section(main_url, locale));
     let t = t.replace("{{SIDEBAR_TECH_SECTION}}", tech_section.unwrap_or(""));
     let t = t.replace("{{SUBSCRIPTIONS_LIST}}", subscriptions_html.unwrap_or(""));
     t
@@ -232,6 +401,7 @@ async fn render_sidebar_with_auth(
     let main_url = base_url(req, config);
     let main_url_trimmed = main_url.trim_end_matches('/');
     let port = config.server.port;
+    let locale = Locale::from_request(req, config);
     
     // Get refresh token from cookie
     let refresh_token = req
@@ -246,6 +416,7 @@ async fn render_sidebar_with_auth(
                 token,
                 auth_config,
                 config,
+                &locale,
                 main_url_trimmed,
             ).await;
             render_sidebar_subscriptions(&subscriptions, &main_url)
@@ -260,7 +431,7 @@ async fn render_sidebar_with_auth(
         String::new()
     };
     
-    render_sidebar(&main_url, Some(&tech_section), Some(&subscriptions_html))
+    render_sidebar(&main_url, Some(&tech_section), Some(&subscriptions_html), &locale)
 }
 
 fn render_sidebar_tech_section(port: u16, instants: &[crate::config::InstantInstance], main_url: &str) -> String {
@@ -303,6 +474,75 @@ fn render_sidebar_tech_section(port: u16, instants: &[crate::config::InstantInst
     )
 }
 
+const LOCALE_LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("ru", "Русский"),
+    ("uk", "Українська"),
+    ("de", "Deutsch"),
+    ("fr", "Français"),
+    ("es", "Español"),
+    ("pt-BR", "Português (Brasil)"),
+    ("it", "Italiano"),
+    ("pl", "Polski"),
+    ("tr", "Türkçe"),
+    ("ja", "日本語"),
+    ("ko", "한국어"),
+    ("zh-CN", "中文"),
+];
+
+const LOCALE_REGIONS: &[&str] = &[
+    "US", "GB", "CA", "AU", "IN", "RU", "UA", "BY", "KZ", "DE", "FR", "ES", "IT", "PL", "TR", "BR",
+    "MX", "JP", "KR",
+];
+
+/// Language / region picker; the form goes to /locale, which stores hl / gl in cookies.
+fn render_sidebar_locale_section(main_url: &str, locale: &Locale) -> String {
+    let mut languages: Vec<(&str, &str)> = LOCALE_LANGUAGES.to_vec();
+    if !languages.iter().any(|(code, _)| *code == locale.hl) {
+        languages.push((&locale.hl, &locale.hl));
+    }
+    let mut regions: Vec<&str> = LOCALE_REGIONS.to_vec();
+    if !regions.contains(&locale.gl.as_str()) {
+        regions.push(&locale.gl);
+    }
+    let language_options: String = languages
+        .iter()
+        .map(|(code, name)| {
+            format!(
+                "<option value=\"{}\"{}>{}</option>",
+                h(code),
+                if *code == locale.hl { " selected" } else { "" },
+                h(name)
+            )
+        })
+        .collect();
+    let region_options: String = regions
+        .iter()
+        .map(|code| {
+            format!(
+                "<option value=\"{}\"{}>{}</option>",
+                h(code),
+                if *code == locale.gl { " selected" } else { "" },
+                h(code)
+            )
+        })
+        .collect();
+    format!(
+        r#"<li class="guide-section vve-check guide-section-locale">
+            <div class="guide-item-container personal-item">
+              <h3>Language</h3>
+              <form class="guide-locale-form" action="{}/locale" method="GET">
+                <select name="hl" title="Language">{}</select>
+                <select name="gl" title="Location">{}</select>
+                <button class="yt-uix-button yt-uix-button-size-default yt-uix-button-default" type="submit"><span class="yt-uix-button-content">Save</span></button>
+              </form>
+            </div>
+            <hr class="guide-section-separator">
+          </li>"#,
+        main_url, language_options, region_options
+    )
+}
+
 // ---- Root "/": index with navbar, sidebar, videos, recommendations shelf, tech footer ----
 pub async fn page_root(
     req: HttpRequest,
@@ -313,11 +553,13 @@ pub async fn page_root(
     let config = &data.config;
     let main_url = base_url(&req, config);
     let main_url_trimmed = main_url.trim_end_matches('/');
+    let locale = Locale::from_request(&req, config);
     let port = config.server.port;
 
     let videos: Vec<TopVideo> = match fetch_json::<Vec<TopVideo>>(
         &main_url,
         "/get_top_videos.php?count=24",
+        &locale,
     )
     .await
     {
@@ -338,6 +580,7 @@ pub async fn page_root(
             token,
             &auth_config,
             config,
+            &locale,
             main_url_trimmed,
             24,
 			None,
@@ -354,6 +597,7 @@ pub async fn page_root(
                 token,
                 &auth_config,
                 config,
+                &locale,
                 main_url_trimmed,
                 24,
             )
@@ -372,6 +616,7 @@ pub async fn page_root(
                 token,
                 &auth_config,
                 config,
+                &locale,
                 main_url_trimmed,
             ).await;
             render_sidebar_subscriptions(&subscriptions, &main_url)
@@ -379,7 +624,7 @@ pub async fn page_root(
         None => String::new(),
     };
     
-    let sidebar_html = render_sidebar(&main_url, Some(&sidebar_tech_section), Some(&subscriptions_html));
+    let sidebar_html = render_sidebar(&main_url, Some(&sidebar_tech_section), Some(&subscriptions_html), &locale);
     let (main_content, body_class) = match refresh_token {
         Some(_) => {
             let videos_grid = render_video_grid(&videos, &main_url);
@@ -784,11 +1029,13 @@ pub async fn page_index(
 ) -> impl Responder {
     let config = &data.config;
     let base = base_url(&req, config);
+    let locale = Locale::from_request(&req, config);
     let main_url = base.clone();
 
     let videos: Vec<TopVideo> = match fetch_json::<Vec<TopVideo>>(
         &base,
         "/get_top_videos.php?count=24",
+        &locale,
     )
     .await
     {
@@ -814,16 +1061,84 @@ pub async fn page_index(
 }
 
 // ---- Results: search ----
-fn render_search_results(videos: &[SearchResult], main_url: &str) -> String {
+fn render_search_results(items: &[SearchItem], main_url: &str) -> String {
     let mut out = String::new();
-    for v in videos {
-        let video_id = v.video_id.as_deref().unwrap_or("");
-        if video_id.is_empty() {
-            continue;
+    for item in items {
+        match item {
+            SearchItem::Video(v) | SearchItem::Movie(v) => out.push_str(&render_search_video(v, main_url)),
+            SearchItem::Short(s) => out.push_str(&render_short_lockup(s, main_url)),
+            SearchItem::Channel(c) => out.push_str(&render_channel_lockup(c, main_url)),
+            SearchItem::Playlist(p) => out.push_str(&render_playlist_lockup(p, false, main_url)),
+            SearchItem::Mix(p) => out.push_str(&render_playlist_lockup(p, true, main_url)),
         }
-        let watch_url = format!("{}/watch?v={}", main_url, h(video_id));
-        out.push_str(&format!(
-            r#"<li class="yt-lockup clearfix yt-lockup-video yt-lockup-tile result-item-padding">
+    }
+    out
+}
+
+fn render_short_lockup(s: &ShortResult, main_url: &str) -> String {
+    let watch_url = format!("{}/watch?v={}", main_url, h(&s.video_id));
+    render_search_lockup(
+        "yt-lockup-video",
+        &watch_url,
+        &s.thumbnail,
+        "Shorts",
+        &s.title,
+        &[s.views.clone().unwrap_or_default()],
+        None,
+    )
+}
+
+fn render_channel_lockup(c: &ChannelResult, main_url: &str) -> String {
+    let channel_url = format!(
+        "{}/channel?handle={}",
+        main_url,
+        urlencoding::encode(c.handle.as_deref().map(|h| h.trim_start_matches('@')).unwrap_or(&c.channel_id))
+    );
+    render_search_lockup(
+        "yt-lockup-channel",
+        &channel_url,
+        &c.thumbnail,
+        "",
+        &c.title,
+        &[
+            c.handle.clone().unwrap_or_default(),
+            c.subscribers.clone().unwrap_or_default(),
+            c.video_count.clone().unwrap_or_default(),
+        ],
+        c.description.as_deref(),
+    )
+}
+
+fn render_playlist_lockup(p: &PlaylistResult, is_mix: bool, main_url: &str) -> String {
+    // Отдельной страницы плейлиста нет — открываем первое видео
+    let watch_url = match &p.video_id {
+        Some(id) => format!("{}/watch?v={}&list={}", main_url, h(id), h(&p.playlist_id)),
+        None => "#".to_string(),
+    };
+    let badge = if is_mix {
+        "Mix".to_string()
+    } else {
+        p.video_count.clone().unwrap_or_default()
+    };
+    render_search_lockup(
+        "yt-lockup-playlist",
+        &watch_url,
+        &p.thumbnail,
+        &badge,
+        &p.title,
+        &[p.author.clone().unwrap_or_default()],
+        None,
+    )
+}
+
+fn render_search_video(v: &SearchResult, main_url: &str) -> String {
+    let video_id = v.video_id.as_deref().unwrap_or("");
+    if video_id.is_empty() {
+        return String::new();
+    }
+    let watch_url = format!("{}/watch?v={}", main_url, h(video_id));
+    format!(
+        r#"<li class="yt-lockup clearfix yt-lockup-video yt-lockup-tile result-item-padding">
     <div class="yt-lockup-thumbnail">
         <a href="{}" class="ux-thumb-wrap spf-link">
             <span class="video-thumb yt-thumb yt-thumb-185">
@@ -844,22 +1159,244 @@ fn render_search_results(videos: &[SearchResult], main_url: &str) -> String {
         <div class="yt-lockup-meta"><ul class="yt-lockup-meta-info"><li>{}</li></ul></div>
     </div>
 </li>"#,
-            watch_url,
-            h(&v.title),
-            v.thumbnail,
-            v.duration.as_deref().unwrap_or(""),
-            watch_url,
-            h(&v.title),
-            h(&v.title),
-            h(&v.author)
+        watch_url,
+        h(&v.title),
+        v.thumbnail,
+        v.duration.as_deref().unwrap_or(""),
+        watch_url,
+        h(&v.title),
+        h(&v.title),
+        h(&v.author)
+    )
+}
+
+/// Карточка канала, плейлиста или шортса: та же разметка, что у видео,
+/// `badge` выводится поверх миниатюры, пустые строки `meta` пропускаются.
+fn render_search_lockup(
+    kind: &str,
+    url: &str,
+    thumbnail: &str,
+    badge: &str,
+    title: &str,
+    meta: &[String],
+    description: Option<&str>,
+) -> String {
+    let meta_html: String = meta
+        .iter()
+        .filter(|m| !m.is_empty())
+        .map(|m| format!("<li>{}</li>", h(m)))
+        .collect();
+    let description_html = description
+        .map(|d| format!(r#"<div class="yt-lockup-description yt-ui-ellipsis yt-ui-ellipsis-2">{}</div>"#, h(d)))
+        .unwrap_or_default();
+    format!(
+        r#"<li class="yt-lockup clearfix {} yt-lockup-tile result-item-padding">
+    <div class="yt-lockup-thumbnail">
+        <a href="{}" class="ux-thumb-wrap spf-link">
+            <span class="video-thumb yt-thumb yt-thumb-185">
+                <span class="yt-thumb-default">
+                    <span class="yt-thumb-clip">
+                        <img alt="{}" src="{}" width="185" height="104">
+                        <span class="vertical-align"></span>
+                    </span>
+                </span>
+            </span>
+            {}
+        </a>
+    </div>
+    <div class="yt-lockup-content">
+        <h3 class="yt-lockup-title">
+            <a class="yt-uix-tile-link spf-link yt-ui-ellipsis-2" href="{}" title="{}">{}</a>
+        </h3>
+        <div class="yt-lockup-meta"><ul class="yt-lockup-meta-info">{}</ul></div>
+        {}
+    </div>
+</li>"#,
+        kind,
+        url,
+        h(title),
+        h(thumbnail),
+        if badge.is_empty() { String::new() } else { format!(r#"<span class="video-time">{}</span>"#, h(badge)) },
+        url,
+        h(title),
+        h(title),
+        meta_html,
+        description_html
+    )
+}
+
+// Колонки фильтров над результатами. Каждая ссылка переключает одно значение,
+// остальные выбранные фильтры сохраняются.
+fn render_search_filters(main_url: &str, search_query: &str, filters: &SearchFilters) -> String {
+    let link = |f: &SearchFilters| {
+        let q = f.to_query();
+        let mut url = format!("{}/results?search_query={}", main_url, urlencoding::encode(search_query));
+        if !q.is_empty() {
+            url.push('&');
+            url.push_str(&q);
+        }
+        url
+    };
+    let item = |label: &str, selected: bool, target: &SearchFilters, sort: bool| {
+        let class = if sort { "filter filter-sort spf-link" } else { "filter spf-link" };
+        if selected && sort {
+            format!(
+                r#"<li><span class="{} filter-selected"><span class="filter-text">{}</span></span></li>"#,
+                class,
+                h(label)
+            )
+        } else {
+            format!(
+                r#"<li><a class="{}{}" title="Search for {}, {}" href="{}"><span class="filter-text{}">{}</span></a></li>"#,
+                class,
+                if selected { " filter-selected" } else { "" },
+                h(search_query),
+                h(label),
+                h(&link(target)),
+                if selected { "" } else { " filter-ghost" },
+                h(label)
+            )
+        }
+    };
+    let column = |title: &str, items: Vec<String>| {
+        format!(
+            r#"<div class="filter-col"><h4 class="filter-col-title">{}</h4><ul>{}</ul></div>"#,
+            h(title),
+            items.concat()
+        )
+    };
+
+    let dates = UploadDate::ALL
+        .iter()
+        .map(|d| {
+            let selected = filters.upload_date == Some(*d);
+            let mut target = filters.clone();
+            target.upload_date = if selected { None } else { Some(*d) };
+            item(d.label(), selected, &target, false)
+        })
+        .collect();
+    let durations = SearchDuration::ALL
+        .iter()
+        .map(|d| {
+            let selected = filters.duration == Some(*d);
+            let mut target = filters.clone();
+            target.duration = if selected { None } else { Some(*d) };
+            item(d.label(), selected, &target, false)
+        })
+        .collect();
+    let features = SearchFeature::ALL
+        .iter()
+        .map(|f| {
+            let selected = filters.features.contains(f);
+            let mut target = filters.clone();
+            if selected {
+                target.features.retain(|x| x != f);
+            } else {
+                target.features.push(*f);
+            }
+            item(f.label(), selected, &target, false)
+        })
+        .collect();
+    let sorts = SearchSort::ALL
+        .iter()
+        .map(|s| {
+            let mut target = filters.clone();
+            target.sort = *s;
+            item(s.label(), filters.sort == *s, &target, true)
+        })
+        .collect();
+
+    // Активные фильтры показываем «крошками» рядом с кнопкой, клик снимает фильтр
+    let mut crumbs = String::new();
+    let mut crumb = |label: &str, target: SearchFilters| {
+        crumbs.push_str(&format!(
+            r#"<li><a class="filter-crumb spf-link" href="{}">{} &times;</a></li>"#,
+            h(&link(&target)),
+            h(label)
         ));
+    };
+    if let Some(d) = filters.upload_date {
+        crumb(d.label(), SearchFilters { upload_date: None, ..filters.clone() });
     }
-    out
+    if let Some(d) = filters.duration {
+        crumb(d.label(), SearchFilters { duration: None, ..filters.clone() });
+    }
+    for f in &filters.features {
+        let mut target = filters.clone();
+        target.features.retain(|x| x != f);
+        crumb(f.label(), target);
+    }
+    if filters.sort != SearchSort::Relevance {
+        crumb(filters.sort.label(), SearchFilters { sort: SearchSort::Relevance, ..filters.clone() });
+    }
+
+    format!(
+        r#"<div class="filter-top">
+    <div class="filter-bar-container">
+      <div class="filter-button-container">
+        <button class="yt-uix-button yt-uix-button-size-small yt-uix-button-default filter-button yt-uix-expander-head" type="button" onclick=";return false;" data-button-menu-id="some-nonexistent-menu" data-button-action="" data-button-toggle="true"><span class="yt-uix-button-content">Filters </span><img src="/assets/images/pixel-vfl3z5WfW.gif" class="yt-uix-button-arrow"></button>
+      </div>
+      <ul class="filter-crumb-list">{}</ul>
+    </div>
+  </div>
+  <div id="filter-dropdown" class="yt-uix-expander-body">{}{}{}{}</div>"#,
+        crumbs,
+        column("Upload Date", dates),
+        column("Duration", durations),
+        column("Features", features),
+        column("Sort by", sorts)
+    )
 }
 
 #[derive(serde::Deserialize)]
 pub struct ResultsQuery {
     search_query: Option<String>,
+    sort: Option<String>,
+    upload_date: Option<String>,
+    duration: Option<String>,
+    features: Option<String>,
+    page: Option<u32>,
+}
+
+// Пейджер под результатами: номера страниц вокруг текущей и «Next», пока есть продолжение.
+fn render_search_pagination(main_url: &str, search_query: &str, filter_query: &str, page: u32, has_next: bool) -> String {
+    if page <= 1 && !has_next {
+        return String::new();
+    }
+    let link = |n: u32| {
+        let mut url = format!("{}/results?search_query={}", main_url, urlencoding::encode(search_query));
+        if !filter_query.is_empty() {
+            url.push('&');
+            url.push_str(filter_query);
+        }
+        format!("{}&page={}", url, n)
+    };
+    let button = |n: u32, text: &str, link_type: &str, toggled: bool| {
+        format!(
+            r#"<a href="{}" class="yt-uix-button yt-uix-pager-button{} yt-uix-sessionlink yt-uix-button-default yt-uix-button-size-default" data-link-type="{}" data-page="{}" aria-label="Go to page {}"><span class="yt-uix-button-content">{} </span></a>"#,
+            h(&link(n)),
+            if toggled { " yt-uix-button-toggled" } else { "" },
+            link_type,
+            n,
+            n,
+            text
+        )
+    };
+
+    let mut out = String::from(r#"<div class="yt-uix-pager search-pager branded-page-box spf-link" role="navigation">"#);
+    if page > 1 {
+        out.push_str(&button(page - 1, "&laquo; Previous", "prev", false));
+    }
+    // Страницы после текущей заранее неизвестны, показываем только следующую
+    let last = if has_next { page + 1 } else { page };
+    for n in page.saturating_sub(6).max(1)..=last.min(MAX_SEARCH_PAGE) {
+        out.push_str(&button(n, &n.to_string(), "num", n == page));
+    }
+    if has_next && page < MAX_SEARCH_PAGE {
+        out.push_str(&button(page + 1, "Next &raquo;", "next", false));
+    }
+    out.push_str("</div>");
+    out
 }
 
 pub async fn page_results(
@@ -871,6 +1408,7 @@ pub async fn page_results(
 ) -> impl Responder {
     let config = &data.config;
     let base = base_url(&req, config);
+    let locale = Locale::from_request(&req, config);
     let main_url = base.clone();
     let search_query = query
         .search_query
@@ -880,19 +1418,49 @@ pub async fn page_results(
         .to_string();
     let search_encoded = urlencoding::encode(&search_query);
 
-    let videos: Vec<SearchResult> = if search_query.is_empty() {
-        Vec::new()
+    // Невалидные значения фильтров просто игнорируем, как будто их не было;
+    // проверяем каждый по отдельности (и каждый элемент features), чтобы
+    // одна опечатка не сбрасывала остальные фильтры
+    let mut filter_params = HashMap::new();
+    for (key, value) in [
+        ("sort", &query.sort),
+        ("upload_date", &query.upload_date),
+        ("duration", &query.duration),
+        ("features", &query.features),
+    ] {
+        let Some(v) = value else { continue };
+        let parts: Vec<&str> = if key == "features" { v.split(',').collect() } else { vec![v.as_str()] };
+        let valid: Vec<&str> = parts
+            .into_iter()
+            .filter(|part| {
+                let single = HashMap::from([(key.to_string(), part.to_string())]);
+                SearchFilters::from_query(&single).is_ok()
+            })
+            .collect();
+        if !valid.is_empty() {
+            filter_params.insert(key.to_string(), valid.join(","));
+        }
+    }
+    let filters = SearchFilters::from_query(&filter_params).unwrap_or_default();
+    let filter_query = filters.to_query();
+    let page = query.page.unwrap_or(1).clamp(1, MAX_SEARCH_PAGE);
+
+    let (videos, has_next): (Vec<SearchItem>, bool) = if search_query.is_empty() {
+        (Vec::new(), false)
     } else {
-        match fetch_json::<Vec<SearchResult>>(
-            &base,
-            &format!("/get_search_videos.php?query={}", search_encoded),
-        )
-        .await
-        {
-            Ok(v) => v,
+        let mut path = format!("/get_search_videos.php?query={}&type=all&paged=1&page={}", search_encoded, page);
+        if !filter_query.is_empty() {
+            path.push('&');
+            path.push_str(&filter_query);
+        }
+        match fetch_json::<SearchPage>(&base, &path, &locale).await {
+            Ok(p) => {
+                let has_next = p.continuation.is_some();
+                (p.results, has_next)
+            }
             Err(e) => {
                 crate::log::info!("Frontend results: failed to fetch search: {}", e);
-                Vec::new()
+                (Vec::new(), false)
             }
         }
     };
@@ -914,7 +1482,12 @@ pub async fn page_results(
         .replace("{{SIDEBAR}}", &sidebar_html)
         .replace("{{MAIN_URL}}", &main_url)
         .replace("{{SEARCH_QUERY}}", &h(&search_query))
-        .replace("{{RESULTS}}", &results_html);
+        .replace("{{SEARCH_FILTERS}}", &render_search_filters(&main_url, &search_query, &filters))
+        .replace("{{RESULTS}}", &results_html)
+        .replace(
+            "{{PAGINATION}}",
+            &render_search_pagination(&main_url, &search_query, &filter_query, page, has_next),
+        );
 
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -1001,6 +1574,7 @@ fn render_comments(comments: &[crate::routes::video::Comment], main_url: &str) -
 #[derive(serde::Deserialize)]
 pub struct WatchQuery {
     v: Option<String>,
+    profile: Option<String>,
 }
 
 pub async fn page_watch(
@@ -1019,12 +1593,14 @@ pub async fn page_watch(
 
     let config = &data.config;
     let base = base_url(&req, config);
+    let locale = Locale::from_request(&req, config);
     let main_url = base.clone();
     let base_trimmed = main_url.trim_end_matches('/');
 
     let info: VideoInfoResponse = match fetch_json(
         &base,
         &format!("/get-ytvideo-info.php?video_id={}", urlencoding::encode(&video_id)),
+        &locale,
     )
     .await
     {
@@ -1040,6 +1616,7 @@ pub async fn page_watch(
     let related: Vec<RelatedVideo> = fetch_json(
         &base,
         &format!("/get_related_videos.php?video_id={}", urlencoding::encode(&video_id)),
+        &locale,
     )
     .await
     .unwrap_or_default();
@@ -1068,7 +1645,10 @@ pub async fn page_watch(
     let comment_count = info.comment_count.as_deref().unwrap_or("0");
     let comments = &info.comments;
 
-    let stream_map = yt_legacy_url_encoded_fmt_stream_map(base_trimmed, &video_id);
+    let device_profile = yt_legacy_device_profile(&req, config, query.profile.as_deref());
+    let stream_map =
+        yt_legacy_url_encoded_fmt_stream_map(base_trimmed, &video_id, device_profile.as_ref());
+    let fmt_list = yt_legacy_fmt_list(device_profile.as_ref());
     let loader_watch = format!(
         "{}/watch?v={}",
         main_url.trim_end_matches('/'),
@@ -1080,7 +1660,9 @@ pub async fn page_watch(
         title,
         len_sec,
         &stream_map,
+        &fmt_list,
         &loader_watch,
+        &yt_legacy_tts_url(base_trimmed, &video_id),
     );
     let yt_cfg_str = serde_json::to_string(&yt_cfg).unwrap_or_else(|_| "{}".to_string());
     let yt_cfg_str = escape_json_for_html_script(&yt_cfg_str);
@@ -1162,7 +1744,8 @@ pub async fn page_watch(
         .replace("{{DISLIKE_RATIO}}", "50")
         .replace("{{LIKES}}", likes)
         .replace("{{PUBLISHED_AT}}", &h(published_at))
-        .replace("{{DESCRIPTION_HTML}}", &make_clickable(description))
+        .replace("{{DESCRIPTION_HTML}}", &make_description_html(description))
+        .replace("{{CHAPTERS_HTML}}", &render_chapters(&info.chapters))
         .replace("{{COMMENT_COUNT}}", comment_count)
         .replace("{{COMMENTS_HTML}}", &comments_html)
         .replace("{{RELATED_VIDEOS}}", &related_html)
@@ -1304,6 +1887,10 @@ fn render_channel_videos(videos: &[ChannelVideo], main_url: &str) -> String {
                 <div class="yt-alert yt-alert-default"><div class="yt-alert-content">No videos found for this channel.</div></div>
             </ul>"#.to_string();
     }
+    render_channel_video_rows(&videos.iter().collect::<Vec<_>>(), main_url)
+}
+
+fn render_channel_video_rows(videos: &[&ChannelVideo], main_url: &str) -> String {
     let mut out = String::new();
     for chunk in videos.chunks(VIDEOS_PER_ROW) {
         out.push_str("<ul class=\"yt-uix-shelfslider-list\">\n");
@@ -1315,9 +1902,194 @@ fn render_channel_videos(videos: &[ChannelVideo], main_url: &str) -> String {
     out
 }
 
+/// Меню вкладок канала: текущая выводится заголовком, остальные — ссылками.
+fn render_channel_tabs(channel_url: &str, active: &str) -> String {
+    let tabs = std::iter::once(("home", "Home"))
+        .chain(ChannelTab::ALL.iter().map(|t| (t.as_str(), t.label())))
+        .chain(std::iter::once(("about", "About")));
+    let mut out = String::new();
+    for (key, label) in tabs {
+        if key == active {
+            out.push_str(&format!(r#"<li><h2 class="epic-nav-item-heading ">{}</h2></li>"#, label));
+        } else {
+            let url = if key == "home" {
+                channel_url.to_string()
+            } else {
+                format!("{}&tab={}", channel_url, key)
+            };
+            out.push_str(&format!(
+                r#"<li><a href="{}" class="yt-uix-button  spf-link  yt-uix-sessionlink yt-uix-button-epic-nav-item yt-uix-button-size-default"><span class="yt-uix-button-content">{} </span></a></li>"#,
+                h(&url),
+                label
+            ));
+        }
+    }
+    out
+}
+
+fn render_channel_post(p: &CommunityPost, main_url: &str) -> String {
+    let avatar = if p.author_thumbnail.is_empty() {
+        "/assets/images/photo.jpg"
+    } else {
+        p.author_thumbnail.as_str()
+    };
+    let attachment = match (&p.video_id, &p.image) {
+        (Some(id), _) => format!(
+            r#"<a class="channel-post-attachment spf-link" href="{}/watch?v={}"><img src="{}/thumbnail/{}" alt="" width="320"></a>"#,
+            main_url,
+            h(id),
+            main_url,
+            h(id)
+        ),
+        (None, Some(image)) => format!(r#"<img class="channel-post-attachment" src="{}" alt="">"#, h(image)),
+        (None, None) => String::new(),
+    };
+    let mut meta = String::new();
+    if let Some(likes) = &p.likes {
+        meta.push_str(&format!("<li>{} likes</li>", h(likes)));
+    }
+    if let Some(comments) = &p.comments {
+        meta.push_str(&format!("<li>{}</li>", h(comments)));
+    }
+    format!(
+        r#"<li class="channel-post">
+    <img class="channel-post-avatar" src="{}" alt="" width="48" height="48">
+    <div class="channel-post-body">
+        <div class="channel-post-header"><span class="channel-post-author">{}</span> <span class="yt-lockup-deemphasized-text">{}</span></div>
+        <div class="channel-post-text">{}</div>
+        {}
+        <ul class="yt-lockup-meta-info">{}</ul>
+    </div>
+</li>"#,
+        h(avatar),
+        h(&p.author),
+        h(&p.published),
+        h(&p.content),
+        attachment,
+        meta
+    )
+}
+
+/// Элементы вкладки: подряд идущие видео — сеткой, как на главной канала,
+/// остальное — списком карточек из поиска.
+fn render_channel_tab_items(items: &[ChannelTabItem], main_url: &str) -> String {
+    let mut out = String::new();
+    let mut videos: Vec<&ChannelVideo> = Vec::new();
+    let mut list = String::new();
+    let flush_list = |out: &mut String, list: &mut String| {
+        if !list.is_empty() {
+            out.push_str(&format!("<ol class=\"result-list channel-tab-list\">\n{}</ol>\n", list));
+            list.clear();
+        }
+    };
+    for item in items {
+        if let ChannelTabItem::Video(v) = item {
+            flush_list(&mut out, &mut list);
+            videos.push(v);
+            continue;
+        }
+        if !videos.is_empty() {
+            out.push_str(&render_channel_video_rows(&videos, main_url));
+            videos.clear();
+        }
+        match item {
+            ChannelTabItem::Short(s) => list.push_str(&render_short_lockup(s, main_url)),
+            ChannelTabItem::Playlist(p) => list.push_str(&render_playlist_lockup(p, false, main_url)),
+            ChannelTabItem::Channel(c) => list.push_str(&render_channel_lockup(c, main_url)),
+            ChannelTabItem::Post(p) => list.push_str(&render_channel_post(p, main_url)),
+            ChannelTabItem::Video(_) => {}
+        }
+    }
+    if !videos.is_empty() {
+        out.push_str(&render_channel_video_rows(&videos, main_url));
+    }
+    flush_list(&mut out, &mut list);
+    out
+}
+
+fn render_channel_tab(page: &ChannelTabPage, main_url: &str, channel_url: &str) -> String {
+    let body = if page.items.is_empty() {
+        r#"<div class="yt-alert yt-alert-default"><div class="yt-alert-content">This channel has nothing here.</div></div>"#.to_string()
+    } else {
+        render_channel_tab_items(&page.items, main_url)
+    };
+    let more = match &page.continuation {
+        Some(token) => format!(
+            r#"<div class="channel-tab-more"><a href="{}" class="yt-uix-button yt-uix-button-default yt-uix-button-size-default spf-link"><span class="yt-uix-button-content">Load more </span></a></div>"#,
+            h(&format!("{}&tab={}&continuation={}", channel_url, page.tab.as_str(), urlencoding::encode(token)))
+        ),
+        None => String::new(),
+    };
+    format!(
+        r#"<div class="yt-card clearfix">
+    <div class="branded-page-v2-body branded-page-v2-primary-column-content channel-tab-content">
+        <h2 class="branded-page-module-title"><span class="branded-page-module-title-text">{}</span></h2>
+        {}
+        {}
+    </div>
+</div>"#,
+        page.tab.label(),
+        body,
+        more
+    )
+}
+
+fn render_channel_about(about: &ChannelAbout) -> String {
+    let mut stats = String::new();
+    if about.subscriber_count != "0" {
+        stats.push_str(&format!("<li>{} subscribers</li>", h(&about.subscriber_count)));
+    }
+    if let Some(views) = &about.total_views {
+        stats.push_str(&format!("<li>{} views</li>", h(views)));
+    }
+    if let Some(joined) = &about.joined_date {
+        stats.push_str(&format!("<li>{}</li>", h(joined)));
+    }
+    if let Some(country) = &about.country {
+        stats.push_str(&format!("<li>Country: {}</li>", h(country)));
+    }
+    // Только http(s), чтобы описание канала не подсунуло javascript:
+    let links: String = about
+        .links
+        .iter()
+        .filter(|l| l.url.starts_with("https://") || l.url.starts_with("http://"))
+        .map(|l| {
+            let title = if l.title.is_empty() { &l.url } else { &l.title };
+            format!(r#"<li><a href="{}" rel="nofollow noopener" target="_blank">{}</a></li>"#, h(&l.url), h(title))
+        })
+        .collect();
+    let links_html = if links.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<h3 class="channel-about-heading">Links</h3><ul class="channel-about-links">{}</ul>"#, links)
+    };
+    format!(
+        r#"<div class="yt-card clearfix">
+    <div class="branded-page-v2-body branded-page-v2-primary-column-content channel-about">
+        <h2 class="branded-page-module-title"><span class="branded-page-module-title-text">About</span></h2>
+        <div class="channel-about-description">{}</div>
+        <ul class="channel-about-stats">{}</ul>
+        {}
+    </div>
+</div>"#,
+        h(&about.description),
+        stats,
+        links_html
+    )
+}
+
 #[derive(serde::Deserialize)]
 pub struct ChannelQuery {
     handle: Option<String>,
+    /// home (по умолчанию), about или одна из вкладок ChannelTab.
+    tab: Option<String>,
+    continuation: Option<String>,
+}
+
+enum ChannelView {
+    Home,
+    About,
+    Tab(ChannelTab),
 }
 
 /// Normalize channel handle: remove leading @ or %40 (URL-encoded @).
@@ -1344,16 +2116,83 @@ pub async fn page_channel(
         }
     };
 
+    let view = match query.tab.as_deref().map(|t| t.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("home") => ChannelView::Home,
+        Some("about") => ChannelView::About,
+        Some(other) => match ChannelTab::from_param(other) {
+            Some(tab) => ChannelView::Tab(tab),
+            None => {
+                return HttpResponse::BadRequest()
+                    .content_type("text/html; charset=utf-8")
+                    .body(format!("<h1>Unknown channel tab</h1><p>{}</p>", h(other)));
+            }
+        },
+    };
+
     let config = &data.config;
     let base = base_url(&req, config);
+    let locale = Locale::from_request(&req, config);
     let main_url = base.clone();
+    let author = urlencoding::encode(&handle).to_string();
+    let channel_url = format!("{}/channel?handle={}", main_url, author);
 
-    let channel_response: ChannelVideosResponse = match fetch_json(
-        &base,
-        &format!("/get_author_videos.php?author={}", urlencoding::encode(&handle)),
-    )
-    .await
-    {
+    let fetched: Result<(ChannelInfo, String, &str), String> = match view {
+        ChannelView::Home => fetch_json::<ChannelVideosResponse>(&base, &format!("/get_author_videos.php?author={}", author), &locale)
+            .await
+            .map(|r| {
+                let content = load_template("partials/channel_home")
+                    .replace("{{SPOTLIGHT_HTML}}", &render_spotlight_html(&r.videos, &main_url))
+                    .replace("{{VIDEOS_HTML}}", &render_channel_videos(&r.videos, &main_url));
+                (r.channel_info, content, "home")
+            }),
+        ChannelView::About => fetch_json::<ChannelAbout>(&base, &format!("/get_channel_about.php?author={}", author), &locale)
+            .await
+            .map(|about| {
+                let content = render_channel_about(&about);
+                let info = ChannelInfo {
+                    title: about.title,
+                    description: about.description,
+                    thumbnail: about.thumbnail,
+                    banner: about.banner,
+                    subscriber_count: about.subscriber_count,
+                    video_count: about.video_count,
+                };
+                (info, content, "about")
+            }),
+        ChannelView::Tab(tab) => {
+            let mut tab_path = format!("/get_channel_tab.php?author={}&tab={}", author, tab.as_str());
+            let page = match query.continuation.as_deref().filter(|c| !c.is_empty()) {
+                Some(token) => {
+                    tab_path.push_str(&format!("&continuation={}", urlencoding::encode(token)));
+                    // Страница продолжения приходит без шапки канала — берём её отдельно
+                    let header_path = format!("/get_author_videos.php?author={}&count=0", author);
+                    let (page, header) = tokio::join!(
+                        fetch_json::<ChannelTabPage>(&base, &tab_path, &locale),
+                        fetch_json::<ChannelVideosResponse>(&base, &header_path, &locale)
+                    );
+                    page.and_then(|mut p| {
+                        p.channel_info = Some(header?.channel_info);
+                        Ok(p)
+                    })
+                }
+                None => fetch_json::<ChannelTabPage>(&base, &tab_path, &locale).await,
+            };
+            page.map(|mut p| {
+                let content = render_channel_tab(&p, &main_url, &channel_url);
+                let info = p.channel_info.take().unwrap_or(ChannelInfo {
+                    title: handle.clone(),
+                    description: String::new(),
+                    thumbnail: String::new(),
+                    banner: String::new(),
+                    subscriber_count: "0".to_string(),
+                    video_count: "0".to_string(),
+                });
+                (info, content, tab.as_str())
+            })
+        }
+    };
+
+    let (channel_info, channel_content, active_tab) = match fetched {
         Ok(r) => r,
         Err(e) => {
             crate::log::info!("Frontend channel: failed to fetch channel: {}", e);
@@ -1363,9 +2202,6 @@ pub async fn page_channel(
         }
     };
 
-    let channel_info = &channel_response.channel_info;
-    let videos = &channel_response.videos;
-
     let channel_title = &channel_info.title;
     let channel_description = &channel_info.description;
     let channel_thumbnail = if channel_info.thumbnail.is_empty() {
@@ -1375,26 +2211,25 @@ pub async fn page_channel(
     };
     let channel_banner = &channel_info.banner;
     let subscriber_count = &channel_info.subscriber_count;
-    let channel_url = format!("{}/channel?handle={}", main_url, urlencoding::encode(&handle));
 
     let navbar = render_navbar(&main_url, "");
     let sidebar_html = render_sidebar_with_auth(&req, &data, &auth_config, &token_store, false).await;
-    let spotlight_html = render_spotlight_html(videos, &main_url);
-    let videos_html = render_channel_videos(videos, &main_url);
+    let channel_tabs = render_channel_tabs(&channel_url, active_tab);
 
     let t = load_template("channel");
     let html = t
         .replace("{{NAVBAR}}", &navbar)
         .replace("{{SIDEBAR}}", &sidebar_html)
+        .replace("{{CHANNEL_CONTENT}}", &channel_content)
+        .replace("{{CHANNEL_APPBAR_TABS}}", &channel_tabs)
+        .replace("{{CHANNEL_TABS}}", &channel_tabs)
         .replace("{{MAIN_URL}}", &main_url)
         .replace("{{CHANNEL_TITLE}}", &h(channel_title))
         .replace("{{CHANNEL_DESCRIPTION}}", &h(channel_description))
         .replace("{{CHANNEL_THUMBNAIL}}", channel_thumbnail)
         .replace("{{CHANNEL_BANNER}}", channel_banner)
         .replace("{{SUBSCRIBER_COUNT}}", subscriber_count)
-        .replace("{{CHANNEL_URL}}", &channel_url)
-        .replace("{{SPOTLIGHT_HTML}}", &spotlight_html)
-        .replace("{{VIDEOS_HTML}}", &videos_html);
+        .replace("{{CHANNEL_URL}}", &channel_url);
 
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -1422,6 +2257,42 @@ pub async fn page_login(
         .body(html)
 }
 
+// ---- Locale: remember hl / gl in cookies (read by every API endpoint), go back ----
+pub async fn page_set_locale(req: HttpRequest, data: web::Data<crate::AppState>) -> impl Responder {
+    let config = &data.config;
+    let main_url = base_url(&req, config);
+    let locale = Locale::from_request(&req, config);
+
+    // Назад на страницу, с которой пришли, но только в пределах этого сервера:
+    // "http://host" как префикс пропустил бы и http://host.evil.com
+    let own_prefix = format!("{}/", main_url);
+    let next = web::Query::<std::collections::HashMap<String, String>>::from_query(req.query_string())
+        .ok()
+        .and_then(|q| q.get("next").cloned())
+        .filter(|n| n.starts_with('/') && !n.starts_with("//") && !n.starts_with("/\\"))
+        .map(|n| format!("{}{}", main_url, n))
+        .or_else(|| {
+            req.headers()
+                .get("Referer")
+                .and_then(|r| r.to_str().ok())
+                .filter(|r| *r == main_url || r.starts_with(&own_prefix))
+                .map(|r| r.to_string())
+        })
+        .unwrap_or_else(|| format!("{}/", main_url));
+
+    HttpResponse::Found()
+        .insert_header(("Location", next))
+        .append_header((
+            "Set-Cookie",
+            format!("hl={}; Path=/; Max-Age=31536000; SameSite=Lax", locale.hl),
+        ))
+        .append_header((
+            "Set-Cookie",
+            format!("gl={}; Path=/; Max-Age=31536000; SameSite=Lax", locale.gl),
+        ))
+        .finish()
+}
+
 // ---- Logout: clear session token, clear cookie, redirect to login ----
 pub async fn page_logout(
     req: HttpRequest,
@@ -1457,8 +2328,12 @@ pub async fn page_embed(
     }
     let config = &data.config;
     let base = base_url(&req, config);
+    let locale = Locale::from_request(&req, config);
     let base_trim = base.trim_end_matches('/');
-    let stream_map = yt_legacy_url_encoded_fmt_stream_map(base_trim, &video_id);
+    let device_profile = yt_legacy_device_profile(&req, config, None);
+    let stream_map =
+        yt_legacy_url_encoded_fmt_stream_map(base_trim, &video_id, device_profile.as_ref());
+    let fmt_list = yt_legacy_fmt_list(device_profile.as_ref());
     let loader_embed = format!(
         "{}/embed/{}",
         base_trim,
@@ -1467,6 +2342,7 @@ pub async fn page_embed(
     let (embed_title, len_sec) = match fetch_json::<VideoInfoResponse>(
         &base,
         &format!("/get-ytvideo-info.php?video_id={}", urlencoding::encode(&video_id)),
+        &locale,
     )
     .await
     {
@@ -1481,7 +2357,9 @@ pub async fn page_embed(
         embed_title.as_str(),
         len_sec,
         &stream_map,
+        &fmt_list,
         &loader_embed,
+        &yt_legacy_tts_url(base_trim, &video_id),
     );
     let yt_cfg_str = serde_json::to_string(&yt_cfg).unwrap_or_else(|_| "{}".to_string());
     let yt_cfg_str = escape_json_for_html_script(&yt_cfg_str);
@@ -0,0 +1,530 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use bytes::Bytes;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use utoipa::ToSchema;
+
+use crate::locale::Locale;
+
+/// How long a long-poll request waits for new live messages before returning empty.
+const LONG_POLL_TIMEOUT_MS: u64 = 20000;
+/// Lower bound between two innertube chat requests; replay pages come with no delay.
+const MIN_POLL_INTERVAL_MS: u64 = 1000;
+
+fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
+    if !config.server.main_url.is_empty() {
+        return config.server.main_url.clone();
+    }
+    let info = req.connection_info();
+    let scheme = info.scheme();
+    let host = info.host();
+    format!("{}://{}/", scheme, host.trim_end_matches('/'))
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct LiveChatMessage {
+    pub id: String,
+    /// `text`, `superchat`, `supersticker` or `membership`.
+    pub kind: String,
+    pub author: String,
+    pub author_channel_id: Option<String>,
+    pub author_thumbnail: String,
+    pub text: String,
+    /// Badge tooltips: "Owner", "Moderator", "Verified", "Member (6 months)"...
+    pub badges: Vec<String>,
+    /// Super Chat / Super Sticker amount as displayed, e.g. "$5.00".
+    pub amount: Option<String>,
+    /// Unix time in microseconds the message was sent.
+    pub timestamp_usec: Option<u64>,
+    /// Replay only: position in the video the message belongs to.
+    pub video_offset_ms: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LiveChatResponse {
+    pub video_id: String,
+    pub is_replay: bool,
+    pub messages: Vec<LiveChatMessage>,
+    /// Pass back as `continuation` (with the same `replay` value) for the next batch;
+    /// absent when the chat has ended.
+    pub continuation: Option<String>,
+    /// Suggested delay before the next request.
+    pub poll_interval_ms: u64,
+}
+
+struct ChatPage {
+    messages: Vec<LiveChatMessage>,
+    continuation: Option<String>,
+    timeout_ms: u64,
+}
+
+fn innertube_context(locale: &Locale) -> Value {
+    serde_json::json!({
+        "client": {
+            "clientName": "WEB",
+            "clientVersion": "2.20260220.00.00",
+            "hl": locale.hl,
+            "gl": locale.gl
+        }
+    })
+}
+
+fn simplify_text(node: &Value) -> String {
+    if let Some(s) = node.get("simpleText").and_then(|t| t.as_str()) {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    if let Some(runs) = node.get("runs").and_then(|r| r.as_array()) {
+        for run in runs {
+            if let Some(text) = run.get("text").and_then(|t| t.as_str()) {
+                out.push_str(text);
+            } else if let Some(emoji) = run.get("emoji") {
+                // Кастомные эмодзи канала отдаём шорткодом (:face:), обычные — символом
+                let shortcut = emoji
+                    .get("shortcuts")
+                    .and_then(|s| s.get(0))
+                    .and_then(|s| s.as_str());
+                let id = emoji.get("emojiId").and_then(|e| e.as_str()).unwrap_or("");
+                let is_custom = emoji.get("isCustomEmoji").and_then(|c| c.as_bool()).unwrap_or(false);
+                if is_custom || id.is_empty() {
+                    out.push_str(shortcut.unwrap_or(""));
+                } else {
+                    out.push_str(id);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Initial chat continuation from the watch `next` response, and whether it is a replay.
+async fn fetch_initial_continuation(
+    video_id: &str,
+    innertube_key: &str,
+    client: &Client,
+    locale: &Locale,
+) -> Result<(String, bool), String> {
+    let url = format!("https://www.youtube.com/youtubei/v1/next?key={}", innertube_key);
+    let payload = serde_json::json!({
+        "context": innertube_context(locale),
+        "videoId": video_id,
+    });
+    let data: Value = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("next request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("next response parse failed: {}", e))?;
+
+    let renderer = data
+        .get("contents")
+        .and_then(|c| c.get("twoColumnWatchNextResults"))
+        .and_then(|t| t.get("conversationBar"))
+        .and_then(|c| c.get("liveChatRenderer"))
+        .ok_or_else(|| format!("Video {} has no live chat (not a stream, or chat disabled)", video_id))?;
+    let is_replay = renderer.get("isReplay").and_then(|r| r.as_bool()).unwrap_or(false);
+
+    // Предпочитаем "Live chat" (все сообщения) вместо "Top chat" из переключателя в шапке
+    let from_selector = renderer
+        .get("header")
+        .and_then(|h| h.get("liveChatHeaderRenderer"))
+        .and_then(|h| h.get("viewSelector"))
+        .and_then(|v| v.get("sortFilterSubMenuRenderer"))
+        .and_then(|s| s.get("subMenuItems"))
+        .and_then(|items| items.as_array())
+        .and_then(|items| items.last())
+        .and_then(|item| item.get("continuation"))
+        .and_then(|c| c.get("reloadContinuationData"))
+        .and_then(|r| r.get("continuation"))
+        .and_then(|c| c.as_str());
+    let continuation = from_selector
+        .or_else(|| {
+            renderer
+                .get("continuations")
+                .and_then(|c| c.get(0))
+                .and_then(|c| c.get("reloadContinuationData"))
+                .and_then(|r| r.get("continuation"))
+                .and_then(|c| c.as_str())
+        })
+        .ok_or_else(|| format!("No chat continuation for {}", video_id))?;
+    Ok((continuation.to_string(), is_replay))
+}
+
+fn parse_chat_item(item: &Value, video_offset_ms: Option<u64>, base_trimmed: &str) -> Option<LiveChatMessage> {
+    let (kind, renderer) = if let Some(r) = item.get("liveChatTextMessageRenderer") {
+        ("text", r)
+    } else if let Some(r) = item.get("liveChatPaidMessageRenderer") {
+        ("superchat", r)
+    } else if let Some(r) = item.get("liveChatPaidStickerRenderer") {
+        ("supersticker", r)
+    } else if let Some(r) = item.get("liveChatMembershipItemRenderer") {
+        ("membership", r)
+    } else {
+        return None;
+    };
+
+    let mut text = renderer.get("message").map(simplify_text).unwrap_or_default();
+    if text.is_empty() && kind == "membership" {
+        text = renderer.get("headerSubtext").map(simplify_text).unwrap_or_default();
+    }
+    let author_thumbnail = renderer
+        .get("authorPhoto")
+        .and_then(|p| p.get("thumbnails"))
+        .and_then(|t| t.as_array())
+        .and_then(|t| t.last())
+        .and_then(|t| t.get("url"))
+        .and_then(|u| u.as_str())
+        .map(|u| format!("{}/channel_icon/{}", base_trimmed, urlencoding::encode(u)))
+        .unwrap_or_default();
+    let badges = renderer
+        .get("authorBadges")
+        .and_then(|b| b.as_array())
+        .map(|badges| {
+            badges
+                .iter()
+                .filter_map(|b| b.get("liveChatAuthorBadgeRenderer"))
+                .filter_map(|b| b.get("tooltip").and_then(|t| t.as_str()))
+                .map(|t| t.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(LiveChatMessage {
+        id: renderer.get("id").and_then(|i| i.as_str()).unwrap_or("").to_string(),
+        kind: kind.to_string(),
+        author: renderer.get("authorName").map(simplify_text).unwrap_or_default(),
+        author_channel_id: renderer
+            .get("authorExternalChannelId")
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string()),
+        author_thumbnail,
+        text,
+        badges,
+        amount: renderer
+            .get("purchaseAmountText")
+            .map(simplify_text)
+            .filter(|a| !a.is_empty()),
+        timestamp_usec: renderer
+            .get("timestampUsec")
+            .and_then(|t| t.as_str())
+            .and_then(|t| t.parse().ok()),
+        video_offset_ms,
+    })
+}
+
+fn parse_actions(actions: &[Value], base_trimmed: &str, messages: &mut Vec<LiveChatMessage>) {
+    for action in actions {
+        if let Some(replay) = action.get("replayChatItemAction") {
+            let offset = replay
+                .get("videoOffsetTimeMsec")
+                .and_then(|o| o.as_str())
+                .and_then(|o| o.parse().ok());
+            if let Some(inner) = replay.get("actions").and_then(|a| a.as_array()) {
+                for inner_action in inner {
+                    if let Some(item) = inner_action.get("addChatItemAction").and_then(|a| a.get("item")) {
+                        messages.extend(parse_chat_item(item, offset, base_trimmed));
+                    }
+                }
+            }
+        } else if let Some(item) = action.get("addChatItemAction").and_then(|a| a.get("item")) {
+            messages.extend(parse_chat_item(item, None, base_trimmed));
+        }
+    }
+}
+
+async fn fetch_chat_page(
+    continuation: &str,
+    is_replay: bool,
+    offset_ms: Option<u64>,
+    innertube_key: &str,
+    client: &Client,
+    base_trimmed: &str,
+    locale: &Locale,
+) -> Result<ChatPage, String> {
+    let (endpoint, payload) = if is_replay {
+        let mut payload = serde_json::json!({
+            "context": innertube_context(locale),
+            "continuation": continuation,
+        });
+        if let Some(offset) = offset_ms {
+            payload["currentPlayerState"] = serde_json::json!({ "playerOffsetMs": offset.to_string() });
+        }
+        ("get_live_chat_replay", payload)
+    } else {
+        (
+            "get_live_chat",
+            serde_json::json!({
+                "context": innertube_context(locale),
+                "continuation": continuation,
+            }),
+        )
+    };
+    let url = format!(
+        "https://www.youtube.com/youtubei/v1/live_chat/{}?key={}",
+        endpoint, innertube_key
+    );
+    let resp = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("{} request failed: {}", endpoint, e))?;
+    if !resp.status().is_success() {
+        return Err(format!("{} HTTP {}", endpoint, resp.status()));
+    }
+    let data: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("{} response parse failed: {}", endpoint, e))?;
+
+    let chat = match data
+        .get("continuationContents")
+        .and_then(|c| c.get("liveChatContinuation"))
+    {
+        Some(c) => c,
+        // Трансляция закончилась — продолжения больше нет
+        None => {
+            return Ok(ChatPage {
+                messages: Vec::new(),
+                continuation: None,
+                timeout_ms: 0,
+            })
+        }
+    };
+
+    let mut messages = Vec::new();
+    if let Some(actions) = chat.get("actions").and_then(|a| a.as_array()) {
+        parse_actions(actions, base_trimmed, &mut messages);
+    }
+
+    let next = chat
+        .get("continuations")
+        .and_then(|c| c.get(0))
+        .and_then(|c| {
+            [
+                "invalidationContinuationData",
+                "timedContinuationData",
+                "liveChatReplayContinuationData",
+                "reloadContinuationData",
+            ]
+            .iter()
+            .find_map(|key| c.get(*key))
+        });
+    let continuation = next
+        .and_then(|n| n.get("continuation"))
+        .and_then(|c| c.as_str())
+        .map(|c| c.to_string());
+    let timeout_ms = next
+        .and_then(|n| n.get("timeoutMs"))
+        .and_then(|t| t.as_u64())
+        .unwrap_or(if is_replay { 0 } else { 5000 })
+        .clamp(MIN_POLL_INTERVAL_MS, 10000);
+
+    Ok(ChatPage {
+        messages,
+        continuation,
+        timeout_ms,
+    })
+}
+
+fn sse_event(event: &str, data: &impl Serialize) -> Bytes {
+    let json = serde_json::to_string(data).unwrap_or_else(|_| "{}".to_string());
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, json))
+}
+
+/// Polls the chat until the stream ends or the client disconnects, pushing each
+/// message as an SSE `message` event; `end` / `error` close the stream.
+#[allow(clippy::too_many_arguments)]
+async fn run_sse(
+    tx: mpsc::Sender<Result<Bytes, actix_web::Error>>,
+    video_id: String,
+    mut continuation: String,
+    is_replay: bool,
+    mut offset_ms: Option<u64>,
+    innertube_key: String,
+    client: Client,
+    base_trimmed: String,
+    locale: Locale,
+) {
+    let start = serde_json::json!({ "video_id": video_id, "is_replay": is_replay });
+    if tx.send(Ok(sse_event("start", &start))).await.is_err() {
+        return;
+    }
+    // Повтор чата отдаём в темпе видео: первое сообщение сразу, остальные по videoOffsetTimeMsec
+    let mut replay_clock: Option<(Instant, u64)> = None;
+    loop {
+        let page_started = Instant::now();
+        let page = match fetch_chat_page(&continuation, is_replay, offset_ms, &innertube_key, &client, &base_trimmed, &locale).await {
+            Ok(p) => p,
+            Err(e) => {
+                log::warn!("Live chat {}: {}", video_id, e);
+                let _ = tx.send(Ok(sse_event("error", &serde_json::json!({ "error": e })))).await;
+                return;
+            }
+        };
+        offset_ms = None;
+
+        let sent = if page.messages.is_empty() {
+            // Комментарий-пинг, чтобы прокси не закрывали простаивающее соединение
+            tx.send(Ok(Bytes::from_static(b": keepalive\n\n"))).await.is_ok()
+        } else {
+            let mut ok = true;
+            for message in &page.messages {
+                if let Some(offset) = message.video_offset_ms.filter(|_| is_replay) {
+                    let (clock_start, first_offset) = *replay_clock.get_or_insert((Instant::now(), offset));
+                    let due = clock_start + Duration::from_millis(offset.saturating_sub(first_offset));
+                    tokio::time::sleep_until(due.into()).await;
+                }
+                if tx.send(Ok(sse_event("message", message))).await.is_err() {
+                    ok = false;
+                    break;
+                }
+            }
+            ok
+        };
+        if !sent {
+            return;
+        }
+
+        match page.continuation {
+            Some(next) => continuation = next,
+            None => {
+                let _ = tx.send(Ok(sse_event("end", &serde_json::json!({ "video_id": video_id })))).await;
+                return;
+            }
+        }
+        let interval = Duration::from_millis(page.timeout_ms.max(MIN_POLL_INTERVAL_MS));
+        tokio::time::sleep(interval.saturating_sub(page_started.elapsed())).await;
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/live_chat/{video_id}",
+    params(
+        ("video_id" = String, Path, description = "YouTube video ID of a live stream or a finished stream with chat replay"),
+        ("mode" = Option<String>, Query, description = "sse (default): Server-Sent Events with start/message/end/error events until the chat ends; json: one long-poll batch for browsers without EventSource"),
+        ("continuation" = Option<String>, Query, description = "json mode: continuation from the previous response"),
+        ("replay" = Option<bool>, Query, description = "json mode: is_replay from the previous response, required together with continuation for replays"),
+        ("offset_ms" = Option<u64>, Query, description = "Replay only: video position to start the chat from"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
+    ),
+    responses(
+        (status = 200, description = "SSE stream of LiveChatMessage events, or a batch in json mode", body = LiveChatResponse),
+        (status = 404, description = "Video has no live chat or chat replay"),
+        (status = 500, description = "Innertube request failed")
+    )
+)]
+pub async fn live_chat(
+    path: web::Path<String>,
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+) -> impl Responder {
+    let video_id = path.into_inner();
+    let config = &data.config;
+    let base = base_url(&req, config);
+    let base_trimmed = base.trim_end_matches('/').to_string();
+
+    let query_params: HashMap<String, String> =
+        web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .map(|q| q.into_inner())
+            .unwrap_or_default();
+    let json_mode = query_params.get("mode").map(|m| m == "json").unwrap_or(false);
+    let offset_ms: Option<u64> = query_params.get("offset_ms").and_then(|o| o.parse().ok());
+
+    let innertube_key = match config.get_innertube_key() {
+        Some(key) => key,
+        None => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Missing innertube_key in config.yml"
+            }));
+        }
+    };
+    let client = Client::new();
+    let locale = Locale::from_request(&req, config);
+
+    let (continuation, is_replay) = match query_params.get("continuation").filter(|c| !c.is_empty()) {
+        Some(c) => (
+            c.clone(),
+            query_params.get("replay").map(|r| r == "true" || r == "1").unwrap_or(false),
+        ),
+        None => match fetch_initial_continuation(&video_id, innertube_key, &client, &locale).await {
+            Ok(found) => found,
+            Err(e) => {
+                let status = if e.contains("has no live chat") {
+                    actix_web::http::StatusCode::NOT_FOUND
+                } else {
+                    actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+                };
+                return HttpResponse::build(status).json(serde_json::json!({
+                    "error": "Failed to open live chat",
+                    "details": e
+                }));
+            }
+        },
+    };
+
+    if !json_mode {
+        let (tx, rx) = mpsc::channel::<Result<Bytes, actix_web::Error>>(32);
+        actix_web::rt::spawn(run_sse(
+            tx,
+            video_id,
+            continuation,
+            is_replay,
+            offset_ms,
+            innertube_key.to_string(),
+            client,
+            base_trimmed,
+            locale,
+        ));
+        return HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header(("Cache-Control", "no-cache"))
+            .insert_header(("X-Accel-Buffering", "no"))
+            .streaming(ReceiverStream::new(rx));
+    }
+
+    // Long-poll: для живого чата ждём, пока появятся сообщения (но не дольше LONG_POLL_TIMEOUT_MS)
+    let mut continuation = continuation;
+    let mut waited_ms = 0;
+    loop {
+        let page = match fetch_chat_page(&continuation, is_replay, offset_ms, innertube_key, &client, &base_trimmed, &locale).await {
+            Ok(p) => p,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to fetch live chat",
+                    "details": e
+                }));
+            }
+        };
+        let done = is_replay
+            || !page.messages.is_empty()
+            || page.continuation.is_none()
+            || waited_ms + page.timeout_ms > LONG_POLL_TIMEOUT_MS;
+        if done {
+            return HttpResponse::Ok().json(LiveChatResponse {
+                video_id,
+                is_replay,
+                messages: page.messages,
+                continuation: page.continuation,
+                poll_interval_ms: page.timeout_ms,
+            });
+        }
+        tokio::time::sleep(Duration::from_millis(page.timeout_ms)).await;
+        waited_ms += page.timeout_ms;
+        if let Some(next) = page.continuation {
+            continuation = next;
+        }
+    }
+}
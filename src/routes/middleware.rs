@@ -1,8 +1,10 @@
 use actix_web::{
     body::BoxBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpResponse,
+    http::header,
+    web, Error, HttpResponse,
 };
+use base64::{engine::general_purpose, Engine as _};
 use futures_util::future::LocalBoxFuture;
 use std::future::{ready, Ready};
 use std::task::{Context, Poll};
@@ -79,3 +81,183 @@ where
         })
     }
 }
+
+/// Guards the `/admin` scope: accepts `Authorization: Bearer <token>`,
+/// `X-Admin-Token: <token>` or HTTP Basic with the credentials from
+/// `server.admin`. Non-GET requests must also pass `same_origin`.
+/// Every request, allowed or not, goes to the audit log.
+pub struct AdminAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for AdminAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AdminAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AdminAuthMiddleware { service }))
+    }
+}
+
+pub struct AdminAuthMiddleware<S> {
+    service: S,
+}
+
+// Сравнение без раннего выхода, чтобы по времени ответа нельзя было подбирать токен
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Which credential matched, or None if the request is not authorized.
+fn check_admin_credentials(req: &ServiceRequest, admin: &crate::config::AdminConfig) -> Option<&'static str> {
+    let header_value = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+    };
+
+    if !admin.token.is_empty() {
+        if let Some(token) = header_value("X-Admin-Token") {
+            if constant_time_eq(token.as_bytes(), admin.token.as_bytes()) {
+                return Some("token");
+            }
+        }
+    }
+
+    let authorization = header_value(header::AUTHORIZATION.as_str())?;
+    let (scheme, value) = authorization.split_once(' ')?;
+    let value = value.trim();
+
+    if scheme.eq_ignore_ascii_case("Bearer") && !admin.token.is_empty() {
+        if constant_time_eq(value.as_bytes(), admin.token.as_bytes()) {
+            return Some("bearer");
+        }
+    } else if scheme.eq_ignore_ascii_case("Basic")
+        && !admin.username.is_empty()
+        && !admin.password.is_empty()
+    {
+        let decoded = general_purpose::STANDARD.decode(value).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (user, pass) = decoded.split_once(':')?;
+        let user_ok = constant_time_eq(user.as_bytes(), admin.username.as_bytes());
+        let pass_ok = constant_time_eq(pass.as_bytes(), admin.password.as_bytes());
+        if user_ok && pass_ok {
+            return Some("basic");
+        }
+    }
+    None
+}
+
+/// CSRF check for state-changing admin requests: Origin (or Referer) must be this
+/// host. Browsers send basic auth credentials on their own, so with basic auth a
+/// request carrying neither header is rejected; tokens are never sent implicitly.
+fn same_origin(req: &ServiceRequest, auth_method: &str) -> bool {
+    let host = req.connection_info().host().to_string();
+    let source = req
+        .headers()
+        .get(header::ORIGIN)
+        .or_else(|| req.headers().get(header::REFERER))
+        .and_then(|v| v.to_str().ok());
+    match source {
+        Some(url) => {
+            let without_scheme = url.split("://").nth(1).unwrap_or("");
+            without_scheme.split('/').next().unwrap_or("") == host
+        }
+        None => auth_method != "basic",
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for AdminAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let peer_addr = req
+            .connection_info()
+            .peer_addr()
+            .map(|addr| addr.split(':').next().unwrap_or(addr).to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+
+        let admin = req
+            .app_data::<web::Data<crate::AppState>>()
+            .map(|data| data.config.server.admin.clone())
+            .unwrap_or_default();
+
+        let auth_method = check_admin_credentials(&req, &admin);
+        let denied = if !admin.is_configured() {
+            Some(HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Admin access is not configured",
+                "details": "Set server.admin.token or server.admin.username/password in config.yml"
+            })))
+        } else if auth_method.is_none() {
+            Some(
+                HttpResponse::Unauthorized()
+                    .insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"admin\""))
+                    .json(serde_json::json!({
+                        "error": "Unauthorized",
+                        "details": "Valid admin token or basic auth credentials required"
+                    })),
+            )
+        } else if !matches!(req.method().as_str(), "GET" | "HEAD" | "OPTIONS")
+            && !same_origin(&req, auth_method.unwrap_or(""))
+        {
+            Some(HttpResponse::Forbidden().json(serde_json::json!({
+                "error": "Cross-site request rejected",
+                "details": "Admin forms must be submitted from the dashboard"
+            })))
+        } else {
+            None
+        };
+
+        if let Some(response) = denied {
+            crate::log::audit(&format!(
+                "{} {} from {} denied ({})",
+                method,
+                path,
+                peer_addr,
+                response.status().as_u16()
+            ));
+            let (request, _payload) = req.into_parts();
+            let service_response = ServiceResponse::new(request, response);
+            return Box::pin(async move { Ok(service_response.map_into_boxed_body()) });
+        }
+
+        let auth_method = auth_method.unwrap_or("unknown");
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            crate::log::audit(&format!(
+                "{} {} from {} via {} -> {}",
+                method,
+                path,
+                peer_addr,
+                auth_method,
+                res.status().as_u16()
+            ));
+            Ok(res.map_into_boxed_body())
+        })
+    }
+}
@@ -1,11 +1,16 @@
 pub mod actions;
+pub mod admin;
 pub mod additional;
 pub mod auth;
 pub mod auth_routes;
+pub mod captions;
 pub mod channel;
 pub mod frontend;
+pub mod live_chat;
 pub mod middleware;
 pub mod oauth;
 pub mod search;
+pub mod search_filters;
+pub mod storyboard;
 pub mod video;
 pub mod shorts;
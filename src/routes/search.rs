@@ -6,6 +6,24 @@ use serde_json;
 use std::collections::HashMap;
 use urlencoding;
 use utoipa::ToSchema;
+use lazy_static::lazy_static;
+use lru::LruCache;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use crate::routes::search_filters::SearchFilters;
+
+/// Continuation tokens for `page=N` with the time they were fetched.
+type CachedContinuations = (Vec<String>, u64);
+
+lazy_static! {
+    static ref SEARCH_CONTINUATION_CACHE: Arc<Mutex<LruCache<String, CachedContinuations>>> = Arc::new(
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(500).unwrap()))
+    );
+}
+
+const SEARCH_CONTINUATION_CACHE_DURATION: u64 = 1800;
+pub(crate) const MAX_SEARCH_PAGE: u32 = 20;
 
 fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
     if !config.server.main_url.is_empty() {
@@ -17,7 +35,7 @@ fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
     format!("{}://{}/", scheme, host.trim_end_matches('/'))
 }
 
-fn simplify_text(node: &serde_json::Value) -> String {
+pub(crate) fn simplify_text(node: &serde_json::Value) -> String {
     if node.is_null() {
         return String::new();
     }
@@ -37,22 +55,261 @@ fn simplify_text(node: &serde_json::Value) -> String {
     String::new()
 }
 
-fn find_video_renderers(obj: &serde_json::Value, out: &mut Vec<serde_json::Value>) {
+/// Обходит ответ /search и собирает результаты всех поддерживаемых типов
+/// в порядке выдачи YouTube (массивы обходятся по порядку, полки раскрываются на месте).
+fn collect_search_items(obj: &serde_json::Value, base_trimmed: &str, out: &mut Vec<SearchItem>) {
     if let Some(obj_map) = obj.as_object() {
-        if obj_map.contains_key("videoRenderer") {
-            out.push(obj_map["videoRenderer"].clone());
+        if let Some(vr) = obj_map.get("videoRenderer") {
+            out.extend(parse_video_renderer(vr, base_trimmed).map(SearchItem::Video));
+        } else if let Some(mr) = obj_map.get("movieRenderer") {
+            out.extend(parse_video_renderer(mr, base_trimmed).map(SearchItem::Movie));
+        } else if let Some(rr) = obj_map.get("reelItemRenderer") {
+            out.extend(parse_reel_item_renderer(rr, base_trimmed).map(SearchItem::Short));
+        } else if let Some(sl) = obj_map.get("shortsLockupViewModel") {
+            out.extend(parse_shorts_lockup(sl, base_trimmed).map(SearchItem::Short));
+        } else if let Some(cr) = obj_map.get("channelRenderer") {
+            out.extend(parse_channel_renderer(cr, base_trimmed).map(SearchItem::Channel));
+        } else if let Some(pr) = obj_map.get("playlistRenderer") {
+            out.extend(parse_playlist_renderer(pr, base_trimmed).map(SearchItem::Playlist));
+        } else if let Some(rr) = obj_map.get("radioRenderer") {
+            out.extend(parse_playlist_renderer(rr, base_trimmed).map(SearchItem::Mix));
+        } else if let Some(lv) = obj_map.get("lockupViewModel") {
+            out.extend(parse_lockup_view_model(lv, base_trimmed));
         } else {
-            for value in obj_map.values() {
-                find_video_renderers(value, out);
+            for (key, value) in obj_map {
+                // Правая колонка (карточки «в тему») к выдаче не относится
+                if key != "secondaryContents" {
+                    collect_search_items(value, base_trimmed, out);
+                }
             }
         }
     } else if let Some(arr) = obj.as_array() {
         for item in arr {
-            find_video_renderers(item, out);
+            collect_search_items(item, base_trimmed, out);
         }
     }
 }
 
+/// Первое строковое значение по ключу на любой глубине.
+fn find_str<'a>(obj: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    if let Some(obj_map) = obj.as_object() {
+        if let Some(value) = obj_map.get(key).and_then(|v| v.as_str()) {
+            return Some(value);
+        }
+        obj_map.values().find_map(|v| find_str(v, key))
+    } else if let Some(arr) = obj.as_array() {
+        arr.iter().find_map(|v| find_str(v, key))
+    } else {
+        None
+    }
+}
+
+/// Последняя (самая крупная) миниатюра из `thumbnails`, с протоколом.
+pub(crate) fn best_thumbnail(node: &serde_json::Value) -> String {
+    node.get("thumbnails")
+        .and_then(|t| t.as_array())
+        .and_then(|arr| arr.last())
+        .and_then(|t| t.get("url"))
+        .and_then(|u| u.as_str())
+        .map(|u| if u.starts_with("//") { format!("https:{}", u) } else { u.to_string() })
+        .unwrap_or_default()
+}
+
+pub(crate) fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() { None } else { Some(value) }
+}
+
+pub(crate) fn parse_reel_item_renderer(rr: &serde_json::Value, base_trimmed: &str) -> Option<ShortResult> {
+    let video_id = rr.get("videoId").and_then(|v| v.as_str())?.to_string();
+    let title = simplify_text(rr.get("headline").unwrap_or(&serde_json::Value::Null));
+    let views = simplify_text(rr.get("viewCountText").unwrap_or(&serde_json::Value::Null));
+    Some(ShortResult {
+        title: decode_label(&title),
+        thumbnail: format!("{}/thumbnail/{}", base_trimmed, video_id),
+        views: non_empty(decode_label(&views)),
+        video_id,
+    })
+}
+
+pub(crate) fn parse_shorts_lockup(sl: &serde_json::Value, base_trimmed: &str) -> Option<ShortResult> {
+    let video_id = sl
+        .pointer("/onTap/innertubeCommand/reelWatchEndpoint/videoId")
+        .and_then(|v| v.as_str())
+        .or_else(|| sl.get("entityId").and_then(|e| e.as_str()).and_then(|e| e.strip_prefix("shorts-shelf-item-")))?
+        .to_string();
+    let title = sl
+        .pointer("/overlayMetadata/primaryText/content")
+        .and_then(|t| t.as_str())
+        .unwrap_or("");
+    let views = sl
+        .pointer("/overlayMetadata/secondaryText/content")
+        .and_then(|t| t.as_str())
+        .unwrap_or("");
+    Some(ShortResult {
+        title: decode_label(title),
+        thumbnail: format!("{}/thumbnail/{}", base_trimmed, video_id),
+        views: non_empty(decode_label(views)),
+        video_id,
+    })
+}
+
+pub(crate) fn parse_channel_renderer(cr: &serde_json::Value, base_trimmed: &str) -> Option<ChannelResult> {
+    let channel_id = cr.get("channelId").and_then(|c| c.as_str())?.to_string();
+    let title = simplify_text(cr.get("title").unwrap_or(&serde_json::Value::Null));
+    let description = simplify_text(cr.get("descriptionSnippet").unwrap_or(&serde_json::Value::Null));
+    // С 2023 года YouTube кладёт @handle в subscriberCountText, а число подписчиков — в videoCountText
+    let mut subscribers = simplify_text(cr.get("subscriberCountText").unwrap_or(&serde_json::Value::Null));
+    let mut video_count = simplify_text(cr.get("videoCountText").unwrap_or(&serde_json::Value::Null));
+    let mut handle = String::new();
+    if subscribers.starts_with('@') {
+        handle = std::mem::take(&mut subscribers);
+        subscribers = std::mem::take(&mut video_count);
+    }
+    if handle.is_empty() {
+        handle = cr
+            .pointer("/navigationEndpoint/browseEndpoint/canonicalBaseUrl")
+            .and_then(|u| u.as_str())
+            .and_then(|u| u.strip_prefix('/'))
+            .filter(|u| u.starts_with('@'))
+            .unwrap_or("")
+            .to_string();
+    }
+    Some(ChannelResult {
+        title: decode_label(&title),
+        thumbnail: format!("{}/channel_icon/{}", base_trimmed, channel_id),
+        handle: non_empty(handle),
+        subscribers: non_empty(decode_label(&subscribers)),
+        video_count: non_empty(decode_label(&video_count)),
+        description: non_empty(decode_label(&description)),
+        channel_id,
+    })
+}
+
+/// playlistRenderer и radioRenderer (микс) устроены одинаково.
+pub(crate) fn parse_playlist_renderer(pr: &serde_json::Value, base_trimmed: &str) -> Option<PlaylistResult> {
+    let playlist_id = pr.get("playlistId").and_then(|p| p.as_str())?.to_string();
+    let title = simplify_text(pr.get("title").unwrap_or(&serde_json::Value::Null));
+    let author = simplify_text(
+        pr.get("shortBylineText")
+            .or_else(|| pr.get("longBylineText"))
+            .unwrap_or(&serde_json::Value::Null),
+    );
+    let channel_id = pr
+        .get("shortBylineText")
+        .and_then(|b| find_str(b, "browseId"))
+        .map(|b| b.to_string());
+    let video_id = pr
+        .pointer("/navigationEndpoint/watchEndpoint/videoId")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    let mut video_count = pr
+        .get("videoCount")
+        .and_then(|c| c.as_str())
+        .map(|c| c.to_string())
+        .unwrap_or_default();
+    if video_count.is_empty() {
+        video_count = simplify_text(pr.get("videoCountText").unwrap_or(&serde_json::Value::Null));
+    }
+    let thumbnail = match &video_id {
+        Some(id) => format!("{}/thumbnail/{}", base_trimmed, id),
+        None => pr
+            .get("thumbnails")
+            .and_then(|t| t.as_array())
+            .and_then(|arr| arr.first())
+            .or_else(|| pr.get("thumbnail"))
+            .map(best_thumbnail)
+            .unwrap_or_default(),
+    };
+    Some(PlaylistResult {
+        title: decode_label(&title),
+        author: non_empty(decode_label(&author)),
+        channel_id,
+        video_id,
+        thumbnail,
+        video_count: non_empty(decode_label(&video_count)),
+        playlist_id,
+    })
+}
+
+/// Новый формат карточек (lockupViewModel): плейлисты, миксы и иногда видео.
+pub(crate) fn parse_lockup_view_model(lv: &serde_json::Value, base_trimmed: &str) -> Option<SearchItem> {
+    let content_id = lv.get("contentId").and_then(|c| c.as_str())?.to_string();
+    let content_type = lv.get("contentType").and_then(|c| c.as_str()).unwrap_or("");
+    let metadata = lv.pointer("/metadata/lockupMetadataViewModel").unwrap_or(&serde_json::Value::Null);
+    let title = metadata
+        .pointer("/title/content")
+        .and_then(|t| t.as_str())
+        .unwrap_or("");
+
+    if content_type == "LOCKUP_CONTENT_TYPE_VIDEO" {
+        return Some(SearchItem::Video(SearchResult {
+            title: decode_label(title),
+            author: String::new(),
+            video_id: Some(content_id.clone()),
+            channel_id: None,
+            playlist_id: None,
+            thumbnail: format!("{}/thumbnail/{}", base_trimmed, content_id),
+            channel_thumbnail: format!("{}/channel_icon/{}", base_trimmed, content_id),
+            duration: None,
+            description: None,
+            views: None,
+            published: None,
+        }));
+    }
+    if content_type != "LOCKUP_CONTENT_TYPE_PLAYLIST" {
+        return None;
+    }
+
+    // Первая строка метаданных — автор (со ссылкой на канал)
+    let first_part = metadata
+        .pointer("/metadata/contentMetadataViewModel/metadataRows/0/metadataParts/0")
+        .unwrap_or(&serde_json::Value::Null);
+    let author = first_part
+        .pointer("/text/content")
+        .and_then(|t| t.as_str())
+        .unwrap_or("");
+    let channel_id = find_str(first_part, "browseId").map(|b| b.to_string());
+    let video_id = lv
+        .pointer("/rendererContext/commandContext/onTap/innertubeCommand/watchEndpoint/videoId")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    let video_count = lv
+        .get("contentImage")
+        .and_then(|img| {
+            img.get("collectionThumbnailViewModel")
+                .and_then(|c| c.get("primaryThumbnail"))
+                .unwrap_or(img)
+                .get("thumbnailViewModel")
+                .and_then(|t| t.get("overlays"))
+        })
+        .and_then(|o| find_str(o, "text"))
+        .unwrap_or("");
+    let thumbnail = match &video_id {
+        Some(id) => format!("{}/thumbnail/{}", base_trimmed, id),
+        None => lv
+            .get("contentImage")
+            .and_then(|img| find_str(img, "url"))
+            .unwrap_or("")
+            .to_string(),
+    };
+
+    let playlist = PlaylistResult {
+        title: decode_label(title),
+        author: non_empty(decode_label(author)),
+        channel_id,
+        video_id,
+        thumbnail,
+        video_count: non_empty(decode_label(video_count)),
+        playlist_id: content_id.clone(),
+    };
+    // Миксы — автоматические плейлисты с идентификатором RD...
+    if content_id.starts_with("RD") {
+        Some(SearchItem::Mix(playlist))
+    } else {
+        Some(SearchItem::Playlist(playlist))
+    }
+}
+
 fn parse_video_renderer(vr: &serde_json::Value, base_trimmed: &str) -> Option<SearchResult> {
     let video_id = vr.get("videoId").and_then(|v| v.as_str())?.to_string();
 
@@ -88,7 +345,12 @@ fn parse_video_renderer(vr: &serde_json::Value, base_trimmed: &str) -> Option<Se
     let duration = simplify_text(&vr.get("lengthText").unwrap_or(&serde_json::Value::Null));
     let views = simplify_text(&vr.get("viewCountText").unwrap_or(&serde_json::Value::Null));
     let published = simplify_text(&vr.get("publishedTimeText").unwrap_or(&serde_json::Value::Null));
-    let author = simplify_text(&vr.get("ownerText").unwrap_or(&serde_json::Value::Null));
+    // У movieRenderer автор лежит в longBylineText
+    let author = simplify_text(
+        vr.get("ownerText")
+            .or_else(|| vr.get("longBylineText"))
+            .unwrap_or(&serde_json::Value::Null),
+    );
 
     let thumbnail = format!("{}/thumbnail/{}", base_trimmed, video_id);
     
@@ -108,8 +370,8 @@ fn parse_video_renderer(vr: &serde_json::Value, base_trimmed: &str) -> Option<Se
         channel_thumbnail,
         duration: if !duration.is_empty() { Some(duration) } else { None },
         description: if !description.is_empty() { Some(decode_label(&description)) } else { None },
-        views: if !views.is_empty() { Some(decode_label(&views)) } else { None },
-        published: if !published.is_empty() { Some(decode_label(&published)) } else { None },
+        views: crate::locale::parse_count(&decode_label(&views)).map(|n| n.to_string()),
+        published: if !published.is_empty() { Some(crate::locale::normalize_relative_time(&decode_label(&published))) } else { None },
     })
 }
 
@@ -139,7 +401,7 @@ fn parse_iso_duration(iso: &str) -> String {
     }
 }
 
-fn decode_label(value: &str) -> String {
+pub(crate) fn decode_label(value: &str) -> String {
     let decoded = urlencoding::decode(value)
         .unwrap_or_else(|_| value.into())
         .to_string();
@@ -184,6 +446,82 @@ pub struct SearchResult {
     pub published: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ShortResult {
+    pub video_id: String,
+    pub title: String,
+    pub thumbnail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub views: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ChannelResult {
+    pub channel_id: String,
+    pub title: String,
+    pub thumbnail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscribers: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_count: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PlaylistResult {
+    pub playlist_id: String,
+    pub title: String,
+    pub thumbnail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    /// Первое видео плейлиста (для ссылки /watch?v=...&list=...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_count: Option<String>,
+}
+
+/// Один результат поиска; `type` указывает вид, остальные поля — как у соответствующей структуры.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchItem {
+    Video(SearchResult),
+    Short(ShortResult),
+    Channel(ChannelResult),
+    Playlist(PlaylistResult),
+    Mix(PlaylistResult),
+    Movie(SearchResult),
+}
+
+impl SearchItem {
+    /// Подходит ли результат под `type=` (video, channel, playlist, all).
+    fn matches_type(&self, search_type: &str) -> bool {
+        match search_type {
+            "all" => true,
+            "video" => matches!(self, SearchItem::Video(_)),
+            "channel" => matches!(self, SearchItem::Channel(_)),
+            "playlist" => matches!(self, SearchItem::Playlist(_) | SearchItem::Mix(_)),
+            _ => false,
+        }
+    }
+}
+
+/// Страница результатов поиска с токеном продолжения (`paged=1` или `continuation=`).
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SearchPage {
+    pub results: Vec<SearchItem>,
+    /// Номер страницы при запросе через `page`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    /// Pass back as `continuation` for the next page; `null` on the last page.
+    pub continuation: Option<String>,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct CategoryItem {
     pub id: String,
@@ -221,10 +559,12 @@ pub struct PlaylistResponse {
     get,
     path = "/get_top_videos.php",
     params(
-        ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)")
+        ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
-        (status = 200, description = "List of top videos", body = [TopVideo]),
+        (status = 200, description = "List of top videos (Data API chart=mostPopular, innertube FEtrending as fallback)", body = [TopVideo]),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -246,86 +586,325 @@ pub async fn get_top_videos(req: HttpRequest, data: web::Data<crate::AppState>)
         .unwrap_or(config.video.default_count as i32);
 
     let count = count.min(50).max(1);
+    let locale = crate::locale::Locale::from_request(&req, config);
+    let session = crate::visitor_data::session_key(&req);
+
+    let result = with_innertube_fallback(
+        "top videos",
+        config.api.prefer_innertube,
+        data_api_popular_videos(config, &locale, base.trim_end_matches('/'), count, None),
+        innertube_popular_videos(config, &locale, session.as_deref(), base.trim_end_matches('/'), count as usize, None),
+    )
+    .await;
+
+    match result {
+        Ok(top_videos) => HttpResponse::Ok().json(top_videos),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to call YouTube API",
+            "details": e
+        })),
+    }
+}
+
+/// Сначала основной источник (Data API или innertube при `api.prefer_innertube`),
+/// при ошибке — второй. Ошибка возвращается, только если не сработали оба.
+async fn with_innertube_fallback<T>(
+    what: &str,
+    prefer_innertube: bool,
+    data_api: impl std::future::Future<Output = Result<T, String>>,
+    innertube: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    if prefer_innertube {
+        match innertube.await {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                crate::log::info!("Innertube {} failed, falling back to Data API: {}", what, e);
+                data_api.await
+            }
+        }
+    } else {
+        match data_api.await {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                crate::log::info!("Data API {} failed, falling back to innertube: {}", what, e);
+                innertube.await
+            }
+        }
+    }
+}
 
-    let apikey = config.get_api_key_rotated();
+/// GET к Data API v3 с учётом квоты ключа; ответ с полем `error`
+/// (квота, неверный ключ) считается ошибкой и может отправить ключ в карантин.
+async fn data_api_get(apikey: &str, url: &str) -> Result<serde_json::Value, String> {
+    crate::api_quota::record_call(apikey, "GET", url);
+    let response = Client::new()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Error calling YouTube API: {}", e))?;
+    let json_data: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Error parsing YouTube API response: {}", e))?;
+    if let Some(error) = json_data.get("error") {
+        if let Some(reason) = crate::api_quota::error_reason(&json_data) {
+            crate::api_quota::record_error(apikey, &reason);
+        }
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("YouTube API error: {}", message));
+    }
+    Ok(json_data)
+}
 
-    let client = Client::new();
+/// chart=mostPopular, опционально по категории (get_top_videos, get-categories_videos).
+async fn data_api_popular_videos(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    base_trimmed: &str,
+    count: i32,
+    category_id: Option<&str>,
+) -> Result<Vec<TopVideo>, String> {
+    let apikey = config.get_api_key_rotated(1);
+    if apikey.is_empty() {
+        return Err("No Data API key configured".to_string());
+    }
 
-    let url = format!(
-        "https://www.googleapis.com/youtube/v3/videos?part=snippet,contentDetails&chart=mostPopular&maxResults={}&key={}",
+    let mut url = format!(
+        "https://www.googleapis.com/youtube/v3/videos?part=snippet,contentDetails&chart=mostPopular&maxResults={}&regionCode={}&hl={}&key={}",
         count,
+        locale.gl,
+        urlencoding::encode(&locale.hl),
         apikey
     );
+    if let Some(cat) = category_id {
+        url.push_str(&format!("&videoCategoryId={}", cat));
+    }
 
-    match client.get(&url).send().await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
-            Ok(json_data) => {
-                let mut top_videos: Vec<TopVideo> = Vec::new();
-
-                if let Some(items) = json_data.get("items").and_then(|i| i.as_array()) {
-                    for video in items {
-                        if let (Some(video_info), Some(video_id)) = (
-                            video.get("snippet"),
-                            video.get("id").and_then(|id| id.as_str()),
-                        ) {
-                            let channel_id = video_info
-                                .get("channelId")
-                                .and_then(|c| c.as_str())
-                                .unwrap_or(video_id);
-                            let title = video_info
-                                .get("title")
-                                .and_then(|t| t.as_str())
-                                .unwrap_or("Unknown Title");
-                            let title = decode_label(title);
-
-                            let author = video_info
-                                .get("channelTitle")
-                                .and_then(|a| a.as_str())
-                                .unwrap_or("Unknown Author")
-                                .to_string();
-
-                            let thumbnail =
-                                format!("{}/thumbnail/{}", base.trim_end_matches('/'), video_id);
-
-                            let channel_thumbnail = format!(
-                                "{}/channel_icon/{}",
-                                base.trim_end_matches('/'),
-                                channel_id
-                            );
-
-                            let duration = video
-                                .get("contentDetails")
-                                .and_then(|c| c.get("duration"))
-                                .and_then(|d| d.as_str())
-                                .map(parse_iso_duration)
-                                .unwrap_or_else(|| "0:00".to_string());
-
-                            top_videos.push(TopVideo {
-                                title,
-                                author,
-                                video_id: video_id.to_string(),
-                                thumbnail,
-                                channel_thumbnail,
-                                duration,
-                            });
-                        }
-                    }
-                }
+    let json_data = data_api_get(&apikey, &url).await?;
+    let mut top_videos: Vec<TopVideo> = Vec::new();
+
+    if let Some(items) = json_data.get("items").and_then(|i| i.as_array()) {
+        for video in items {
+            if let (Some(video_info), Some(video_id)) = (
+                video.get("snippet"),
+                video.get("id").and_then(|id| id.as_str()),
+            ) {
+                let channel_id = video_info
+                    .get("channelId")
+                    .and_then(|c| c.as_str())
+                    .unwrap_or(video_id);
+                // snippet.localized учитывает hl
+                let title = video_info
+                    .get("localized")
+                    .and_then(|l| l.get("title"))
+                    .or_else(|| video_info.get("title"))
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("Unknown Title");
+                let title = decode_label(title);
+
+                let author = video_info
+                    .get("channelTitle")
+                    .and_then(|a| a.as_str())
+                    .unwrap_or("Unknown Author")
+                    .to_string();
+
+                let thumbnail = format!("{}/thumbnail/{}", base_trimmed, video_id);
+                let channel_thumbnail = format!("{}/channel_icon/{}", base_trimmed, channel_id);
+
+                let duration = video
+                    .get("contentDetails")
+                    .and_then(|c| c.get("duration"))
+                    .and_then(|d| d.as_str())
+                    .map(parse_iso_duration)
+                    .unwrap_or_else(|| "0:00".to_string());
+
+                top_videos.push(TopVideo {
+                    title,
+                    author,
+                    video_id: video_id.to_string(),
+                    thumbnail,
+                    channel_thumbnail,
+                    duration,
+                });
+            }
+        }
+    }
+
+    Ok(top_videos)
+}
 
-                HttpResponse::Ok().json(top_videos)
+/// Категории Data API, у которых есть вкладка на странице трендов (id, params вкладки, название).
+const TRENDING_CATEGORIES: [(&str, &str, &str); 3] = [
+    ("10", "4gINGgt5dG1hX2NoYXJ0cw==", "Music"),
+    ("20", "4gIcGhpnYW1pbmdfY29ycHVzX21vc3RfcG9wdWxhcg==", "Gaming"),
+    ("1", "4gIKGgh0cmFpbGVycw==", "Film & Animation"),
+];
+
+/// POST /youtubei/v1/browse WEB-клиентом; `body` дополняет payload (browseId, params, continuation).
+async fn innertube_browse(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    session: Option<&str>,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let innertube_key = config
+        .get_innertube_key()
+        .ok_or_else(|| "Missing innertube_key in config.yml".to_string())?;
+
+    let mut payload = serde_json::json!({
+        "context": {
+            "client": {
+                "clientName": "WEB",
+                "clientVersion": "2.20250101"
             }
-            Err(e) => {
-                crate::log::info!("Error parsing YouTube API response: {}", e);
-                HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse YouTube API response"
-                }))
+        }
+    });
+    if let (Some(target), Some(fields)) = (payload.as_object_mut(), body.as_object()) {
+        for (key, value) in fields {
+            target.insert(key.clone(), value.clone());
+        }
+    }
+    locale.apply(&mut payload);
+    let visitor = crate::visitor_data::visitor_data(config, session).await;
+    crate::visitor_data::attach(&mut payload, visitor.as_deref());
+
+    let url = format!("https://www.youtube.com/youtubei/v1/browse?key={}", innertube_key);
+    let response = locale
+        .with_header(crate::visitor_data::with_header(Client::new().post(&url), visitor.as_deref()))
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/117.0.0.0 Safari/537.36")
+        .header("X-YouTube-Client-Name", "1")
+        .header("X-YouTube-Client-Version", "2.20250101")
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Error calling InnerTube browse: {}", e))?;
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Error parsing InnerTube browse response: {}", e))
+}
+
+fn browse_top_videos(json_data: &serde_json::Value, base_trimmed: &str, count: usize) -> Vec<TopVideo> {
+    let mut items = Vec::new();
+    collect_search_items(json_data, base_trimmed, &mut items);
+    let mut seen = std::collections::HashSet::new();
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            SearchItem::Video(v) => Some(v),
+            _ => None,
+        })
+        .filter_map(|v| {
+            let video_id = v.video_id?;
+            if !seen.insert(video_id.clone()) {
+                return None;
             }
+            Some(TopVideo {
+                title: v.title,
+                author: v.author,
+                video_id,
+                thumbnail: v.thumbnail,
+                channel_thumbnail: v.channel_thumbnail,
+                duration: v.duration.unwrap_or_else(|| "0:00".to_string()),
+            })
+        })
+        .take(count)
+        .collect()
+}
+
+/// Тренды через innertube (FEtrending, для категорий — вкладка трендов).
+async fn innertube_popular_videos(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    session: Option<&str>,
+    base_trimmed: &str,
+    count: usize,
+    category_id: Option<&str>,
+) -> Result<Vec<TopVideo>, String> {
+    // Категории без вкладки трендов отдавать общими трендами нельзя — это были бы
+    // чужие видео под её названием; ошибка даёт вызывающему перейти на Data API
+    let params = match category_id.filter(|id| !id.is_empty()) {
+        Some(id) => match TRENDING_CATEGORIES.iter().find(|(cat, _, _)| *cat == id) {
+            Some((_, p, _)) => Some(*p),
+            None => return Err(format!("No innertube trending tab for category {}", id)),
         },
+        None => None,
+    };
+
+    let mut body = serde_json::json!({ "browseId": "FEtrending" });
+    if let Some(p) = params {
+        body["params"] = serde_json::Value::String(p.to_string());
+    }
+    let json_data = innertube_browse(config, locale, session, body).await?;
+    // Главную (FEwhat_to_watch) вместо трендов не подставляем: она персональная
+    // для visitor ID и к «популярному» отношения не имеет
+    let videos = browse_top_videos(&json_data, base_trimmed, count);
+
+    if videos.is_empty() {
+        Err("InnerTube returned no videos".to_string())
+    } else {
+        Ok(videos)
+    }
+}
+
+/// Ищет токен следующей страницы в ответе /search (первая страница и continuation).
+pub(crate) fn find_search_continuation(obj: &serde_json::Value) -> Option<String> {
+    if let Some(obj_map) = obj.as_object() {
+        if let Some(token) = obj_map
+            .get("continuationItemRenderer")
+            .and_then(|c| c.pointer("/continuationEndpoint/continuationCommand/token"))
+            .and_then(|t| t.as_str())
+        {
+            return Some(token.to_string());
+        }
+        for value in obj_map.values() {
+            if let Some(token) = find_search_continuation(value) {
+                return Some(token);
+            }
+        }
+    } else if let Some(arr) = obj.as_array() {
+        for item in arr {
+            if let Some(token) = find_search_continuation(item) {
+                return Some(token);
+            }
+        }
+    }
+    None
+}
+
+async fn fetch_search_page(
+    client: &Client,
+    url: &str,
+    payload: &serde_json::Value,
+    locale: &crate::locale::Locale,
+    visitor: Option<&str>,
+) -> Result<serde_json::Value, &'static str> {
+    let headers = [
+        ("Content-Type", "application/json"),
+        ("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/117.0.0.0 Safari/537.36"),
+        ("X-YouTube-Client-Name", "1"),
+        ("X-YouTube-Client-Version", "2.20250101"),
+    ];
+
+    let mut request_builder = locale
+        .with_header(crate::visitor_data::with_header(client.post(url), visitor))
+        .json(payload);
+    for (key, value) in &headers {
+        request_builder = request_builder.header(*key, *value);
+    }
+
+    match request_builder.send().await {
+        Ok(response) => response.json::<serde_json::Value>().await.map_err(|e| {
+            crate::log::info!("Error parsing InnerTube response: {}", e);
+            "Failed to parse InnerTube response"
+        }),
         Err(e) => {
-            crate::log::info!("Error calling YouTube API: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to call YouTube API"
-            }))
+            crate::log::info!("Error calling InnerTube API: {}", e);
+            Err("Failed to call InnerTube API")
         }
     }
 }
@@ -334,13 +913,22 @@ pub async fn get_top_videos(req: HttpRequest, data: web::Data<crate::AppState>)
     get,
     path = "/get_search_videos.php",
     params(
-        ("query" = String, Query, description = "Search query"),
+        ("query" = String, Query, description = "Search query (not needed together with continuation)"),
         ("count" = Option<i32>, Query, description = "Number of results to return (default: 50)"),
-        ("type" = Option<String>, Query, description = "Type of search results (video, channel, playlist) (default: video)")
+        ("type" = Option<String>, Query, description = "Type of search results: video, channel, playlist (playlists and mixes) or all (every result type in YouTube's order) (default: video)"),
+        ("sort" = Option<SearchSort>, Query, description = "Sort order: relevance, date, views, rating (default: relevance)"),
+        ("upload_date" = Option<UploadDate>, Query, description = "Upload date filter: hour, today, week, month, year"),
+        ("duration" = Option<SearchDuration>, Query, description = "Duration bucket: short (< 4 min), medium (4-20 min), long (> 20 min)"),
+        ("features" = Option<String>, Query, description = "Comma-separated features: hd, 4k, subtitles, live, creative_commons, 360"),
+        ("page" = Option<u32>, Query, description = "Page number for legacy clients, 1-20 (default: 1). Continuations are walked server-side and cached"),
+        ("continuation" = Option<String>, Query, description = "Continuation token from a previous SearchPage response; implies paged=1"),
+        ("paged" = Option<bool>, Query, description = "Return a SearchPage object with the next continuation token instead of a plain array"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
-        (status = 200, description = "List of search results", body = [SearchResult]),
-        (status = 400, description = "Missing query parameter"),
+        (status = 200, description = "List of search results, or a SearchPage when paged=1 or continuation is set", body = [SearchItem]),
+        (status = 400, description = "Missing query parameter or invalid filter value"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -364,11 +952,20 @@ pub async fn get_search_videos(
         }
     }
 
+    // Токен продолжения: '+' в base64 мог превратиться в пробел при разборе
+    let continuation = query_params
+        .get("continuation")
+        .map(|c| c.trim().replace(' ', "+"))
+        .filter(|c| !c.is_empty());
+    let paged = continuation.is_some()
+        || matches!(query_params.get("paged").map(|p| p.as_str()), Some("1") | Some("true"));
+
     let query = match query_params.get("query") {
         Some(q) => {
             let decoded_entity = decode_html_entities(q);
             decoded_entity.to_string()
         },
+        None if continuation.is_some() => String::new(),
         None => {
             return HttpResponse::BadRequest().json(serde_json::json!({
                 "error": "query parameter not specified"
@@ -381,18 +978,39 @@ pub async fn get_search_videos(
         .and_then(|c| c.parse().ok())
         .unwrap_or(config.video.default_count as usize);
 
+    let page: u32 = match query_params.get("page").filter(|p| !p.is_empty()) {
+        Some(p) => match p.parse::<u32>() {
+            Ok(n) if (1..=MAX_SEARCH_PAGE).contains(&n) => n,
+            _ => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Invalid page parameter. Must be between 1 and {}", MAX_SEARCH_PAGE)
+                }));
+            }
+        },
+        None => 1,
+    };
+
     let search_type = query_params
         .get("type")
         .map(|t| t.as_str())
         .unwrap_or("video");
 
-    let valid_types = ["video", "channel", "playlist"];
+    let valid_types = ["video", "channel", "playlist", "all"];
     if !valid_types.contains(&search_type) {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "error": format!("Invalid type parameter. Must be one of: {}", valid_types.join(", "))
         }));
     }
 
+    let filters = match SearchFilters::from_query(&query_params) {
+        Ok(f) => f,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": e
+            }));
+        }
+    };
+
     let innertube_key = match config.get_innertube_key() {
         Some(key) => key,
         None => {
@@ -404,64 +1022,129 @@ pub async fn get_search_videos(
 
     let client = Client::new();
 
-    let payload = serde_json::json!({
+    let mut context_payload = serde_json::json!({
         "context": {
             "client": {
                 "clientName": "WEB",
-                "clientVersion": "2.20250101",
-                "hl": "ru",
-                "gl": "RU"
+                "clientVersion": "2.20250101"
             }
-        },
-        "query": query
+        }
     });
+    let locale = crate::locale::Locale::from_request(&req, config);
+    locale.apply(&mut context_payload);
+    let visitor = crate::visitor_data::visitor_data(config, crate::visitor_data::session_key(&req).as_deref()).await;
+    crate::visitor_data::attach(&mut context_payload, visitor.as_deref());
+
+    let search_params = filters.to_params();
+    let mut initial_payload = context_payload.clone();
+    initial_payload["query"] = serde_json::Value::String(query.clone());
+    if let Some(params) = &search_params {
+        initial_payload["params"] = serde_json::Value::String(params.clone());
+    }
+    let continuation_payload = |token: &str| {
+        let mut p = context_payload.clone();
+        p["continuation"] = serde_json::Value::String(token.to_string());
+        p
+    };
 
     let url = format!(
         "https://www.youtube.com/youtubei/v1/search?key={}",
         innertube_key
     );
 
-    let headers = [
-        ("Content-Type", "application/json"),
-        ("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/117.0.0.0 Safari/537.36"),
-        ("Accept-Language", "ru-RU,ru;q=0.9,en-US;q=0.8,en;q=0.7"),
-        ("X-YouTube-Client-Name", "1"),
-        ("X-YouTube-Client-Version", "2.20250101"),
-    ];
-
-    let mut request_builder = client.post(&url).json(&payload);
-    for (key, value) in &headers {
-        request_builder = request_builder.header(*key, *value);
-    }
-
-    match request_builder.send().await
-    {
-        Ok(response) => match response.json::<serde_json::Value>().await {
-            Ok(json_data) => {
-                let mut search_results: Vec<SearchResult> = Vec::new();
-                let mut video_renderers = Vec::new();
-                find_video_renderers(&json_data, &mut video_renderers);
-                for vr in video_renderers.iter().take(count) {
-                    if let Some(result) = parse_video_renderer(vr, base_trimmed) {
-                        search_results.push(result);
-                    }
+    let fetched = if let Some(token) = &continuation {
+        fetch_search_page(&client, &url, &continuation_payload(token), &locale, visitor.as_deref())
+            .await
+            .map(|json| {
+                let next = find_search_continuation(&json);
+                (Some(json), next)
+            })
+    } else {
+        // page=N: идём по цепочке continuation от первой страницы,
+        // известные токены берём из кэша. tokens[i] открывает страницу i + 2.
+        let cache_key = format!(
+            "{}|{}|{}|{}",
+            query,
+            search_params.as_deref().unwrap_or(""),
+            locale.hl,
+            locale.gl
+        );
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut tokens: Vec<String> = {
+            let mut cache = SEARCH_CONTINUATION_CACHE.lock().await;
+            match cache.get(&cache_key) {
+                Some((cached, timestamp)) if now - *timestamp < SEARCH_CONTINUATION_CACHE_DURATION => cached.clone(),
+                _ => Vec::new(),
+            }
+        };
+        let known = tokens.len();
+        let target = (page - 1) as usize;
+
+        let result = async {
+            if target == 0 {
+                let json = fetch_search_page(&client, &url, &initial_payload, &locale, visitor.as_deref()).await?;
+                if tokens.is_empty() {
+                    tokens.extend(find_search_continuation(&json));
                 }
-
-                HttpResponse::Ok().json(search_results)
+                return Ok(Some(json));
             }
-            Err(e) => {
-                crate::log::info!("Error parsing InnerTube response: {}", e);
-                HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse InnerTube response"
-                }))
+            if tokens.is_empty() {
+                let json = fetch_search_page(&client, &url, &initial_payload, &locale, visitor.as_deref()).await?;
+                tokens.extend(find_search_continuation(&json));
             }
-        },
+            while !tokens.is_empty() && tokens.len() < target {
+                let last = tokens[tokens.len() - 1].clone();
+                let json = fetch_search_page(&client, &url, &continuation_payload(&last), &locale, visitor.as_deref()).await?;
+                match find_search_continuation(&json) {
+                    Some(next) => tokens.push(next),
+                    None => break,
+                }
+            }
+            if tokens.len() < target {
+                // Результаты закончились раньше запрошенной страницы
+                return Ok(None);
+            }
+            let json = fetch_search_page(&client, &url, &continuation_payload(&tokens[target - 1]), &locale, visitor.as_deref()).await?;
+            if tokens.len() == target {
+                tokens.extend(find_search_continuation(&json));
+            }
+            Ok(Some(json))
+        }
+        .await;
+
+        if tokens.len() > known {
+            SEARCH_CONTINUATION_CACHE.lock().await.put(cache_key, (tokens.clone(), now));
+        }
+        result.map(|json| (json, tokens.get(target).cloned()))
+    };
+
+    let (json_data, next_continuation) = match fetched {
+        Ok(v) => v,
         Err(e) => {
-            crate::log::info!("Error calling InnerTube API: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to call InnerTube API"
-            }))
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }));
         }
+    };
+
+    let mut search_results: Vec<SearchItem> = Vec::new();
+    if let Some(json_data) = &json_data {
+        collect_search_items(json_data, base_trimmed, &mut search_results);
+        search_results.retain(|item| item.matches_type(search_type));
+        search_results.truncate(count);
+    }
+
+    if paged {
+        HttpResponse::Ok().json(SearchPage {
+            results: search_results,
+            page: if continuation.is_none() { Some(page) } else { None },
+            continuation: next_continuation,
+        })
+    } else {
+        HttpResponse::Ok().json(search_results)
     }
 }
 
@@ -469,7 +1152,9 @@ pub async fn get_search_videos(
     get,
     path = "/get_search_suggestions.php",
     params(
-        ("query" = String, Query, description = "Search query for suggestions")
+        ("query" = String, Query, description = "Search query for suggestions"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Search suggestions", body = SearchSuggestions),
@@ -479,8 +1164,9 @@ pub async fn get_search_videos(
 )]
 pub async fn get_search_suggestions(
     req: HttpRequest,
-    _data: web::Data<crate::AppState>,
+    data: web::Data<crate::AppState>,
 ) -> impl Responder {
+    let locale = crate::locale::Locale::from_request(&req, &data.config);
     let mut query_params: HashMap<String, String> = HashMap::new();
     for pair in req.query_string().split('&') {
         let mut parts = pair.split('=');
@@ -505,7 +1191,8 @@ pub async fn get_search_suggestions(
 
     let encoded_query = urlencoding::encode(query);
     let url = format!(
-        "https://clients1.google.com/complete/search?client=youtube&hl=en&ds=yt&q={}",
+        "https://clients1.google.com/complete/search?client=youtube&ds=yt&{}&q={}",
+        locale.query(),
         encoded_query
     );
 
@@ -564,15 +1251,18 @@ pub async fn get_search_suggestions(
     get,
     path = "/get-categories.php",
     params(
-        ("region" = Option<String>, Query, description = "Region code (default: US)")
+        ("region" = Option<String>, Query, description = "Region code (default: gl)"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
-        (status = 200, description = "List of categories", body = [CategoryItem]),
+        (status = 200, description = "List of categories (innertube fallback: trending tabs Music, Gaming, Film & Animation)", body = [CategoryItem]),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn get_categories(req: HttpRequest, data: web::Data<crate::AppState>) -> impl Responder {
     let config = &data.config;
+    let locale = crate::locale::Locale::from_request(&req, config);
     let region = req
         .query_string()
         .split('&')
@@ -584,54 +1274,109 @@ pub async fn get_categories(req: HttpRequest, data: web::Data<crate::AppState>)
                 None
             }
         })
-        .unwrap_or_else(|| "US".to_string());
+        .unwrap_or_else(|| locale.gl.clone());
+    let session = crate::visitor_data::session_key(&req);
+    let region_locale = crate::locale::Locale {
+        hl: locale.hl.clone(),
+        gl: region.to_uppercase(),
+    };
 
-    let apikey = config.get_api_key_rotated();
+    let result = with_innertube_fallback(
+        "categories",
+        config.api.prefer_innertube,
+        data_api_categories(config, &locale, &region),
+        innertube_categories(config, &region_locale, session.as_deref()),
+    )
+    .await;
+
+    match result {
+        Ok(categories) => HttpResponse::Ok().json(categories),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to call categories API",
+            "details": e
+        })),
+    }
+}
+
+async fn data_api_categories(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    region: &str,
+) -> Result<Vec<CategoryItem>, String> {
+    let apikey = config.get_api_key_rotated(1);
+    if apikey.is_empty() {
+        return Err("No Data API key configured".to_string());
+    }
     let url = format!(
-        "https://www.googleapis.com/youtube/v3/videoCategories?part=snippet&regionCode={}&key={}",
-        region, apikey
+        "https://www.googleapis.com/youtube/v3/videoCategories?part=snippet&regionCode={}&hl={}&key={}",
+        region,
+        urlencoding::encode(&locale.hl),
+        apikey
     );
 
-    let client = Client::new();
-    match client.get(&url).send().await {
-        Ok(resp) => match resp.json::<serde_json::Value>().await {
-            Ok(json_data) => {
-                let mut categories = Vec::new();
-                if let Some(items) = json_data.get("items").and_then(|i| i.as_array()) {
-                    for item in items {
-                        if let (Some(id), Some(snippet)) =
-                            (item.get("id").and_then(|i| i.as_str()), item.get("snippet"))
-                        {
-                            let title = snippet
-                                .get("title")
-                                .and_then(|t| t.as_str())
-                                .unwrap_or("");
-                            let title = decode_label(title);
-
-                            categories.push(CategoryItem {
-                                id: id.to_string(),
-                                title,
-                            });
-                        }
-                    }
-                }
-
-                HttpResponse::Ok().json(categories)
-            }
-            Err(e) => {
-                crate::log::info!("Error parsing categories response: {}", e);
-                HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse categories response"
-                }))
+    let json_data = data_api_get(&apikey, &url).await?;
+    let mut categories = Vec::new();
+    if let Some(items) = json_data.get("items").and_then(|i| i.as_array()) {
+        for item in items {
+            if let (Some(id), Some(snippet)) =
+                (item.get("id").and_then(|i| i.as_str()), item.get("snippet"))
+            {
+                let title = snippet
+                    .get("title")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("");
+                let title = decode_label(title);
+
+                categories.push(CategoryItem {
+                    id: id.to_string(),
+                    title,
+                });
             }
-        },
-        Err(e) => {
-            crate::log::info!("Error calling categories API: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to call categories API"
-            }))
         }
     }
+
+    Ok(categories)
+}
+
+/// Категории, доступные без Data API: вкладки страницы трендов (названия уже на языке hl).
+/// Если вкладок нет, это ошибка, чтобы вызывающий перешёл на полный список Data API.
+async fn innertube_categories(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    session: Option<&str>,
+) -> Result<Vec<CategoryItem>, String> {
+    let json_data = innertube_browse(config, locale, session, serde_json::json!({ "browseId": "FEtrending" })).await?;
+    let tabs = json_data
+        .pointer("/contents/twoColumnBrowseResultsRenderer/tabs")
+        .and_then(|t| t.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut categories = Vec::new();
+    for tab in &tabs {
+        let renderer = match tab.get("tabRenderer") {
+            Some(r) => r,
+            None => continue,
+        };
+        let params = renderer
+            .pointer("/endpoint/browseEndpoint/params")
+            .and_then(|p| p.as_str())
+            .map(|p| urlencoding::decode(p).map(|d| d.into_owned()).unwrap_or_else(|_| p.to_string()))
+            .unwrap_or_default();
+        if let Some((id, _, title)) = TRENDING_CATEGORIES.iter().find(|(_, p, _)| *p == params) {
+            let tab_title = renderer.get("title").and_then(|t| t.as_str()).unwrap_or("");
+            categories.push(CategoryItem {
+                id: id.to_string(),
+                title: if tab_title.is_empty() { title.to_string() } else { decode_label(tab_title) },
+            });
+        }
+    }
+
+    if categories.is_empty() {
+        Err("InnerTube trending page has no category tabs".to_string())
+    } else {
+        Ok(categories)
+    }
 }
 
 #[utoipa::path(
@@ -639,10 +1384,12 @@ pub async fn get_categories(req: HttpRequest, data: web::Data<crate::AppState>)
     path = "/get-categories_videos.php",
     params(
         ("count" = Option<i32>, Query, description = "Number of videos to return (default: 50)"),
-        ("categoryId" = Option<String>, Query, description = "YouTube category ID")
+        ("categoryId" = Option<String>, Query, description = "YouTube category ID"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
-        (status = 200, description = "Videos from a category", body = [TopVideo]),
+        (status = 200, description = "Videos from a category (innertube fallback: matching trending tab)", body = [TopVideo]),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -666,93 +1413,30 @@ pub async fn get_categories_videos(
         .unwrap_or(config.video.default_count as i32);
 
     let category_id = query_params.get("categoryId").cloned();
-    let apikey = config.get_api_key_rotated();
-
-    let mut url = format!(
-        "https://www.googleapis.com/youtube/v3/videos?part=snippet,contentDetails&chart=mostPopular&maxResults={}&key={}",
-        count,
-        apikey
-    );
-
-    if let Some(cat) = category_id {
-        url.push_str(&format!("&videoCategoryId={}", cat));
-    }
-
-    let client = Client::new();
-    match client.get(&url).send().await {
-        Ok(response) => match response.json::<serde_json::Value>().await {
-            Ok(json_data) => {
-                let mut top_videos: Vec<TopVideo> = Vec::new();
-
-                if let Some(items) = json_data.get("items").and_then(|i| i.as_array()) {
-                    for video in items {
-                        if let (Some(video_info), Some(video_id)) = (
-                            video.get("snippet"),
-                            video.get("id").and_then(|id| id.as_str()),
-                        ) {
-                            let title = video_info
-                                .get("title")
-                                .and_then(|t| t.as_str())
-                                .unwrap_or("Unknown Title");
-                            let title = decode_label(title);
-
-                            let author = video_info
-                                .get("channelTitle")
-                                .and_then(|a| a.as_str())
-                                .unwrap_or("Unknown Author")
-                                .to_string();
-
-                            let thumbnail =
-                                format!("{}/thumbnail/{}", base.trim_end_matches('/'), video_id);
-
-                            let channel_thumbnail = video_info
-                                .get("channelId")
-                                .and_then(|c| c.as_str())
-                                .map(|c| {
-                                    format!("{}/channel_icon/{}", base.trim_end_matches('/'), c)
-                                })
-                                .unwrap_or_else(|| {
-                                    format!(
-                                        "{}/channel_icon/{}",
-                                        base.trim_end_matches('/'),
-                                        video_id
-                                    )
-                                });
-
-                            let duration = video
-                                .get("contentDetails")
-                                .and_then(|c| c.get("duration"))
-                                .and_then(|d| d.as_str())
-                                .map(parse_iso_duration)
-                                .unwrap_or_else(|| "0:00".to_string());
-
-                            top_videos.push(TopVideo {
-                                title,
-                                author,
-                                video_id: video_id.to_string(),
-                                thumbnail,
-                                channel_thumbnail,
-                                duration,
-                            });
-                        }
-                    }
-                }
-
-                HttpResponse::Ok().json(top_videos)
-            }
-            Err(e) => {
-                crate::log::info!("Error parsing category videos response: {}", e);
-                HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to parse response"
-                }))
-            }
-        },
-        Err(e) => {
-            crate::log::info!("Error calling category videos API: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to call YouTube API"
-            }))
-        }
+    let locale = crate::locale::Locale::from_request(&req, config);
+    let session = crate::visitor_data::session_key(&req);
+
+    let result = with_innertube_fallback(
+        "category videos",
+        config.api.prefer_innertube,
+        data_api_popular_videos(config, &locale, base.trim_end_matches('/'), count, category_id.as_deref()),
+        innertube_popular_videos(
+            config,
+            &locale,
+            session.as_deref(),
+            base.trim_end_matches('/'),
+            count.max(1) as usize,
+            category_id.as_deref(),
+        ),
+    )
+    .await;
+
+    match result {
+        Ok(top_videos) => HttpResponse::Ok().json(top_videos),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to call YouTube API",
+            "details": e
+        })),
     }
 }
 
@@ -774,10 +1458,12 @@ pub async fn playlist_root() -> impl Responder {
     path = "/playlist/{playlist_id}",
     params(
         ("playlist_id" = String, Path, description = "YouTube playlist ID"),
-        ("count" = Option<i32>, Query, description = "Number of items to return (default: 50)")
+        ("count" = Option<i32>, Query, description = "Number of items to return (default: 50)"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
-        (status = 200, description = "Playlist metadata and videos", body = PlaylistResponse),
+        (status = 200, description = "Playlist metadata and videos (innertube VL browse as fallback)", body = PlaylistResponse),
         (status = 400, description = "Playlist ID missing"),
         (status = 500, description = "Internal server error")
     )
@@ -808,33 +1494,52 @@ pub async fn get_playlist_videos(
         .and_then(|c| c.parse().ok())
         .unwrap_or(config.video.default_count as i32);
 
-    let apikey = config.get_api_key_rotated();
-    let client = Client::new();
+    let locale = crate::locale::Locale::from_request(&req, config);
+    let session = crate::visitor_data::session_key(&req);
+
+    let result = with_innertube_fallback(
+        "playlist",
+        config.api.prefer_innertube,
+        data_api_playlist(config, &locale, base.trim_end_matches('/'), &playlist_id, count),
+        innertube_playlist(config, &locale, session.as_deref(), base.trim_end_matches('/'), &playlist_id, count),
+    )
+    .await;
+
+    match result {
+        Ok(Some(response)) => HttpResponse::Ok().json(response),
+        Ok(None) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Playlist not found"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to fetch playlist",
+            "details": e
+        })),
+    }
+}
+
+/// Плейлист через Data API; `Ok(None)` — плейлист не найден.
+async fn data_api_playlist(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    base_trimmed: &str,
+    playlist_id: &str,
+    count: i32,
+) -> Result<Option<PlaylistResponse>, String> {
+    // playlists + channels + playlistItems по 50 за страницу, по 1 единице
+    let cost = 2 + (count.max(1) as u64).div_ceil(50);
+    let apikey = config.get_api_key_rotated(cost);
+    if apikey.is_empty() {
+        return Err("No Data API key configured".to_string());
+    }
 
     let playlist_url = format!(
-        "https://www.googleapis.com/youtube/v3/playlists?part=snippet,contentDetails&id={}&key={}",
-        playlist_id, apikey
+        "https://www.googleapis.com/youtube/v3/playlists?part=snippet,contentDetails&id={}&hl={}&key={}",
+        playlist_id,
+        urlencoding::encode(&locale.hl),
+        apikey
     );
 
-    let playlist_resp = match client.get(&playlist_url).send().await {
-        Ok(r) => r,
-        Err(e) => {
-            crate::log::info!("Error fetching playlist info: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch playlist"
-            }));
-        }
-    };
-
-    let playlist_data: serde_json::Value = match playlist_resp.json().await {
-        Ok(d) => d,
-        Err(e) => {
-            crate::log::info!("Error parsing playlist info: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to parse playlist"
-            }));
-        }
-    };
+    let playlist_data = data_api_get(&apikey, &playlist_url).await?;
 
     let playlist_info = match playlist_data
         .get("items")
@@ -842,11 +1547,7 @@ pub async fn get_playlist_videos(
         .and_then(|arr| arr.get(0))
     {
         Some(info) => info,
-        None => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Playlist not found"
-            }));
-        }
+        None => return Ok(None),
     };
 
     let channel_id = playlist_info
@@ -855,21 +1556,15 @@ pub async fn get_playlist_videos(
         .and_then(|c| c.as_str())
         .unwrap_or("");
 
-    let channel_resp = client
-        .get(format!(
-            "https://www.googleapis.com/youtube/v3/channels?part=snippet,statistics&id={}&key={}",
-            channel_id, apikey
-        ))
-        .send()
-        .await;
-
-    let channel_data: serde_json::Value = match channel_resp {
-        Ok(r) => match r.json().await {
-            Ok(d) => d,
-            Err(_) => serde_json::json!({}),
-        },
-        Err(_) => serde_json::json!({}),
-    };
+    let channel_url = format!(
+        "https://www.googleapis.com/youtube/v3/channels?part=snippet,statistics&id={}&hl={}&key={}",
+        channel_id,
+        urlencoding::encode(&locale.hl),
+        apikey
+    );
+    let channel_data = data_api_get(&apikey, &channel_url)
+        .await
+        .unwrap_or_else(|_| serde_json::json!({}));
 
     let channel_info = channel_data
         .get("items")
@@ -889,18 +1584,10 @@ pub async fn get_playlist_videos(
             playlist_items_url.push_str(&format!("&pageToken={}", token));
         }
 
-        let items_resp = match client.get(&playlist_items_url).send().await {
-            Ok(r) => r,
-            Err(e) => {
-                crate::log::info!("Error fetching playlist items: {}", e);
-                break;
-            }
-        };
-
-        let items_data: serde_json::Value = match items_resp.json().await {
+        let items_data = match data_api_get(&apikey, &playlist_items_url).await {
             Ok(d) => d,
             Err(e) => {
-                crate::log::info!("Error parsing playlist items: {}", e);
+                crate::log::info!("Error fetching playlist items: {}", e);
                 break;
             }
         };
@@ -934,8 +1621,7 @@ pub async fn get_playlist_videos(
                             })
                             .to_string();
 
-                        let thumbnail =
-                            format!("{}/thumbnail/{}", base.trim_end_matches('/'), video_id);
+                        let thumbnail = format!("{}/thumbnail/{}", base_trimmed, video_id);
 
                         let channel_thumbnail = channel_info
                             .and_then(|c| c.get("snippet"))
@@ -944,13 +1630,7 @@ pub async fn get_playlist_videos(
                             .and_then(|h| h.get("url"))
                             .and_then(|u| u.as_str())
                             .map(|u| u.to_string())
-                            .unwrap_or_else(|| {
-                                format!(
-                                    "{}/channel_icon/{}",
-                                    base.trim_end_matches('/'),
-                                    channel_id
-                                )
-                            });
+                            .unwrap_or_else(|| format!("{}/channel_icon/{}", base_trimmed, channel_id));
 
                         videos.push(PlaylistVideo {
                             title,
@@ -999,11 +1679,7 @@ pub async fn get_playlist_videos(
             .unwrap_or("")
             .to_string(),
         thumbnail: if !first_video_id.is_empty() {
-            format!(
-                "{}/thumbnail/{}",
-                base.trim_end_matches('/'),
-                first_video_id
-            )
+            format!("{}/thumbnail/{}", base_trimmed, first_video_id)
         } else {
             "".to_string()
         },
@@ -1028,10 +1704,196 @@ pub async fn get_playlist_videos(
             .unwrap_or(0) as i32,
     };
 
-    let response = PlaylistResponse {
+    Ok(Some(PlaylistResponse {
         playlist_info: playlist_info_resp,
         videos,
-    };
+    }))
+}
 
-    HttpResponse::Ok().json(response)
+/// Первый объект по ключу на любой глубине.
+pub(crate) fn find_value<'a>(obj: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    if let Some(obj_map) = obj.as_object() {
+        if let Some(value) = obj_map.get(key) {
+            return Some(value);
+        }
+        obj_map.values().find_map(|v| find_value(v, key))
+    } else if let Some(arr) = obj.as_array() {
+        arr.iter().find_map(|v| find_value(v, key))
+    } else {
+        None
+    }
+}
+
+fn collect_playlist_video_renderers<'a>(obj: &'a serde_json::Value, out: &mut Vec<&'a serde_json::Value>) {
+    if let Some(obj_map) = obj.as_object() {
+        if let Some(pvr) = obj_map.get("playlistVideoRenderer") {
+            out.push(pvr);
+        } else {
+            for value in obj_map.values() {
+                collect_playlist_video_renderers(value, out);
+            }
+        }
+    } else if let Some(arr) = obj.as_array() {
+        for item in arr {
+            collect_playlist_video_renderers(item, out);
+        }
+    }
+}
+
+fn parse_playlist_video_renderer(
+    pvr: &serde_json::Value,
+    base_trimmed: &str,
+    fallback_channel_thumbnail: &str,
+) -> Option<PlaylistVideo> {
+    let video_id = pvr.get("videoId").and_then(|v| v.as_str())?;
+    let title = simplify_text(pvr.get("title").unwrap_or(&serde_json::Value::Null));
+    let byline = pvr.get("shortBylineText").unwrap_or(&serde_json::Value::Null);
+    let author = simplify_text(byline);
+    let channel_thumbnail = find_str(byline, "browseId")
+        .map(|id| format!("{}/channel_icon/{}", base_trimmed, id))
+        .unwrap_or_else(|| fallback_channel_thumbnail.to_string());
+    // videoInfo: «1,2 млн просмотров • 3 года назад»
+    let info: Vec<String> = pvr
+        .pointer("/videoInfo/runs")
+        .and_then(|r| r.as_array())
+        .map(|runs| {
+            runs.iter()
+                .filter_map(|run| run.get("text").and_then(|t| t.as_str()))
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty() && t != "•")
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(PlaylistVideo {
+        title: decode_label(&title),
+        author: decode_label(&author),
+        video_id: video_id.to_string(),
+        thumbnail: format!("{}/thumbnail/{}", base_trimmed, video_id),
+        channel_thumbnail,
+        views: info.first().map(|v| decode_label(v)),
+        published_at: info.get(1).and_then(|p| crate::locale::relative_time_to_iso(&decode_label(p))),
+    })
+}
+
+/// Плейлист через innertube (browseId VL<id>); `Ok(None)` — плейлист не найден.
+/// `published_at` — ISO-дата, приблизительно восстановленная из относительной («3 years ago»).
+async fn innertube_playlist(
+    config: &crate::config::Config,
+    locale: &crate::locale::Locale,
+    session: Option<&str>,
+    base_trimmed: &str,
+    playlist_id: &str,
+    count: i32,
+) -> Result<Option<PlaylistResponse>, String> {
+    let json_data = innertube_browse(
+        config,
+        locale,
+        session,
+        serde_json::json!({ "browseId": format!("VL{}", playlist_id) }),
+    )
+    .await?;
+
+    let microformat = json_data.pointer("/microformat/microformatDataRenderer");
+    let mut renderers = Vec::new();
+    collect_playlist_video_renderers(&json_data, &mut renderers);
+    if microformat.is_none() && renderers.is_empty() {
+        return Ok(None);
+    }
+
+    let owner = find_value(&json_data, "videoOwnerRenderer");
+    let owner_id = owner
+        .and_then(|o| find_str(o, "browseId"))
+        .or_else(|| json_data.get("header").and_then(|h| find_str(h, "browseId")))
+        .unwrap_or("");
+    let channel_title = owner
+        .and_then(|o| o.get("title"))
+        .map(simplify_text)
+        .or_else(|| {
+            json_data
+                .pointer("/header/playlistHeaderRenderer/ownerText")
+                .map(simplify_text)
+        })
+        .unwrap_or_default();
+    let channel_thumbnail = owner
+        .and_then(|o| o.get("thumbnail"))
+        .map(best_thumbnail)
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| {
+            if owner_id.is_empty() {
+                String::new()
+            } else {
+                format!("{}/channel_icon/{}", base_trimmed, owner_id)
+            }
+        });
+
+    let title = microformat
+        .and_then(|m| m.get("title"))
+        .and_then(|t| t.as_str())
+        .map(|t| t.to_string())
+        .or_else(|| {
+            json_data
+                .pointer("/header/playlistHeaderRenderer/title")
+                .map(simplify_text)
+        })
+        .unwrap_or_default();
+    let description = microformat
+        .and_then(|m| m.get("description"))
+        .and_then(|d| d.as_str())
+        .unwrap_or("")
+        .to_string();
+    // «123 видео» / «1,234 videos» — берём только цифры
+    let stated_count = find_value(&json_data, "playlistSidebarPrimaryInfoRenderer")
+        .and_then(|r| r.pointer("/stats/0"))
+        .or_else(|| json_data.pointer("/header/playlistHeaderRenderer/numVideosText"))
+        .map(simplify_text)
+        .and_then(|t| t.chars().filter(|c| c.is_ascii_digit()).collect::<String>().parse::<i32>().ok());
+
+    let mut videos: Vec<PlaylistVideo> = renderers
+        .iter()
+        .filter_map(|pvr| parse_playlist_video_renderer(pvr, base_trimmed, &channel_thumbnail))
+        .collect();
+    let mut continuation = find_search_continuation(&json_data);
+    while (videos.len() as i32) < count {
+        let token = match continuation.take() {
+            Some(t) => t,
+            None => break,
+        };
+        let page = match innertube_browse(config, locale, session, serde_json::json!({ "continuation": token })).await {
+            Ok(p) => p,
+            Err(e) => {
+                crate::log::info!("Error fetching playlist continuation: {}", e);
+                break;
+            }
+        };
+        let mut page_renderers = Vec::new();
+        collect_playlist_video_renderers(&page, &mut page_renderers);
+        if page_renderers.is_empty() {
+            break;
+        }
+        videos.extend(
+            page_renderers
+                .iter()
+                .filter_map(|pvr| parse_playlist_video_renderer(pvr, base_trimmed, &channel_thumbnail)),
+        );
+        continuation = find_search_continuation(&page);
+    }
+    videos.truncate(count.max(0) as usize);
+
+    let video_count = stated_count.unwrap_or(videos.len() as i32);
+    let thumbnail = videos
+        .first()
+        .map(|v| format!("{}/thumbnail/{}", base_trimmed, v.video_id))
+        .unwrap_or_default();
+
+    Ok(Some(PlaylistResponse {
+        playlist_info: PlaylistInfo {
+            title: decode_label(&title),
+            description,
+            thumbnail,
+            channel_title: decode_label(&channel_title),
+            channel_thumbnail,
+            video_count,
+        },
+        videos,
+    }))
 }
@@ -0,0 +1,319 @@
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+// Фильтры поиска InnerTube. YouTube принимает их в поле `params` запроса
+// /youtubei/v1/search как base64 от protobuf-сообщения SearchParams:
+//   1: sort (varint)
+//   2: filters { 1: upload_date, 2: type, 3: duration, <feature>: 1 }
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    Date,
+    Views,
+    Rating,
+}
+
+impl SearchSort {
+    pub const ALL: [SearchSort; 4] = [Self::Relevance, Self::Date, Self::Views, Self::Rating];
+
+    fn code(self) -> u64 {
+        match self {
+            Self::Relevance => 0,
+            Self::Rating => 1,
+            Self::Date => 2,
+            Self::Views => 3,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Relevance => "relevance",
+            Self::Date => "date",
+            Self::Views => "views",
+            Self::Rating => "rating",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Relevance => "Relevance",
+            Self::Date => "Upload date",
+            Self::Views => "View count",
+            Self::Rating => "Rating",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadDate {
+    Hour,
+    Today,
+    Week,
+    Month,
+    Year,
+}
+
+impl UploadDate {
+    pub const ALL: [UploadDate; 5] = [Self::Hour, Self::Today, Self::Week, Self::Month, Self::Year];
+
+    fn code(self) -> u64 {
+        match self {
+            Self::Hour => 1,
+            Self::Today => 2,
+            Self::Week => 3,
+            Self::Month => 4,
+            Self::Year => 5,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Today => "today",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Hour => "Last hour",
+            Self::Today => "Today",
+            Self::Week => "This week",
+            Self::Month => "This month",
+            Self::Year => "This year",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDuration {
+    Short,
+    Medium,
+    Long,
+}
+
+impl SearchDuration {
+    pub const ALL: [SearchDuration; 3] = [Self::Short, Self::Medium, Self::Long];
+
+    fn code(self) -> u64 {
+        match self {
+            Self::Short => 1,
+            Self::Long => 2,
+            Self::Medium => 3,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Short => "short",
+            Self::Medium => "medium",
+            Self::Long => "long",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Short => "Short (< 4 minutes)",
+            Self::Medium => "Medium (4-20 minutes)",
+            Self::Long => "Long (> 20 minutes)",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFeature {
+    #[serde(rename = "hd")]
+    Hd,
+    #[serde(rename = "4k")]
+    FourK,
+    #[serde(rename = "subtitles")]
+    Subtitles,
+    #[serde(rename = "live")]
+    Live,
+    #[serde(rename = "creative_commons")]
+    CreativeCommons,
+    #[serde(rename = "360")]
+    Spherical,
+}
+
+impl SearchFeature {
+    pub const ALL: [SearchFeature; 6] = [
+        Self::Hd,
+        Self::FourK,
+        Self::Subtitles,
+        Self::Live,
+        Self::CreativeCommons,
+        Self::Spherical,
+    ];
+
+    // Номер поля в сообщении фильтров, значение всегда 1
+    fn field(self) -> u64 {
+        match self {
+            Self::Hd => 4,
+            Self::Subtitles => 5,
+            Self::CreativeCommons => 6,
+            Self::Live => 8,
+            Self::FourK => 14,
+            Self::Spherical => 15,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hd => "hd",
+            Self::FourK => "4k",
+            Self::Subtitles => "subtitles",
+            Self::Live => "live",
+            Self::CreativeCommons => "creative_commons",
+            Self::Spherical => "360",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Hd => "HD (high definition)",
+            Self::FourK => "4K",
+            Self::Subtitles => "CC (subtitles)",
+            Self::Live => "Live",
+            Self::CreativeCommons => "Creative Commons",
+            Self::Spherical => "360°",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilters {
+    pub sort: SearchSort,
+    pub upload_date: Option<UploadDate>,
+    pub duration: Option<SearchDuration>,
+    pub features: Vec<SearchFeature>,
+    /// video, channel или playlist
+    pub result_type: Option<String>,
+}
+
+fn parse_value<T: Copy>(all: &[T], value: &str, as_str: fn(T) -> &'static str, name: &str) -> Result<T, String> {
+    all.iter()
+        .copied()
+        .find(|v| as_str(*v).eq_ignore_ascii_case(value.trim()))
+        .ok_or_else(|| {
+            let allowed: Vec<&str> = all.iter().map(|v| as_str(*v)).collect();
+            format!("Invalid {} parameter. Must be one of: {}", name, allowed.join(", "))
+        })
+}
+
+impl SearchFilters {
+    /// Разбирает sort, upload_date, duration, features и type из query-параметров.
+    /// Пустые значения считаются отсутствующими.
+    pub fn from_query(params: &HashMap<String, String>) -> Result<Self, String> {
+        let get = |key: &str| params.get(key).map(|v| v.trim()).filter(|v| !v.is_empty());
+
+        let mut filters = SearchFilters::default();
+        if let Some(v) = get("sort") {
+            filters.sort = parse_value(&SearchSort::ALL, v, SearchSort::as_str, "sort")?;
+        }
+        if let Some(v) = get("upload_date") {
+            filters.upload_date = Some(parse_value(&UploadDate::ALL, v, UploadDate::as_str, "upload_date")?);
+        }
+        if let Some(v) = get("duration") {
+            filters.duration = Some(parse_value(&SearchDuration::ALL, v, SearchDuration::as_str, "duration")?);
+        }
+        if let Some(v) = get("features") {
+            for part in v.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+                let feature = parse_value(&SearchFeature::ALL, part, SearchFeature::as_str, "features")?;
+                if !filters.features.contains(&feature) {
+                    filters.features.push(feature);
+                }
+            }
+        }
+        if let Some(v) = get("type") {
+            filters.result_type = Some(v.to_lowercase());
+        }
+        Ok(filters)
+    }
+
+    /// Фильтры в виде query-строки (без ведущего `&`), значения по умолчанию опускаются.
+    /// Тип результатов сюда не входит, его передают отдельно.
+    pub fn to_query(&self) -> String {
+        let mut parts = Vec::new();
+        if self.sort != SearchSort::Relevance {
+            parts.push(format!("sort={}", self.sort.as_str()));
+        }
+        if let Some(date) = self.upload_date {
+            parts.push(format!("upload_date={}", date.as_str()));
+        }
+        if let Some(duration) = self.duration {
+            parts.push(format!("duration={}", duration.as_str()));
+        }
+        if !self.features.is_empty() {
+            let features: Vec<&str> = self.features.iter().map(|f| f.as_str()).collect();
+            parts.push(format!("features={}", features.join(",")));
+        }
+        parts.join("&")
+    }
+
+    fn type_code(&self) -> Option<u64> {
+        match self.result_type.as_deref() {
+            Some("video") => Some(1),
+            Some("channel") => Some(2),
+            Some("playlist") => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Значение поля `params` для /youtubei/v1/search, None если фильтров нет.
+    pub fn to_params(&self) -> Option<String> {
+        let mut inner = Vec::new();
+        if let Some(date) = self.upload_date {
+            put_varint_field(&mut inner, 1, date.code());
+        }
+        if let Some(code) = self.type_code() {
+            put_varint_field(&mut inner, 2, code);
+        }
+        if let Some(duration) = self.duration {
+            put_varint_field(&mut inner, 3, duration.code());
+        }
+        let mut features = self.features.clone();
+        features.sort_by_key(|f| f.field());
+        for feature in features {
+            put_varint_field(&mut inner, feature.field(), 1);
+        }
+
+        let mut outer = Vec::new();
+        if self.sort.code() != 0 {
+            put_varint_field(&mut outer, 1, self.sort.code());
+        }
+        if !inner.is_empty() {
+            put_varint(&mut outer, (2 << 3) | 2);
+            put_varint(&mut outer, inner.len() as u64);
+            outer.extend_from_slice(&inner);
+        }
+
+        if outer.is_empty() {
+            None
+        } else {
+            Some(general_purpose::STANDARD.encode(outer))
+        }
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
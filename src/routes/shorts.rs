@@ -95,7 +95,9 @@ fn parse_single_short(entry: &serde_json::Value, base_trimmed: &str) -> Option<S
     get,
     path = "/get_shorts.php",
     params(
-        ("sequence" = Option<String>, Query, description = "Sequence token for pagination")
+        ("sequence" = Option<String>, Query, description = "Sequence token for pagination"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Shorts list", body = ShortsResponse)
@@ -104,7 +106,14 @@ fn parse_single_short(entry: &serde_json::Value, base_trimmed: &str) -> Option<S
 pub async fn get_shorts(req: HttpRequest, data: web::Data<crate::AppState>, auth_config: web::Data<crate::routes::auth::AuthConfig>) -> impl Responder {
     let base = base_url(&req, &data.config);
     let base_trimmed = base.trim_end_matches('/');
-    let innertube_key = data.config.get_api_key_rotated();
+    let innertube_key = match data.config.get_innertube_key() {
+        Some(key) => key.to_string(),
+        None => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Missing innertube_key in config.yml"
+            }));
+        }
+    };
     
     let query_params: HashMap<String, String> = web::Query::<HashMap<String, String>>::from_query(req.query_string())
         .map(|q| q.into_inner())
@@ -113,15 +122,13 @@ pub async fn get_shorts(req: HttpRequest, data: web::Data<crate::AppState>, auth
     let sequence_params = query_params.get("sequence");
 
     // Выбираем эндпоинт: если токена нет — берем начальный "Seedless" ролик, если есть — последовательность
-    let (url, payload) = if let Some(token) = sequence_params {
+    let (url, mut payload) = if let Some(token) = sequence_params {
         let u = format!("https://www.youtube.com/youtubei/v1/reel/reel_watch_sequence?key={}", innertube_key);
         let p = serde_json::json!({
             "context": {
                 "client": {
                     "clientName": "WEB",
-                    "clientVersion": "2.20260206.01.00",
-                    "hl": "ru",
-                    "gl": "US"
+                    "clientVersion": "2.20260206.01.00"
                 }
             },
             "sequenceParams": token
@@ -134,9 +141,7 @@ pub async fn get_shorts(req: HttpRequest, data: web::Data<crate::AppState>, auth
             "context": {
                 "client": {
                     "clientName": "WEB",
-                    "clientVersion": "2.20260206.01.00",
-                    "hl": "ru",
-                    "gl": "US"
+                    "clientVersion": "2.20260206.01.00"
                 }
             },
             "inputType": "REEL_WATCH_INPUT_TYPE_SEEDLESS",
@@ -149,10 +154,21 @@ pub async fn get_shorts(req: HttpRequest, data: web::Data<crate::AppState>, auth
     let client = reqwest::Client::new();
 	
 	let refresh_token = query_params.get("token");
-    let mut request_builder = client.post(&url)
-        .header("Content-Type", "application/json")
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36")
-		.header("X-Goog-Visitor-Id", "CgtjTS00dGRYTXhBOCif8OnOBjIoCgJQTBIiEh4SHAsMDg8QERITFBUWFxgZGhscHR4fICEiIyQlJicgSA%3D%3D");
+    // visitorData вместо захардкоженного ID: с ним последовательность шортсов не общая на всех
+    let visitor = crate::visitor_data::visitor_data(
+        &data.config,
+        crate::visitor_data::session_key(&req).as_deref(),
+    )
+    .await;
+    crate::visitor_data::attach(&mut payload, visitor.as_deref());
+    let locale = crate::locale::Locale::from_request(&req, &data.config);
+    locale.apply(&mut payload);
+    let mut request_builder = crate::visitor_data::with_header(
+        client.post(&url)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"),
+        visitor.as_deref(),
+    );
         
 
     // 2. Добавляем Authorization, если есть токен
@@ -0,0 +1,410 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use lazy_static::lazy_static;
+use lru::LruCache;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tokio::task;
+use utoipa::ToSchema;
+
+use crate::routes::video::fetch_player_metadata;
+
+/// Parsed spec with the time it was fetched.
+type CachedSpec = (StoryboardSpec, u64);
+/// Sprite sheet JPEG with the time it was fetched.
+type CachedSheet = (Vec<u8>, u64);
+
+lazy_static! {
+    static ref STORYBOARD_SPEC_CACHE: Arc<Mutex<LruCache<String, CachedSpec>>> = Arc::new(
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(500).unwrap()))
+    );
+    static ref STORYBOARD_SHEET_CACHE: Arc<Mutex<LruCache<String, CachedSheet>>> = Arc::new(
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(300).unwrap()))
+    );
+}
+
+const STORYBOARD_CACHE_DURATION: u64 = 3600;
+
+fn base_url(req: &HttpRequest, config: &crate::config::Config) -> String {
+    if !config.server.main_url.is_empty() {
+        return config.server.main_url.clone();
+    }
+    let info = req.connection_info();
+    let scheme = info.scheme();
+    let host = info.host();
+    format!("{}://{}/", scheme, host.trim_end_matches('/'))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StoryboardLevelInfo {
+    pub level: usize,
+    /// Size of one frame in pixels.
+    pub width: u32,
+    pub height: u32,
+    pub frame_count: u32,
+    /// Frames per row / column of a sprite sheet.
+    pub columns: u32,
+    pub rows: u32,
+    /// Time between frames in milliseconds.
+    pub interval_ms: u64,
+    /// Proxied sprite sheet URLs, in playback order.
+    pub sheets: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StoryboardResponse {
+    pub video_id: String,
+    pub duration_seconds: u64,
+    pub levels: Vec<StoryboardLevelInfo>,
+}
+
+/// One level of `playerStoryboardSpecRenderer.spec`.
+#[derive(Debug, Clone)]
+struct StoryboardLevel {
+    /// Sheet URL with `$M` still in place of the sheet index.
+    url_template: String,
+    sigh: String,
+    width: u32,
+    height: u32,
+    frame_count: u32,
+    columns: u32,
+    rows: u32,
+    interval_ms: u64,
+}
+
+impl StoryboardLevel {
+    fn frames_per_sheet(&self) -> u32 {
+        (self.columns * self.rows).max(1)
+    }
+
+    fn sheet_count(&self) -> u32 {
+        if self.url_template.contains("$M") {
+            self.frame_count.div_ceil(self.frames_per_sheet()).max(1)
+        } else {
+            1
+        }
+    }
+
+    fn sheet_url(&self, sheet: u32) -> String {
+        let url = self.url_template.replace("$M", &sheet.to_string());
+        if self.sigh.is_empty() {
+            url
+        } else {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{}{}sigh={}", url, separator, self.sigh)
+        }
+    }
+
+    /// Sheet index and crop rectangle of the frame shown at `t_ms`.
+    fn frame_at(&self, t_ms: u64) -> (u32, u32, u32) {
+        let index = t_ms
+            .checked_div(self.interval_ms)
+            .map(|i| (i as u32).min(self.frame_count.saturating_sub(1)))
+            .unwrap_or(0);
+        let sheet = index / self.frames_per_sheet();
+        let position = index % self.frames_per_sheet();
+        let x = (position % self.columns.max(1)) * self.width;
+        let y = (position / self.columns.max(1)) * self.height;
+        (sheet, x, y)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoryboardSpec {
+    duration_ms: u64,
+    levels: Vec<StoryboardLevel>,
+}
+
+/// Spec format: `base_url|w#h#count#cols#rows#interval#name#sigh|...`, where
+/// `$L` in the base URL is the level index and `$N` the level name.
+fn parse_storyboard_spec(spec: &str, duration_ms: u64) -> Vec<StoryboardLevel> {
+    let mut parts = spec.split('|');
+    let Some(base) = parts.next() else {
+        return Vec::new();
+    };
+    parts
+        .enumerate()
+        .filter_map(|(level, part)| {
+            let fields: Vec<&str> = part.split('#').collect();
+            if fields.len() < 8 {
+                return None;
+            }
+            let width = fields[0].parse().ok()?;
+            let height = fields[1].parse().ok()?;
+            let frame_count: u32 = fields[2].parse().ok()?;
+            let columns = fields[3].parse().ok()?;
+            let rows = fields[4].parse().ok()?;
+            let interval_ms = match fields[5].parse::<u64>().unwrap_or(0) {
+                0 if frame_count > 0 => duration_ms / frame_count as u64,
+                i => i,
+            };
+            let url_template = base
+                .replace("$L", &level.to_string())
+                .replace("$N", fields[6]);
+            Some(StoryboardLevel {
+                url_template,
+                sigh: fields[7].to_string(),
+                width,
+                height,
+                frame_count,
+                columns,
+                rows,
+                interval_ms,
+            })
+        })
+        .collect()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+async fn get_storyboard_spec(
+    video_id: &str,
+    config: &crate::config::Config,
+) -> Result<StoryboardSpec, String> {
+    {
+        let mut cache = STORYBOARD_SPEC_CACHE.lock().await;
+        if let Some((spec, timestamp)) = cache.get(video_id) {
+            if now_secs() - timestamp < STORYBOARD_CACHE_DURATION {
+                return Ok(spec.clone());
+            }
+        }
+    }
+
+    let player_response: Value = fetch_player_metadata(video_id, config).await?;
+    let spec = player_response
+        .get("storyboards")
+        .and_then(|s| s.get("playerStoryboardSpecRenderer"))
+        .and_then(|r| r.get("spec"))
+        .and_then(|s| s.as_str())
+        .ok_or("Storyboard is not available for this video")?;
+    let duration_ms = player_response
+        .get("videoDetails")
+        .and_then(|vd| vd.get("lengthSeconds"))
+        .and_then(|l| l.as_str())
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0)
+        * 1000;
+
+    let storyboard = StoryboardSpec {
+        duration_ms,
+        levels: parse_storyboard_spec(spec, duration_ms),
+    };
+    if storyboard.levels.is_empty() {
+        return Err("Storyboard spec could not be parsed".to_string());
+    }
+    STORYBOARD_SPEC_CACHE
+        .lock()
+        .await
+        .put(video_id.to_string(), (storyboard.clone(), now_secs()));
+    Ok(storyboard)
+}
+
+async fn get_storyboard_sheet(
+    video_id: &str,
+    level: usize,
+    sheet: u32,
+    storyboard_level: &StoryboardLevel,
+) -> Result<Vec<u8>, String> {
+    let cache_key = format!("{}_{}_{}", video_id, level, sheet);
+    {
+        let mut cache = STORYBOARD_SHEET_CACHE.lock().await;
+        if let Some((data, timestamp)) = cache.get(&cache_key) {
+            if now_secs() - timestamp < STORYBOARD_CACHE_DURATION {
+                return Ok(data.clone());
+            }
+        }
+    }
+
+    let resp = Client::new()
+        .get(storyboard_level.sheet_url(sheet))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("storyboard HTTP {}", resp.status()));
+    }
+    let data = resp.bytes().await.map_err(|e| e.to_string())?.to_vec();
+    STORYBOARD_SHEET_CACHE
+        .lock()
+        .await
+        .put(cache_key, (data.clone(), now_secs()));
+    Ok(data)
+}
+
+fn crop_frame(sheet: &[u8], x: u32, y: u32, width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let img = image::load_from_memory(sheet).map_err(|e| e.to_string())?;
+    if x + width > img.width() || y + height > img.height() {
+        return Err("Frame is outside of the sprite sheet".to_string());
+    }
+    let frame = img.crop_imm(x, y, width, height);
+    let mut out = Vec::new();
+    frame
+        .write_to(&mut Cursor::new(&mut out), image::ImageOutputFormat::Jpeg(85))
+        .map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+fn storyboard_error(e: String) -> HttpResponse {
+    if e.starts_with("Storyboard is not available") {
+        HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Storyboard not found",
+            "details": e
+        }))
+    } else {
+        HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to fetch storyboard",
+            "details": e
+        }))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/storyboard/{video_id}",
+    params(
+        ("video_id" = String, Path, description = "YouTube video ID"),
+        ("t" = Option<f64>, Query, description = "Return the single preview frame (JPEG) shown at this time in seconds instead of metadata"),
+        ("level" = Option<usize>, Query, description = "Storyboard level used with t (default: the largest frames)")
+    ),
+    responses(
+        (status = 200, description = "Storyboard metadata, or a JPEG frame when t is given", body = StoryboardResponse),
+        (status = 400, description = "Invalid level or time"),
+        (status = 404, description = "Storyboard not available")
+    )
+)]
+pub async fn get_storyboard(
+    path: web::Path<String>,
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+) -> impl Responder {
+    let video_id = path.into_inner();
+    let config = &data.config;
+    let base = base_url(&req, config);
+    let base_trimmed = base.trim_end_matches('/');
+
+    let mut query_params: HashMap<String, String> = HashMap::new();
+    for pair in req.query_string().split('&') {
+        let mut parts = pair.split('=');
+        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            query_params.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let storyboard = match get_storyboard_spec(&video_id, config).await {
+        Ok(s) => s,
+        Err(e) => return storyboard_error(e),
+    };
+
+    let Some(t) = query_params.get("t") else {
+        let levels = storyboard
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, l)| StoryboardLevelInfo {
+                level: i,
+                width: l.width,
+                height: l.height,
+                frame_count: l.frame_count,
+                columns: l.columns,
+                rows: l.rows,
+                interval_ms: l.interval_ms,
+                sheets: (0..l.sheet_count())
+                    .map(|s| format!("{}/storyboard/{}/{}/{}", base_trimmed, video_id, i, s))
+                    .collect(),
+            })
+            .collect();
+        return HttpResponse::Ok().json(StoryboardResponse {
+            video_id,
+            duration_seconds: storyboard.duration_ms / 1000,
+            levels,
+        });
+    };
+
+    let t_ms = match t.parse::<f64>() {
+        Ok(t) if t >= 0.0 => (t * 1000.0) as u64,
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid t parameter",
+                "details": "t must be a non-negative number of seconds"
+            }));
+        }
+    };
+    let level = match query_params.get("level") {
+        Some(l) => match l.parse::<usize>() {
+            Ok(l) if l < storyboard.levels.len() => l,
+            _ => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Invalid level",
+                    "details": format!("level must be between 0 and {}", storyboard.levels.len() - 1)
+                }));
+            }
+        },
+        None => storyboard.levels.len() - 1,
+    };
+    let storyboard_level = &storyboard.levels[level];
+    let (sheet, x, y) = storyboard_level.frame_at(t_ms);
+    let sheet_data = match get_storyboard_sheet(&video_id, level, sheet, storyboard_level).await {
+        Ok(d) => d,
+        Err(e) => return storyboard_error(e),
+    };
+
+    let (width, height) = (storyboard_level.width, storyboard_level.height);
+    match task::spawn_blocking(move || crop_frame(&sheet_data, x, y, width, height)).await {
+        Ok(Ok(frame)) => HttpResponse::Ok()
+            .content_type("image/jpeg")
+            .insert_header(("Cache-Control", "public, max-age=3600"))
+            .body(frame),
+        Ok(Err(e)) => storyboard_error(e),
+        Err(e) => storyboard_error(e.to_string()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/storyboard/{video_id}/{level}/{sheet}",
+    params(
+        ("video_id" = String, Path, description = "YouTube video ID"),
+        ("level" = usize, Path, description = "Storyboard level"),
+        ("sheet" = u32, Path, description = "Sprite sheet index within the level")
+    ),
+    responses(
+        (status = 200, description = "Sprite sheet", content_type = "image/jpeg"),
+        (status = 404, description = "Storyboard, level or sheet not found")
+    )
+)]
+pub async fn storyboard_sheet(
+    path: web::Path<(String, usize, u32)>,
+    data: web::Data<crate::AppState>,
+) -> impl Responder {
+    let (video_id, level, sheet) = path.into_inner();
+
+    let storyboard = match get_storyboard_spec(&video_id, &data.config).await {
+        Ok(s) => s,
+        Err(e) => return storyboard_error(e),
+    };
+    let Some(storyboard_level) = storyboard.levels.get(level) else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Storyboard level not found"
+        }));
+    };
+    if sheet >= storyboard_level.sheet_count() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Storyboard sheet not found"
+        }));
+    }
+
+    match get_storyboard_sheet(&video_id, level, sheet, storyboard_level).await {
+        Ok(data) => HttpResponse::Ok()
+            .content_type("image/jpeg")
+            .insert_header(("Cache-Control", "public, max-age=3600"))
+            .body(data),
+        Err(e) => storyboard_error(e),
+    }
+}
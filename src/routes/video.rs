@@ -10,7 +10,6 @@ use tokio_stream::wrappers::ReceiverStream;
 use html_escape::decode_html_entities;
 use image::{GenericImageView, Pixel};
 use lazy_static::lazy_static;
-use lru::LruCache;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -19,12 +18,12 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::sync::Mutex;
 use tokio::task;
 use urlencoding;
 use utoipa::ToSchema;
+use crate::config::DeviceProfile;
+use crate::image_cache::image_response;
 use tokio_util::io::ReaderStream;
 use tokio::io::AsyncReadExt;
 
@@ -74,6 +73,7 @@ async fn download_mux_to_temp_file(
     video_id: String,
     height: u32,
     cache: &crate::config::CacheConfig,
+    auth: Option<&PlayerAuth>,
 ) -> Result<PathBuf, String> {
     // Определяем временную папку из конфига или системную
     let temp_dir = match &cache.temp_dir {
@@ -93,10 +93,12 @@ async fn download_mux_to_temp_file(
         _ => env::temp_dir(),
     };
 
-    let final_file_name = format!("yt_api_video_{}_{}p.mp4", video_id, height);
+    // Файлы, скачанные с учёткой пользователя, не должны достаться другим
+    let user_suffix = auth.map(|a| format!("_u{}", a.scope)).unwrap_or_default();
+    let final_file_name = format!("yt_api_video_{}_{}p{}.mp4", video_id, height, user_suffix);
     let final_path = temp_dir.join(&final_file_name);
 
-    let lock_file_name = format!("yt_api_video_{}_{}p.lock", video_id, height);
+    let lock_file_name = format!("yt_api_video_{}_{}p{}.lock", video_id, height, user_suffix);
     let lock_path = temp_dir.join(&lock_file_name);
 
     // Already cached
@@ -180,10 +182,14 @@ async fn download_mux_to_temp_file(
         .map(|p| p.to_string_lossy().to_string());
 
     let output_template = temp_dir
-        .join(format!("yt_api_video_{}_{}p.%(ext)s", video_id, height));
+        .join(format!("yt_api_video_{}_{}p{}.%(ext)s", video_id, height, user_suffix));
     let output_template_str = output_template.to_string_lossy().to_string();
 
-    let output_stem = format!("yt_api_video_{}_{}p", video_id, height);
+    let output_stem = format!("yt_api_video_{}_{}p{}", video_id, height, user_suffix);
+    let auth_args = match auth {
+        Some(a) => Some(a.yt_dlp_args().await?),
+        None => None,
+    };
 
     let download_result = task::spawn_blocking(move || {
         let mut cmd = Command::new(&yt_dlp);
@@ -208,7 +214,11 @@ async fn download_mux_to_temp_file(
            .arg("--force-overwrites")
            .arg("--postprocessor-args").arg("ffmpeg:-movflags +faststart");
 
-        if let Some(c) = cookie_arg { cmd.arg("--cookies").arg(c); }
+        if let Some(auth_args) = &auth_args {
+            cmd.args(auth_args.args());
+        } else if let Some(c) = cookie_arg {
+            cmd.arg("--cookies").arg(c);
+        }
         
         cmd.arg(format!("https://www.youtube.com/watch?v={}", video_id));
 
@@ -378,6 +388,214 @@ fn simplify_text(node: &serde_json::Value) -> String {
     String::new()
 }
 
+/// Parses "1:02:03" / "12:34" / "0:05" into seconds.
+fn parse_timestamp(text: &str) -> Option<u64> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    let mut seconds = 0u64;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let value: u64 = part.parse().ok()?;
+        if i > 0 && value >= 60 {
+            return None;
+        }
+        seconds = seconds * 60 + value;
+    }
+    Some(seconds)
+}
+
+fn proxied_image_url(url: &str, base_trimmed: &str) -> Option<String> {
+    if url.is_empty() {
+        return None;
+    }
+    let url = if url.starts_with("//") {
+        format!("https:{}", url)
+    } else {
+        url.to_string()
+    };
+    Some(format!("{}/channel_icon/{}", base_trimmed, urlencoding::encode(&url)))
+}
+
+fn last_thumbnail_url(node: &serde_json::Value) -> Option<&str> {
+    node.get("thumbnails")
+        .and_then(|t| t.as_array())
+        .and_then(|arr| arr.last())
+        .and_then(|t| t.get("url"))
+        .and_then(|u| u.as_str())
+}
+
+/// Chapters from the `macroMarkersListRenderer` engagement panels of a `next` response.
+/// Creator chapters win over auto-generated ones.
+fn chapters_from_macro_markers(next_data: &serde_json::Value, base_trimmed: &str) -> Vec<Chapter> {
+    let panels = match next_data.get("engagementPanels").and_then(|p| p.as_array()) {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+
+    for wanted in ["description-chapters", "auto-chapters"] {
+        for panel in panels {
+            let renderer = match panel.get("engagementPanelSectionListRenderer") {
+                Some(r) => r,
+                None => continue,
+            };
+            let identifier = renderer
+                .get("panelIdentifier")
+                .and_then(|p| p.as_str())
+                .unwrap_or("");
+            if !identifier.ends_with(wanted) {
+                continue;
+            }
+            let items = match renderer
+                .get("content")
+                .and_then(|c| c.get("macroMarkersListRenderer"))
+                .and_then(|m| m.get("contents"))
+                .and_then(|c| c.as_array())
+            {
+                Some(items) => items,
+                None => continue,
+            };
+
+            let chapters: Vec<Chapter> = items
+                .iter()
+                .filter_map(|item| item.get("macroMarkersListItemRenderer"))
+                .filter_map(|marker| {
+                    let start_seconds = marker
+                        .get("onTap")
+                        .and_then(|t| t.get("watchEndpoint"))
+                        .and_then(|w| w.get("startTimeSeconds"))
+                        .and_then(|s| s.as_u64())
+                        .or_else(|| {
+                            marker
+                                .get("timeDescription")
+                                .map(simplify_text)
+                                .and_then(|t| parse_timestamp(&t))
+                        })?;
+                    Some(Chapter {
+                        title: simplify_text(marker.get("title").unwrap_or(&serde_json::Value::Null)),
+                        start_seconds,
+                        thumbnail: marker
+                            .get("thumbnail")
+                            .and_then(last_thumbnail_url)
+                            .and_then(|u| proxied_image_url(u, base_trimmed)),
+                    })
+                })
+                .collect();
+            if !chapters.is_empty() {
+                return chapters;
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Chapters from the player bar markers (`multiMarkersPlayerBarRenderer`), which
+/// some responses carry instead of the engagement panel.
+fn chapters_from_player_bar(next_data: &serde_json::Value, base_trimmed: &str) -> Vec<Chapter> {
+    let markers = match next_data
+        .get("playerOverlays")
+        .and_then(|p| p.get("playerOverlayRenderer"))
+        .and_then(|p| p.get("decoratedPlayerBarRenderer"))
+        .and_then(|d| d.get("decoratedPlayerBarRenderer"))
+        .and_then(|d| d.get("playerBar"))
+        .and_then(|b| b.get("multiMarkersPlayerBarRenderer"))
+        .and_then(|m| m.get("markersMap"))
+        .and_then(|m| m.as_array())
+    {
+        Some(m) => m,
+        None => return Vec::new(),
+    };
+
+    markers
+        .iter()
+        .filter_map(|entry| entry.get("value").and_then(|v| v.get("chapters")).and_then(|c| c.as_array()))
+        .flatten()
+        .filter_map(|c| c.get("chapterRenderer"))
+        .filter_map(|chapter| {
+            let start_ms = chapter.get("timeRangeStartMillis").and_then(|t| t.as_u64())?;
+            Some(Chapter {
+                title: simplify_text(chapter.get("title").unwrap_or(&serde_json::Value::Null)),
+                start_seconds: start_ms / 1000,
+                thumbnail: chapter
+                    .get("thumbnail")
+                    .and_then(last_thumbnail_url)
+                    .and_then(|u| proxied_image_url(u, base_trimmed)),
+            })
+        })
+        .collect()
+}
+
+/// Chapters written by hand in the description ("0:00 Intro"), using the same
+/// rules YouTube applies: at least three timestamps, the first at 0:00, ascending.
+fn chapters_from_description(description: &str, video_id: &str, base_trimmed: &str) -> Vec<Chapter> {
+    lazy_static! {
+        static ref LEADING: regex::Regex = regex::Regex::new(
+            r"^[\s\-–—•*]*[\[(]?((?:\d{1,2}:)?\d{1,2}:\d{2})[\])]?\s*[-–—:|.]?\s*(.+?)\s*$"
+        )
+        .unwrap();
+        static ref TRAILING: regex::Regex = regex::Regex::new(
+            r"^[\s\-–—•*]*(.+?)\s*[-–—:|]?\s*[\[(]?((?:\d{1,2}:)?\d{1,2}:\d{2})[\])]?\s*$"
+        )
+        .unwrap();
+    }
+
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for line in description.lines() {
+        let parsed = LEADING
+            .captures(line)
+            .map(|c| (c[1].to_string(), c[2].to_string()))
+            .or_else(|| TRAILING.captures(line).map(|c| (c[2].to_string(), c[1].to_string())));
+        let (time, title) = match parsed {
+            Some(p) => p,
+            None => continue,
+        };
+        let start_seconds = match parse_timestamp(&time) {
+            Some(s) => s,
+            None => continue,
+        };
+        if let Some(last) = chapters.last() {
+            if start_seconds <= last.start_seconds {
+                return Vec::new();
+            }
+        } else if start_seconds != 0 {
+            return Vec::new();
+        }
+        chapters.push(Chapter {
+            title,
+            start_seconds,
+            thumbnail: Some(format!(
+                "{}/storyboard/{}?t={}",
+                base_trimmed, video_id, start_seconds
+            )),
+        });
+    }
+
+    if chapters.len() < 3 {
+        return Vec::new();
+    }
+    chapters
+}
+
+fn extract_chapters(
+    next_data: &serde_json::Value,
+    description: &str,
+    video_id: &str,
+    base_trimmed: &str,
+) -> Vec<Chapter> {
+    let chapters = chapters_from_macro_markers(next_data, base_trimmed);
+    if !chapters.is_empty() {
+        return chapters;
+    }
+    let chapters = chapters_from_player_bar(next_data, base_trimmed);
+    if !chapters.is_empty() {
+        return chapters;
+    }
+    chapters_from_description(description, video_id, base_trimmed)
+}
+
 fn recursive_find(obj: &serde_json::Value, key: &str) -> Vec<serde_json::Value> {
     let mut found = Vec::new();
     if let Some(obj_map) = obj.as_object() {
@@ -415,8 +633,8 @@ fn search_number_near(data: &serde_json::Value, words: &[&str]) -> String {
     for s in all_strings(data) {
         let sl = s.to_lowercase();
         if words.iter().any(|w| sl.contains(w)) {
-            if let Some(captures) = regex::Regex::new(r"[\d][\d,. ]*").unwrap().captures(&s) {
-                return captures[0].replace(" ", "").replace(",", "");
+            if let Some(count) = crate::locale::parse_count(&s) {
+                return count.to_string();
             }
         }
     }
@@ -514,55 +732,17 @@ captures(acc_text) {
         }
     }
     
-    search_number_near(next_data, &["like", "likes", "лайк", "лайков", "лайка"])
+    search_number_near(
+        next_data,
+        &["like", "лайк", "gefällt", "j'aime", "me gusta", "gostei", "mi piace", "polub", "beğen", "いいね", "赞", "좋아요"],
+    )
 }
 
 fn parse_human_number(s: &str) -> String {
     if s.is_empty() {
         return "0".to_string();
     }
-    
-    let trimmed = s.trim();
-    let mut cleaned = String::with_capacity(trimmed.len());
-    
-    for c in trimmed.chars() {
-        if c != ',' && c != ' ' {
-            cleaned.push(c.to_ascii_uppercase());
-        }
-    }
-    
-    if cleaned.len() > 1 {
-        let last_char = cleaned.chars().last().unwrap();
-        if last_char.is_alphabetic() {
-            let num_part = &cleaned[..cleaned.len()-1];
-            match last_char {
-                'K' => {
-                    if let Ok(num) = num_part.parse::<f64>() {
-                        return ((num * 1000.0).round() as i64).to_string();
-                    }
-                },
-                'M' => {
-                    if let Ok(num) = num_part.parse::<f64>() {
-                        return ((num * 1000000.0).round() as i64).to_string();
-                    }
-                },
-                'B' => {
-                    if let Ok(num) = num_part.parse::<f64>() {
-                        return ((num * 1000000000.0).round() as i64).to_string();
-                    }
-                },
-                _ => {} // Not a recognized multiplier
-            }
-        }
-    }
-    
-    let mut result = String::new();
-    for c in cleaned.chars() {
-        if c.is_ascii_digit() {
-            result.push(c);
-        }
-    }
-    result
+    crate::locale::count_string(s, "")
 }
 
 fn find_subscriber_count(nd: &serde_json::Value) -> String {
@@ -587,30 +767,10 @@ fn find_subscriber_count(nd: &serde_json::Value) -> String {
 and_then(|r| r.get("text").and_then(|t| t.as_str()))
                                                 });
                                             if let Some(simple_text) = text {
-                                                let cleaned = simple_text.replace(" подписчиков", "").replace(" подписчик", "").
-replace(" subscribers", "").replace(" subscriber", "");
-                                                
-                                                let re = regex::Regex::new(r"([\d,]+\.?\d*)\s*(млн|тыс|[KM]?)").unwrap();
-                                                if let Some(captures) = re.captures(&cleaned) {
-                                                    let number_part = &captures[1].replace(",", "").replace(".", ""); // Removecommas and dots
-                                                    let multiplier = &captures[2];
-                                                    
-                                                    if let Ok(number) = number_part.parse::<f64>() {
-                                                        let result = match multiplier {
-                                                            "млн" => (number * 1000000.0) as u64, // Russian million
-                                                            "тыс" => (number * 1000.0) as u64,    // Russian thousand
-                                                            "K" => (number * 1000.0) as u64,      // English thousand
-                                                            "M" => (number * 1000000.0) as u64,   // English million
-                                                            _ => number as u64,                   // No multiplier
-                                                        };
-                                                        return result.to_string();
-                                                    }
-                                                }
-                                                let digits: String = cleaned.chars().filter(|c| c.is_ascii_digit()).collect();
-                                                if !digits.is_empty() {
-                                                    return digits;
+                                                if let Some(count) = crate::locale::parse_count(simple_text) {
+                                                    return count.to_string();
                                                 }
-                                                return cleaned;
+                                                return simple_text.to_string();
                                             }
                                         }
                                     }
@@ -684,48 +844,10 @@ fn find_comments_count(pr: &serde_json::Value, nd: &serde_json::Value) -> String
             }
         }
     }
-    search_number_near(nd, &["comment", "comments", "коммент", "коммента"])
-}
-
-fn translate_russian_time(time_str: &str) -> String {
-    let time_lower = time_str.to_lowercase();
-    
-    let translations = [
-        ("несколько секунд назад", "a few seconds ago"),
-        ("секунду назад", "a second ago"),
-        (" секунд назад", " seconds ago"),
-        (" секунды назад", " seconds ago"),
-        (" минуту назад", " a minute ago"),
-        (" минут назад", " minutes ago"),
-        (" часа назад", " hours ago"),
-        (" часов назад", " hours ago"),
-        (" день назад", " a day ago"),
-        (" дней назад", " days ago"),
-        (" недели назад", " weeks ago"),
-        (" недель назад", " weeks ago"),
-        (" месяц назад", " a month ago"),
-        (" месяцев назад", " months ago"),
-        (" года назад", " years ago"),
-        (" лет назад", " years ago"),
-        ("только что", "just now"),
-        ("сегодня", "today"),
-        ("вчера", "yesterday"),
-        ("позавчера", "day before yesterday"),
-    ];
-    
-    let mut result = time_str.to_string();
-    for (russian, english) in &translations {
-        if time_lower.contains(russian) {
-            result = result.replace(russian, english);
-            let capitalized_russian = format!("{}{}", 
-                russian.chars().next().unwrap().to_uppercase().collect::<String>(),
-                &russian[1..]
-            );
-            result = result.replace(&capitalized_russian, english);
-        }
-    }
-    
-    result
+    search_number_near(
+        nd,
+        &["comment", "коммент", "kommentar", "comentario", "comentário", "komentarz", "yorum", "コメント", "评论", "댓글"],
+    )
 }
 
 fn extract_comments(data: &serde_json::Value, base_url: &str) -> Vec<Comment> {
@@ -781,7 +903,7 @@ fn extract_comments(data: &serde_json::Value, base_url: &str) -> Vec<Comment> {
                         .and_then(|p| p.as_str())
                         .unwrap_or("unknown");
                     
-                    let published_at = translate_russian_time(published_at_raw);
+                    let published_at = crate::locale::normalize_relative_time(published_at_raw);
                     
                     let author_thumbnail_raw = p
                         .get("avatar")
@@ -823,14 +945,9 @@ fn extract_comments(data: &serde_json::Value, base_url: &str) -> Vec<Comment> {
 }
 
 lazy_static! {
-    static ref THUMBNAIL_CACHE: Arc<Mutex<LruCache<String, (Vec<u8>, String, u64)>>> = Arc::new(
-        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(1000).unwrap()))
-    );
     static ref DIRECT_URL_CLEANUP_STARTED: AtomicBool = AtomicBool::new(false);
 }
 
-const CACHE_DURATION: u64 = 3600;
-
 fn ffmpeg_binary() -> String {
     let exe_name = if cfg!(target_os = "windows") { "ffmpeg.exe" } else { "ffmpeg" };
 
@@ -886,6 +1003,410 @@ fn get_duration_from_player_response(data: &serde_json::Value) -> u64 {
     0 // Если не нашли, считаем видео коротким/потоком
 }
 
+/// Live / premiere state from a player response. Live streams have no fixed
+/// duration, so they must never go through the VOD download/mux path.
+#[derive(Debug, Clone)]
+struct LiveStatus {
+    /// Broadcasting right now (including a premiere that is playing).
+    is_live: bool,
+    /// Scheduled stream or premiere that has not started yet.
+    is_upcoming: bool,
+    /// Upcoming premiere (pre-recorded video) rather than a live broadcast.
+    is_premiere: bool,
+    /// Scheduled start as unix time.
+    scheduled_start: Option<i64>,
+}
+
+impl LiveStatus {
+    fn from_player_response(data: &Value) -> Self {
+        let vd = data.get("videoDetails").unwrap_or(&Value::Null);
+        let broadcast = data
+            .get("microformat")
+            .and_then(|m| m.get("playerMicroformatRenderer"))
+            .and_then(|p| p.get("liveBroadcastDetails"))
+            .unwrap_or(&Value::Null);
+        let playability = data.get("playabilityStatus").unwrap_or(&Value::Null);
+
+        let is_upcoming = vd.get("isUpcoming").and_then(|v| v.as_bool()).unwrap_or(false)
+            || playability.get("status").and_then(|s| s.as_str()) == Some("LIVE_STREAM_OFFLINE");
+        let is_live = !is_upcoming
+            && (vd.get("isLive").and_then(|v| v.as_bool()).unwrap_or(false)
+                || broadcast.get("isLiveNow").and_then(|v| v.as_bool()).unwrap_or(false));
+        let is_live_content = vd.get("isLiveContent").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let scheduled_start = playability
+            .get("liveStreamability")
+            .and_then(|l| l.get("liveStreamabilityRenderer"))
+            .and_then(|l| l.get("offlineSlate"))
+            .and_then(|o| o.get("liveStreamOfflineSlateRenderer"))
+            .and_then(|o| o.get("scheduledStartTime"))
+            .and_then(|t| t.as_str())
+            .and_then(|t| t.parse::<i64>().ok())
+            .or_else(|| {
+                broadcast
+                    .get("startTimestamp")
+                    .and_then(|t| t.as_str())
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.timestamp())
+            });
+
+        LiveStatus {
+            is_live,
+            is_upcoming,
+            is_premiere: is_upcoming && !is_live_content,
+            scheduled_start,
+        }
+    }
+
+    fn scheduled_start_rfc3339(&self) -> Option<String> {
+        self.scheduled_start
+            .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+            .map(|t| t.to_rfc3339())
+    }
+
+    /// Error for a stream or premiere that has not started: 503 with Retry-After
+    /// when the start time is known.
+    fn not_started_response(&self, video_id: &str) -> HttpResponse {
+        let kind = if self.is_premiere { "Premiere" } else { "Live stream" };
+        let details = match self.scheduled_start_rfc3339() {
+            Some(start) => format!("{} {} is scheduled to start at {}", kind, video_id, start),
+            None => format!("{} {} has not started yet", kind, video_id),
+        };
+        let mut builder = HttpResponse::ServiceUnavailable();
+        if let Some(start) = self.scheduled_start {
+            let wait = (start - chrono::Utc::now().timestamp()).max(60);
+            builder.insert_header(("Retry-After", wait.to_string()));
+        }
+        builder.json(serde_json::json!({
+            "error": format!("{} has not started yet", kind),
+            "details": details,
+            "scheduled_start": self.scheduled_start_rfc3339()
+        }))
+    }
+}
+
+/// Picks the variant playlist closest to `max_height` (highest not above it,
+/// otherwise the smallest) from an HLS master playlist.
+async fn select_hls_variant(manifest_url: &str, max_height: u32, user_agent: &str) -> Result<String, String> {
+    let body = Client::new()
+        .get(manifest_url)
+        .header("User-Agent", user_agent)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch HLS manifest: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read HLS manifest: {}", e))?;
+
+    let mut variants: Vec<(u32, String)> = Vec::new();
+    let mut pending_height: Option<u32> = None;
+    for line in body.lines().map(str::trim) {
+        if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            pending_height = Some(
+                attrs
+                    .split(',')
+                    .find_map(|a| a.strip_prefix("RESOLUTION="))
+                    .and_then(|r| r.split('x').nth(1))
+                    .and_then(|h| h.parse().ok())
+                    .unwrap_or(0),
+            );
+        } else if !line.is_empty() && !line.starts_with('#') {
+            if let Some(height) = pending_height.take() {
+                variants.push((height, line.to_string()));
+            }
+        }
+    }
+    if variants.is_empty() {
+        // Уже media playlist, а не master
+        return Ok(manifest_url.to_string());
+    }
+
+    let best = variants
+        .iter()
+        .filter(|(h, _)| *h <= max_height)
+        .max_by_key(|(h, _)| *h)
+        .or_else(|| variants.iter().min_by_key(|(h, _)| *h))
+        .map(|(_, url)| url.clone())
+        .unwrap_or_default();
+    if best.starts_with("http") {
+        Ok(best)
+    } else {
+        reqwest::Url::parse(manifest_url)
+            .and_then(|base| base.join(&best))
+            .map(|u| u.to_string())
+            .map_err(|e| format!("Bad variant URL '{}': {}", best, e))
+    }
+}
+
+/// Manifests and segments are bound to the server's IP, so live HLS is only
+/// proxied for YouTube hosts.
+fn is_youtube_media_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+        .map(|host| {
+            ["googlevideo.com", "youtube.com"]
+                .iter()
+                .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+        })
+        .unwrap_or(false)
+}
+
+/// Rewrites an HLS playlist for clients: nested playlists come back through
+/// `/hls.proxy`, segments and keys go through `/video.proxy` (or stay direct
+/// with `use_proxy` off). Relative URIs are resolved against `playlist_url`.
+fn rewrite_hls_playlist(body: &str, playlist_url: &str, base_trimmed: &str, use_proxy: bool) -> String {
+    let base = reqwest::Url::parse(playlist_url).ok();
+    let absolute = |uri: &str| -> String {
+        base.as_ref()
+            .and_then(|b| b.join(uri).ok())
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| uri.to_string())
+    };
+    let playlist = |uri: &str| -> String {
+        format!(
+            "{}/hls.proxy?url={}{}",
+            base_trimmed,
+            urlencoding::encode(&absolute(uri)),
+            if use_proxy { "" } else { "&proxy=false" }
+        )
+    };
+    let media = |uri: &str| -> String {
+        if use_proxy {
+            format!("{}/video.proxy?url={}", base_trimmed, urlencoding::encode(&absolute(uri)))
+        } else {
+            absolute(uri)
+        }
+    };
+
+    let mut out = String::with_capacity(body.len() * 2);
+    let mut next_is_playlist = false;
+    for line in body.lines().map(str::trim_end) {
+        if line.starts_with('#') {
+            next_is_playlist = line.starts_with("#EXT-X-STREAM-INF");
+            match line.find("URI=\"") {
+                Some(start) => {
+                    let value_start = start + 5;
+                    let value_end = line[value_start..].find('"').map(|e| value_start + e).unwrap_or(line.len());
+                    let uri = &line[value_start..value_end];
+                    let nested = line.starts_with("#EXT-X-MEDIA") || line.starts_with("#EXT-X-I-FRAME-STREAM-INF");
+                    let rewritten = if nested { playlist(uri) } else { media(uri) };
+                    out.push_str(&line[..value_start]);
+                    out.push_str(&rewritten);
+                    out.push_str(&line[value_end..]);
+                }
+                None => out.push_str(line),
+            }
+        } else if line.is_empty() {
+            out.push_str(line);
+        } else if next_is_playlist || line.contains(".m3u8") {
+            out.push_str(&playlist(line));
+            next_is_playlist = false;
+        } else {
+            out.push_str(&media(line));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Fetches an HLS playlist and answers with the rewritten copy.
+async fn serve_hls_playlist(req: &HttpRequest, config: &crate::config::Config, playlist_url: &str, use_proxy: bool) -> HttpResponse {
+    let response = Client::new()
+        .get(playlist_url)
+        .header("User-Agent", config.get_innertube_user_agent())
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await;
+    let body = match response {
+        Ok(resp) if resp.status().is_success() => match resp.text().await {
+            Ok(body) => body,
+            Err(e) => {
+                return HttpResponse::BadGateway().json(serde_json::json!({
+                    "error": "Failed to read HLS playlist",
+                    "details": e.to_string()
+                }));
+            }
+        },
+        Ok(resp) => {
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "Failed to fetch HLS playlist",
+                "details": format!("HTTP {}", resp.status())
+            }));
+        }
+        Err(e) => {
+            return HttpResponse::BadGateway().json(serde_json::json!({
+                "error": "Failed to fetch HLS playlist",
+                "details": e.to_string()
+            }));
+        }
+    };
+    let base = base_url(req, config);
+    let rewritten = rewrite_hls_playlist(&body, playlist_url, base.trim_end_matches('/'), use_proxy);
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, HeaderValue::from_static("application/vnd.apple.mpegurl")))
+        .insert_header(("Cache-Control", "no-cache, no-store"))
+        .body(rewritten)
+}
+
+/// ffmpeg output arguments for a live stream: a plain remux to MPEG-TS, or one
+/// of the conversion codecs adjusted for non-seekable output (fragmented MP4/3GP,
+/// FLV without the keyframe index that needs a rewrite at the end).
+fn live_ffmpeg_args(codec: Option<ConversionCodec>, settings: &ConversionSettings) -> (Vec<String>, &'static str) {
+    let codec = match codec {
+        Some(c) => c,
+        None => {
+            let args = ["-c", "copy", "-f", "mpegts"];
+            return (args.iter().map(|a| a.to_string()).collect(), "video/mp2t");
+        }
+    };
+    let mut args = codec.ffmpeg_args(settings);
+    if let Some(pos) = args.iter().position(|a| a == "-flvflags") {
+        args.drain(pos..(pos + 2).min(args.len()));
+    }
+    if matches!(codec, ConversionCodec::Mpeg4 | ConversionCodec::H263) {
+        set_ffmpeg_option(&mut args, "-movflags", "frag_keyframe+empty_moov".to_string());
+    }
+    (args, codec.mime_type())
+}
+
+/// Pipes a live HLS variant through ffmpeg to the client until either side stops.
+/// Nothing is cached: the output has no end and no fixed size.
+fn stream_live_video(
+    playlist_url: String,
+    user_agent: String,
+    args: Vec<String>,
+    mime_type: &'static str,
+    permit: Option<crate::conversions::ConversionPermit>,
+) -> HttpResponse {
+    let (tx, rx) = mpsc::channel::<std::result::Result<Bytes, std::io::Error>>(8);
+
+    std::thread::spawn(move || {
+        let _permit = permit;
+        let mut cmd = Command::new(ffmpeg_binary());
+        cmd.args(["-hide_banner", "-loglevel", "error", "-nostdin", "-user_agent"])
+            .arg(&user_agent)
+            .args(["-i", &playlist_url])
+            .args(&args)
+            .arg("pipe:1");
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null());
+
+        let mut child = match cmd.spawn() {
+            Ok(c) => c,
+            Err(e) => {
+                let _ = tx.blocking_send(Err(std::io::Error::other(format!("FFmpeg failed to start: {}", e))));
+                return;
+            }
+        };
+        if let Some(mut stdout) = child.stdout.take() {
+            let mut buffer = [0u8; 65536];
+            loop {
+                match stdout.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // Клиент отключился — останавливаем ffmpeg
+                        if tx.blocking_send(Ok(Bytes::copy_from_slice(&buffer[..n]))).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.blocking_send(Err(e));
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+    });
+
+    let stream = ReceiverStream::new(rx).map(|r| r.map_err(actix_web::error::ErrorInternalServerError));
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, HeaderValue::from_static(mime_type)))
+        .insert_header(("Cache-Control", "no-cache, no-store"))
+        .streaming(stream)
+}
+
+/// Serves a live broadcast. Without a codec: `live=hls` (default) serves the HLS
+/// manifest rewritten to go through this server (`proxy=false` leaves segments
+/// direct), `live=ts` remuxes one variant to MPEG-TS, `live=3gp` transcodes to
+/// H.263/AMR for phones. With a codec the variant is transcoded to that codec.
+async fn serve_live_stream(
+    req: &HttpRequest,
+    data: &web::Data<crate::AppState>,
+    video_id: &str,
+    player_response: &Value,
+    codec: Option<ConversionCodec>,
+    settings: ConversionSettings,
+    live_mode: &str,
+) -> HttpResponse {
+    let manifest_url = match get_hls_manifest_url_from_player(player_response) {
+        Ok(url) => url,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to get HLS manifest URL for live stream",
+                "details": e
+            }));
+        }
+    };
+
+    let (codec, settings) = match (codec, live_mode) {
+        (Some(c), _) => (Some(c), settings),
+        (None, "3gp") => (
+            Some(ConversionCodec::H263),
+            ConversionSettings::new(ConversionCodec::H263.target_height(settings.height)),
+        ),
+        (None, "ts") => (None, settings),
+        (None, "hls") => {
+            let use_proxy = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                .ok()
+                .and_then(|q| q.get("proxy").map(|p| p.to_lowercase()))
+                .map(|p| p != "false")
+                .unwrap_or(true);
+            log::info!("Live {}: serving proxied HLS manifest", video_id);
+            return serve_hls_playlist(req, &data.config, &manifest_url, use_proxy).await;
+        }
+        (None, other) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Unsupported live mode",
+                "details": format!("live='{}'; available: hls, ts, 3gp", other)
+            }));
+        }
+    };
+
+    let user_agent = data.config.get_innertube_user_agent();
+    let variant_url = match select_hls_variant(&manifest_url, settings.height, &user_agent).await {
+        Ok(url) => url,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to select live stream variant",
+                "details": e
+            }));
+        }
+    };
+    let (args, mime_type) = live_ffmpeg_args(codec, &settings);
+    if req.method() == actix_web::http::Method::HEAD {
+        return HttpResponse::Ok()
+            .insert_header((CONTENT_TYPE, HeaderValue::from_static(mime_type)))
+            .finish();
+    }
+    // Ремукс без перекодирования почти не нагружает CPU, семафор нужен только кодекам
+    let permit = match codec {
+        Some(c) => {
+            let label = format!("live {} {} {}p", video_id, c.as_str(), settings.height);
+            crate::conversions::acquire(&data.codec_semaphore, label).await
+        }
+        None => None,
+    };
+    log::info!(
+        "Live {}: streaming {} ({}p)",
+        video_id,
+        codec.map(|c| c.as_str()).unwrap_or("mpegts"),
+        settings.height
+    );
+    stream_live_video(variant_url, user_agent, args, mime_type, permit)
+}
+
 fn yt_dlp_binary() -> String {
     if cfg!(target_os = "windows") {
         if Path::new("assets/yt-dlp.exe").exists() {
@@ -1006,176 +1527,906 @@ fn parse_quality_height(quality: &str) -> Option<u32> {
 }
 
 
+/// Target formats for on-the-fly conversion requested via `/direct_url?codec=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConversionCodec {
+    Mpeg4,
+    H263,
+    /// Sorenson Spark (H.263) + MP3 in FLV, as served to the AS2 player (itag 5).
+    Flv,
+    /// H.264 + AAC in FLV, as served to the AS3 player (itag 34).
+    FlvH264,
+    /// VP8 + Vorbis in WebM for early HTML5 browsers (itags 43/44/45).
+    Webm,
+    /// Theora + Vorbis in Ogg for HTML5 browsers without WebM support.
+    Theora,
+}
+
+/// Size of the FLV file header plus the first `PreviousTagSize0` field.
+const FLV_HEADER_LEN: u64 = 13;
+
+impl ConversionCodec {
+    const SUPPORTED: [&'static str; 6] = ["mpeg4", "h263", "flv", "flv_h264", "webm", "theora"];
+
+    fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "mpeg4" => Some(Self::Mpeg4),
+            "h263" => Some(Self::H263),
+            "flv" => Some(Self::Flv),
+            "flv_h264" => Some(Self::FlvH264),
+            "webm" | "vp8" => Some(Self::Webm),
+            "theora" | "ogg" => Some(Self::Theora),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mpeg4 => "mpeg4",
+            Self::H263 => "h263",
+            Self::Flv => "flv",
+            Self::FlvH264 => "flv_h264",
+            Self::Webm => "webm",
+            Self::Theora => "theora",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Mpeg4 => "mp4",
+            Self::H263 => "3gp",
+            Self::Flv | Self::FlvH264 => "flv",
+            Self::Webm => "webm",
+            Self::Theora => "ogv",
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Mpeg4 => "video/mp4",
+            Self::H263 => "video/3gpp",
+            Self::Flv | Self::FlvH264 => "video/x-flv",
+            Self::Webm => "video/webm",
+            Self::Theora => "video/ogg",
+        }
+    }
+
+    /// Container name as used in device profiles.
+    fn container(&self) -> &'static str {
+        match self {
+            Self::Mpeg4 => "mp4",
+            Self::H263 => "3gp",
+            Self::Flv | Self::FlvH264 => "flv",
+            Self::Webm => "webm",
+            Self::Theora => "ogg",
+        }
+    }
+
+    fn is_flv(&self) -> bool {
+        matches!(self, Self::Flv | Self::FlvH264)
+    }
+
+    /// Legacy itag a converted stream is advertised under in `url_encoded_fmt_stream_map`.
+    /// Old players pick the decoder by itag, so the itag's container must match ours;
+    /// Ogg/Theora never had one and is not advertised at all.
+    fn legacy_itag(&self) -> Option<u32> {
+        match self {
+            Self::Mpeg4 => Some(18),
+            Self::H263 => Some(13),
+            Self::Flv => Some(5),
+            Self::FlvH264 => Some(34),
+            Self::Webm => Some(43),
+            Self::Theora => None,
+        }
+    }
+
+    /// Output height for a requested quality. Legacy phone/Flash formats have
+    /// fixed sizes (CIF for H.263, 240p FLV, 360p otherwise); WebM/Ogg follow
+    /// the itag 43/44/45 ladder (360/480/720).
+    fn target_height(&self, requested: u32) -> u32 {
+        match self {
+            Self::Webm | Self::Theora => {
+                if requested <= 360 {
+                    360
+                } else if requested <= 480 {
+                    480
+                } else {
+                    720
+                }
+            }
+            Self::H263 => 288,
+            Self::Flv => 240,
+            _ => 360,
+        }
+    }
+
+    fn ffmpeg_args(&self, settings: &ConversionSettings) -> Vec<String> {
+        let height = settings.height;
+        // Не растягиваем исходник, если он меньше целевой высоты
+        let scale = format!("scale=-2:min({}\\,ih)", height);
+        // H.263 умеет только фиксированные размеры кадра (sub-QCIF/QCIF/CIF)
+        let h263_scale = match height {
+            h if h <= 96 => "scale=128:96",
+            h if h <= 144 => "scale=176:144",
+            _ => "scale=352:288",
+        };
+        let video_bitrate = match height {
+            h if h <= 360 => "600k",
+            h if h <= 480 => "1000k",
+            _ => "2000k",
+        };
+        let args: Vec<&str> = match self {
+            Self::Mpeg4 => vec![
+                "-c:v", "mpeg4", "-vtag", "mp4v", "-vf", &scale, "-b:v", "501k",
+                "-brand", "isom", "-pix_fmt", "yuv420p",
+                "-c:a", "copy", "-f", "mp4",
+            ],
+            Self::H263 => vec![
+                "-c:v", "h263", "-vf", h263_scale,
+                "-c:a", "libopencore_amrnb", "-ar", "8000", "-ac", "1",
+                "-f", "3gp",
+            ],
+            // add_keyframe_index пишет в onMetaData таблицу keyframes (times/filepositions),
+            // по которой Flash-плееры запрашивают перемотку через start=<байтовое смещение>.
+            Self::Flv => vec![
+                "-c:v", "flv1", "-vf", &scale, "-b:v", "300k", "-g", "25",
+                "-c:a", "libmp3lame", "-ar", "22050", "-ac", "2", "-b:a", "64k",
+                "-flvflags", "add_keyframe_index", "-f", "flv",
+            ],
+            Self::FlvH264 => vec![
+                "-c:v", "libx264", "-profile:v", "main", "-preset", "veryfast",
+                "-vf", &scale, "-b:v", "500k", "-g", "50", "-pix_fmt", "yuv420p",
+                "-c:a", "aac", "-ar", "44100", "-b:a", "128k",
+                "-flvflags", "add_keyframe_index", "-f", "flv",
+            ],
+            Self::Webm => vec![
+                "-c:v", "libvpx", "-vf", &scale, "-b:v", video_bitrate,
+                "-deadline", "realtime", "-cpu-used", "8", "-pix_fmt", "yuv420p",
+                "-c:a", "libvorbis", "-ar", "44100", "-b:a", "128k",
+                "-f", "webm",
+            ],
+            Self::Theora => vec![
+                "-c:v", "libtheora", "-vf", &scale, "-b:v", video_bitrate,
+                "-pix_fmt", "yuv420p",
+                "-c:a", "libvorbis", "-ar", "44100", "-b:a", "128k",
+                "-f", "ogg",
+            ],
+        };
+        let mut args: Vec<String> = args.into_iter().map(String::from).collect();
+
+        if let Some(kbps) = settings.video_bitrate_kbps {
+            set_ffmpeg_option(&mut args, "-b:v", format!("{}k", kbps));
+        }
+        let audio_overridden = settings.audio_bitrate_kbps.is_some()
+            || settings.audio_channels.is_some()
+            || settings.audio_sample_rate.is_some();
+        if audio_overridden && *self == Self::Mpeg4 {
+            // Исходный AAC копируется как есть, для новых параметров его нужно перекодировать
+            set_ffmpeg_option(&mut args, "-c:a", "aac".to_string());
+        }
+        if let Some(kbps) = settings.audio_bitrate_kbps {
+            let bps = if *self == Self::H263 {
+                AudioFormat::AmrNb.bitrate_bps(Some(kbps as f64))
+            } else {
+                kbps * 1000
+            };
+            set_ffmpeg_option(&mut args, "-b:a", bps.to_string());
+        }
+        if let Some(channels) = settings.audio_channels {
+            if *self != Self::H263 {
+                set_ffmpeg_option(&mut args, "-ac", channels.to_string());
+            }
+        }
+        if let Some(rate) = settings.audio_sample_rate {
+            // AMR-NB работает только на 8 кГц
+            if *self != Self::H263 {
+                set_ffmpeg_option(&mut args, "-ar", rate.to_string());
+            }
+        }
+        if let Some(subs) = &settings.subtitles_file {
+            // Субтитры рисуем после масштабирования, чтобы шрифт был читаем на маленьком экране
+            let filter = format!("subtitles={}", ffmpeg_filter_path(subs));
+            match args.iter().position(|a| a == "-vf") {
+                Some(pos) if pos + 1 < args.len() => {
+                    args[pos + 1] = format!("{},{}", args[pos + 1], filter);
+                }
+                _ => set_ffmpeg_option(&mut args, "-vf", filter),
+            }
+        }
+        args
+    }
+}
+
+/// Escapes a path as a filtergraph option value: first for the option parser
+/// (`:` and `'`), then for the filtergraph itself (`\`, `'`, `,`, `;`, `[`, `]`).
+fn ffmpeg_filter_path(path: &Path) -> String {
+    let option_escaped = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "\\'");
+    let mut out = String::with_capacity(option_escaped.len());
+    for c in option_escaped.chars() {
+        if matches!(c, '\\' | '\'' | ',' | ';' | '[' | ']') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Replaces the value of `flag` in an ffmpeg argument list, or adds the option
+/// before the output format (`-f`) if it is not present yet.
+fn set_ffmpeg_option(args: &mut Vec<String>, flag: &str, value: String) {
+    if let Some(pos) = args.iter().position(|a| a == flag) {
+        if pos + 1 < args.len() {
+            args[pos + 1] = value;
+            return;
+        }
+    }
+    let pos = args.iter().position(|a| a == "-f").unwrap_or(args.len());
+    args.insert(pos, value);
+    args.insert(pos, flag.to_string());
+}
+
+/// Output size and encoder overrides for one `/direct_url?codec=` conversion.
+#[derive(Debug, Clone)]
+struct ConversionSettings {
+    height: u32,
+    video_bitrate_kbps: Option<u32>,
+    audio_bitrate_kbps: Option<u32>,
+    audio_channels: Option<u32>,
+    audio_sample_rate: Option<u32>,
+    /// Device profile the overrides come from; its output is cached separately.
+    profile: Option<String>,
+    /// Caption language burned into the picture (`subs=`).
+    subtitles_lang: Option<String>,
+    /// SRT file for `subtitles_lang`, written right before the conversion.
+    subtitles_file: Option<PathBuf>,
+    /// `PlayerAuth` scope when the source needed the user's credentials.
+    user_scope: Option<String>,
+}
+
+impl ConversionSettings {
+    fn new(height: u32) -> Self {
+        Self {
+            height,
+            video_bitrate_kbps: None,
+            audio_bitrate_kbps: None,
+            audio_channels: None,
+            audio_sample_rate: None,
+            profile: None,
+            subtitles_lang: None,
+            subtitles_file: None,
+            user_scope: None,
+        }
+    }
+
+    fn from_profile(name: &str, profile: &DeviceProfile) -> Self {
+        Self {
+            height: profile.max_height.clamp(96, 720),
+            video_bitrate_kbps: profile.video_bitrate,
+            audio_bitrate_kbps: profile.audio_bitrate,
+            audio_channels: profile.audio_channels,
+            audio_sample_rate: profile.audio_sample_rate,
+            profile: Some(name.to_string()),
+            subtitles_lang: None,
+            subtitles_file: None,
+            user_scope: None,
+        }
+    }
+}
+
+/// Checks that a profile's codec is known and matches its container.
+/// Returns `None` for profiles that use the native H.264 stream.
+fn device_profile_codec(profile: &DeviceProfile) -> Result<Option<ConversionCodec>, String> {
+    let container = profile.container.trim().to_lowercase();
+    match profile.conversion_codec() {
+        None if container == "mp4" => Ok(None),
+        None => Err(format!("h264 is only served in mp4, not {}", container)),
+        Some(name) => {
+            let codec = ConversionCodec::from_param(name)
+                .ok_or_else(|| format!("unsupported codec '{}'", name))?;
+            if codec.container() == container {
+                Ok(Some(codec))
+            } else {
+                Err(format!(
+                    "codec {} is served in {}, not {}",
+                    codec.as_str(),
+                    codec.container(),
+                    container
+                ))
+            }
+        }
+    }
+}
+
+/// Legacy itag and MIME type under which a device profile's stream is advertised
+/// to the old players' `url_encoded_fmt_stream_map`; None when no itag fits its
+/// container or the profile is invalid.
+pub(crate) fn device_profile_stream_format(profile: &DeviceProfile) -> Option<(u32, &'static str)> {
+    match device_profile_codec(profile) {
+        Ok(Some(codec)) => codec.legacy_itag().map(|itag| (itag, codec.mime_type())),
+        Ok(None) => Some((18, "video/mp4")),
+        Err(_) => None,
+    }
+}
+
+fn converted_cache_path(
+    temp_dir: &Path,
+    video_id: &str,
+    codec: ConversionCodec,
+    settings: &ConversionSettings,
+) -> PathBuf {
+    let profile_suffix = settings
+        .profile
+        .as_deref()
+        .map(|p| format!("_{}", p.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_")))
+        .unwrap_or_default();
+    // Параметры кодирования профиля тоже в имени: после правки профиля в конфиге
+    // старый файл с прежними битрейтами не должен отдаваться
+    let encoding_suffix: String = [
+        settings.video_bitrate_kbps.map(|v| format!("-v{}k", v)),
+        settings.audio_bitrate_kbps.map(|a| format!("-a{}k", a)),
+        settings.audio_channels.map(|c| format!("-c{}", c)),
+        settings.audio_sample_rate.map(|r| format!("-r{}", r)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let subs_suffix = settings
+        .subtitles_lang
+        .as_deref()
+        .map(|l| format!("_subs-{}", l))
+        .unwrap_or_default();
+    let user_suffix = settings
+        .user_scope
+        .as_deref()
+        .map(|u| format!("_u{}", u))
+        .unwrap_or_default();
+    temp_dir.join(format!(
+        "yt_api_video_{}_{}_{}p{}{}{}{}.{}",
+        video_id,
+        codec.as_str(),
+        settings.height,
+        profile_suffix,
+        encoding_suffix,
+        subs_suffix,
+        user_suffix,
+        codec.extension()
+    ))
+}
+
+/// Writes the caption track for `lang` as SRT next to the conversion output.
+async fn write_subtitles_file(
+    video_id: &str,
+    lang: &str,
+    temp_dir: &Path,
+    config: &crate::config::Config,
+) -> Result<PathBuf, String> {
+    let cues = crate::routes::captions::fetch_caption_cues(video_id, lang, None, None, config).await?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = temp_dir.join(format!("yt_api_video_{}_subs_{}_{}.srt", video_id, lang, millis));
+    fs::write(&path, crate::routes::captions::cues_to_srt(&cues)).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Byte offset to resume an FLV from, if `start` points inside the file body.
+fn flv_start_offset(codec: ConversionCodec, start: u64, file_size: u64) -> Option<u64> {
+    if codec.is_flv() && start > FLV_HEADER_LEN && start < file_size {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+/// Path of the in-progress file next to `cache_path`. Conversions write here and
+/// rename on success so a parallel request never serves a half-written file.
+/// Only written while holding `conversions::lock_output(cache_path)`.
+fn conversion_part_path(cache_path: &Path) -> PathBuf {
+    PathBuf::from(format!(
+        "{}.{}_{}.part",
+        cache_path.to_string_lossy(),
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis(),
+        std::process::id()
+    ))
+}
+
+/// Downloads `source_url` and pipes it through ffmpeg into `output` (blocking).
+/// `extra_inputs` are passed as additional `-i` inputs after the piped stream
+/// (e.g. cover art); `args` go between the inputs and the output path.
+fn transcode_to_file(
+    source_url: &str,
+    user_agent: &str,
+    extra_inputs: &[String],
+    args: &[String],
+    output: &Path,
+) -> Result<(), String> {
+    // 1. Download the stream using Rust (reqwest::blocking) instead of FFmpeg
+    // We move the network logic that caused the crash out of FFmpeg
+    let client = reqwest::blocking::Client::new();
+    let mut response = client
+        .get(source_url)
+        .header("User-Agent", user_agent)
+        .header("Referer", "https://www.youtube.com")
+        .header("Origin", "https://www.youtube.com")
+        .send()
+        .map_err(|e| format!("Failed to start download: {}", e))?;
+
+    // 2. Prepare FFmpeg to read from STDIN (pipe:0)
+    let mut cmd = Command::new(ffmpeg_binary());
+    cmd.args([
+        "-y",
+        "-hide_banner", "-loglevel", "error",
+        // REMOVED: -nostdin (we need stdin!)
+        // REMOVED: -reconnect, -user_agent, -headers, -i URL (network args)
+        "-i", "pipe:0", // Read from Stdin
+    ]);
+    for input in extra_inputs {
+        cmd.arg("-i").arg(input);
+    }
+    cmd.args(args);
+    cmd.arg(output.to_string_lossy().to_string());
+
+    // Configure Stdin to be piped
+    cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("FFmpeg failed to start: {}", e))?;
+
+    // 3. Pipe data from HTTP response to FFmpeg stdin
+    // We take() stdin here to get the handle
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut buffer = [0u8; 8192];
+        loop {
+            match response.read(&mut buffer) {
+                Ok(0) => break, // EOF
+                Ok(n) => {
+                    if stdin.write_all(&buffer[..n]).is_err() {
+                        // FFmpeg might have closed stdin early (error or finished)
+                        break; 
+                    }
+                }
+                Err(e) => {
+                    log::error!("Network read error: {}", e);
+                    break;
+                }
+            }
+        }
+    } 
+    // Drop stdin handle to signal EOF to FFmpeg
+
+    // 4. Wait for FFmpeg to finish
+    let output_status = child
+        .wait_with_output()
+        .map_err(|e| format!("FFmpeg wait error: {}", e))?;
+
+    if !output_status.status.success() {
+        let err_msg = String::from_utf8_lossy(&output_status.stderr).to_string();
+        log::error!(
+            "FFmpeg conversion failed. Status: {:?} | STDERR: {}",
+            output_status.status, err_msg
+        );
+        return Err(format!("FFmpeg failed: {}", err_msg));
+    }
+    Ok(())
+}
+
 fn stream_converted_video(
     source_url: &str,
     user_agent: &str,
-    _video_id: &str,
-    codec: &str,
-    _permit: Option<tokio::sync::OwnedSemaphorePermit>,
-    temp_dir: PathBuf,
+    codec: ConversionCodec,
+    settings: &ConversionSettings,
+    _permit: Option<crate::conversions::ConversionPermit>,
+    cache_path: PathBuf,
+    start: u64,
 ) -> HttpResponse {
     let source_url = source_url.to_string();
     let ua = user_agent.to_string();
-    let codec_str = codec.to_string();
+    let ffmpeg_args = codec.ffmpeg_args(settings);
+    let subtitles_file = settings.subtitles_file.clone();
     let (tx, rx) = mpsc::channel::<std::result::Result<Bytes, std::io::Error>>(8);
-    
-    let ffmpeg = ffmpeg_binary();
 
     std::thread::spawn(move || {
         let _permit = _permit; // Hold semaphore permit
-        let temp_dir = env::temp_dir();
-        let temp_file_name = format!(
-            "yt_api_video_{}_{}.{}",
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis(),
-            std::process::id(),
-            if codec_str == "mpeg4" { "mp4" } else { "3gp" }
-        );
-        let temp_file_path = temp_dir.join(temp_file_name);
-
-        // 1. Download the stream using Rust (reqwest::blocking) instead of FFmpeg
-        // We move the network logic that caused the crash out of FFmpeg
-        let client = reqwest::blocking::Client::new();
-        let download_result = client
-            .get(&source_url)
-            .header("User-Agent", &ua)
-            .header("Referer", "https://www.youtube.com")
-            .header("Origin", "https://www.youtube.com")
-            .send();
-
-        let mut response = match download_result {
-            Ok(r) => r,
+        let temp_file_path = conversion_part_path(&cache_path);
+
+        let transcode_result = transcode_to_file(&source_url, &ua, &[], &ffmpeg_args, &temp_file_path);
+        if let Some(subs) = &subtitles_file {
+            let _ = fs::remove_file(subs);
+        }
+        if let Err(e) = transcode_result {
+            let _ = tx.blocking_send(Err(std::io::Error::other(e)));
+            let _ = fs::remove_file(&temp_file_path);
+            return;
+        }
+
+        let result_path = match fs::rename(&temp_file_path, &cache_path) {
+            Ok(()) => cache_path,
             Err(e) => {
-                let _ = tx.blocking_send(Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to start download: {}", e)
-                )));
-                return;
+                log::warn!("Failed to move {} into cache: {}", temp_file_path.display(), e);
+                temp_file_path.clone()
             }
         };
 
-        // 2. Prepare FFmpeg to read from STDIN (pipe:0)
-        let mut cmd = Command::new(&ffmpeg);
-        cmd.args([
-            "-y",
-            "-hide_banner", "-loglevel", "error",
-            // REMOVED: -nostdin (we need stdin!)
-            // REMOVED: -reconnect, -user_agent, -headers, -i URL (network args)
-            "-i", "pipe:0", // Read from Stdin
-        ]);
-
-        if codec_str == "mpeg4" {
-            cmd.args([
-                "-c:v", "mpeg4", "-vtag", "mp4v", "-b:v", "501k",
-                "-brand", "isom", "-pix_fmt", "yuv420p",
-                "-c:a", "copy", "-f", "mp4",
-            ]);
-        } else {
-            cmd.args([
-                "-c:v", "h263", "-vf", "scale=352:288",
-                "-c:a", "libopencore_amrnb", "-ar", "8000", "-ac", "1",
-                "-f", "3gp",
-            ]);
+        // 5. Stream the resulting file back (Same logic as before)
+        match fs::File::open(&result_path) {
+            Ok(mut file) => {
+                let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+                if let Some(offset) = flv_start_offset(codec, start, file_size) {
+                    // Как videoplayback у старого YouTube: заголовок FLV + данные с keyframe-смещения
+                    let mut header = [0u8; FLV_HEADER_LEN as usize];
+                    if file.read_exact(&mut header).is_ok()
+                        && file.seek(std::io::SeekFrom::Start(offset)).is_ok()
+                    {
+                        let _ = tx.blocking_send(Ok(Bytes::copy_from_slice(&header)));
+                    } else {
+                        let _ = file.seek(std::io::SeekFrom::Start(0));
+                    }
+                }
+                let mut buffer = [0u8; 65536];
+                loop {
+                    match file.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(n) => {
+                            if tx.blocking_send(Ok(Bytes::copy_from_slice(&buffer[..n]))).is_err() { break; }
+                        }
+                        Err(e) => {
+                            let _ = tx.blocking_send(Err(e));
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = tx.blocking_send(Err(e));
+            }
+        }
+
+        if result_path == temp_file_path {
+            let _ = fs::remove_file(&temp_file_path);
         }
+    });
+
+    let stream = ReceiverStream::new(rx).map(|r| r.map(web::Bytes::from).map_err(actix_web::error::ErrorInternalServerError));
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, HeaderValue::from_static(codec.mime_type())))
+        .insert_header(("Cache-Control", "public, max-age=3600"))
+        .streaming(stream)
+}
+
+/// Serves an FLV from the conversion cache starting at a keyframe byte offset
+/// (`start=`), prefixed with the original FLV header so Flash players can resume.
+fn serve_flv_from_offset(path: &Path, offset: u64) -> HttpResponse {
+    let mut std_file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let file_size = std_file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    let mut header = [0u8; FLV_HEADER_LEN as usize];
+    if std_file.read_exact(&mut header).is_err() || &header[..3] != b"FLV" {
+        return HttpResponse::InternalServerError().finish();
+    }
+    if std_file.seek(std::io::SeekFrom::Start(offset)).is_err() {
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    let body_len = FLV_HEADER_LEN + file_size.saturating_sub(offset);
+    let tokio_file = tokio::fs::File::from_std(std_file);
+    let head = futures_util::stream::once(async move {
+        Ok::<Bytes, std::io::Error>(Bytes::copy_from_slice(&header))
+    });
+    let stream = head.chain(ReaderStream::with_capacity(tokio_file, 65536));
+
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, "video/x-flv"))
+        .insert_header((CONTENT_LENGTH, body_len.to_string()))
+        .insert_header(("Cache-Control", "public, max-age=3600"))
+        .streaming(stream)
+}
+
+/// Transcoded outputs for `/direct_audio_url?format=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AudioFormat {
+    Mp3,
+    /// AAC-LC in an iPod-compatible M4A container.
+    AacLc,
+    /// Vorbis in Ogg.
+    Ogg,
+    /// AMR narrowband for feature phones.
+    AmrNb,
+}
+
+/// Bitrates (bps) supported by the AMR-NB codec.
+const AMR_NB_BITRATES: [u32; 8] = [4750, 5150, 5900, 6700, 7400, 7950, 10200, 12200];
+
+impl AudioFormat {
+    const SUPPORTED: [&'static str; 4] = ["mp3", "aac-lc", "ogg", "amr-nb"];
+
+    fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "mp3" => Some(Self::Mp3),
+            "aac-lc" | "aac" | "m4a" => Some(Self::AacLc),
+            "ogg" | "vorbis" => Some(Self::Ogg),
+            "amr-nb" | "amr" => Some(Self::AmrNb),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::AacLc => "aac-lc",
+            Self::Ogg => "ogg",
+            Self::AmrNb => "amr-nb",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::AacLc => "m4a",
+            Self::Ogg => "ogg",
+            Self::AmrNb => "amr",
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "audio/mpeg",
+            Self::AacLc => "audio/mp4",
+            Self::Ogg => "audio/ogg",
+            Self::AmrNb => "audio/amr",
+        }
+    }
+
+    /// Cover art can be embedded as ID3 APIC (MP3) or `covr` atom (M4A) only.
+    fn supports_cover(&self) -> bool {
+        matches!(self, Self::Mp3 | Self::AacLc)
+    }
+
+    /// Raw AMR has no container metadata at all.
+    fn supports_tags(&self) -> bool {
+        !matches!(self, Self::AmrNb)
+    }
+
+    /// Clamps a requested bitrate (kbps) to what the encoder accepts, in bps.
+    fn bitrate_bps(&self, requested_kbps: Option<f64>) -> u32 {
+        match self {
+            Self::AmrNb => {
+                let wanted = requested_kbps.map(|k| (k * 1000.0) as u32).unwrap_or(12200);
+                AMR_NB_BITRATES
+                    .iter()
+                    .copied()
+                    .rev()
+                    .find(|b| *b <= wanted)
+                    .unwrap_or(AMR_NB_BITRATES[0])
+            }
+            _ => {
+                let (min, max) = match self {
+                    Self::Ogg => (48, 320),
+                    _ => (32, 320),
+                };
+                let kbps = requested_kbps.map(|k| k as u32).unwrap_or(128);
+                kbps.clamp(min, max) * 1000
+            }
+        }
+    }
+
+    fn ffmpeg_args(&self, bitrate_bps: u32, with_cover: bool) -> Vec<String> {
+        let bitrate = bitrate_bps.to_string();
+        let mut args: Vec<String> = vec!["-map".into(), "0:a:0".into()];
+        if with_cover {
+            args.extend(
+                ["-map", "1:v:0", "-c:v", "mjpeg", "-disposition:v", "attached_pic"]
+                    .iter()
+                    .map(|s| s.to_string()),
+            );
+        }
+        let codec_args: Vec<&str> = match self {
+            Self::Mp3 => vec![
+                "-c:a", "libmp3lame", "-b:a", &bitrate, "-ar", "44100",
+                "-id3v2_version", "3", "-write_id3v1", "1", "-f", "mp3",
+            ],
+            Self::AacLc => vec![
+                "-c:a", "aac", "-profile:a", "aac_low", "-b:a", &bitrate, "-ar", "44100",
+                "-movflags", "+faststart", "-f", "ipod",
+            ],
+            Self::Ogg => vec![
+                "-c:a", "libvorbis", "-b:a", &bitrate, "-ar", "44100", "-f", "ogg",
+            ],
+            Self::AmrNb => vec![
+                "-c:a", "libopencore_amrnb", "-b:a", &bitrate, "-ar", "8000", "-ac", "1",
+                "-f", "amr",
+            ],
+        };
+        args.extend(codec_args.into_iter().map(String::from));
+        args
+    }
+}
+
+/// Tags embedded into transcoded audio, taken from the player response.
+struct AudioTags {
+    title: String,
+    artist: String,
+    video_id: String,
+}
+
+impl AudioTags {
+    fn from_player_response(video_id: &str, player_response: &Value) -> Self {
+        let vd = player_response.get("videoDetails").unwrap_or(&Value::Null);
+        Self {
+            title: vd.get("title").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+            artist: vd.get("author").and_then(|a| a.as_str()).unwrap_or("").to_string(),
+            video_id: video_id.to_string(),
+        }
+    }
+
+    fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for (key, value) in [
+            ("title", self.title.as_str()),
+            ("artist", self.artist.as_str()),
+            ("album_artist", self.artist.as_str()),
+        ] {
+            if !value.is_empty() {
+                args.push("-metadata".to_string());
+                args.push(format!("{}={}", key, value));
+            }
+        }
+        args.push("-metadata".to_string());
+        args.push(format!("comment=https://www.youtube.com/watch?v={}", self.video_id));
+        args
+    }
+}
 
-        let temp_path_str = temp_file_path.to_string_lossy().to_string();
-        cmd.arg(&temp_path_str);
+fn transcoded_audio_cache_path(
+    temp_dir: &Path,
+    video_id: &str,
+    format: AudioFormat,
+    bitrate_bps: u32,
+) -> PathBuf {
+    temp_dir.join(format!(
+        "yt_api_audio_{}_{}_{}.{}",
+        video_id,
+        format.as_str(),
+        bitrate_bps,
+        format.extension()
+    ))
+}
 
-        // Configure Stdin to be piped
-        cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+/// Temp dir for transcoded audio: `cache.temp_dir` or the system one.
+fn audio_transcode_temp_dir(config: &crate::config::Config) -> PathBuf {
+    let temp_dir = config.cache.temp_dir.as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+    if let Err(e) = fs::create_dir_all(&temp_dir) {
+        log::warn!("Не удалось создать временную папку {}: {}", temp_dir.display(), e);
+    }
+    temp_dir
+}
 
-        let mut child = match cmd.spawn() {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = tx.blocking_send(Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("FFmpeg failed to start: {}", e)
-                )));
-                return;
-            }
-        };
+/// Transcodes the best audio stream of a video into `cache_path`.
+/// `player_response` is used for the title/artist tags.
+async fn transcode_audio_to_cache(
+    video_id: &str,
+    format: AudioFormat,
+    bitrate_bps: u32,
+    temp_dir: &Path,
+    cache_path: PathBuf,
+    player_response: &Value,
+    data: &web::Data<crate::AppState>,
+) -> Result<PathBuf, String> {
+    // Параллельные первые запросы того же файла ждут одну конвертацию
+    let output_lock = crate::conversions::lock_output(&cache_path).await;
+    if cache_path.exists() {
+        return Ok(cache_path);
+    }
 
-        // 3. Pipe data from HTTP response to FFmpeg stdin
-        // We take() stdin here to get the handle
-        if let Some(mut stdin) = child.stdin.take() {
-            let mut buffer = [0u8; 8192];
-            loop {
-                match response.read(&mut buffer) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        if stdin.write_all(&buffer[..n]).is_err() {
-                            // FFmpeg might have closed stdin early (error or finished)
-                            break; 
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Network read error: {}", e);
-                        break;
-                    }
-                }
-            }
-        } 
-        // Drop stdin handle to signal EOF to FFmpeg
+    let source_url = resolve_direct_stream_url(video_id, None, true, &data.config, None).await?;
 
-        // 4. Wait for FFmpeg to finish
-        let output = match child.wait_with_output() {
-            Ok(o) => o,
-            Err(e) => {
-                let _ = tx.blocking_send(Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("FFmpeg wait error: {}", e)
-                )));
-                let _ = fs::remove_file(&temp_file_path);
-                return;
-            }
-        };
+    let tags = if format.supports_tags() {
+        Some(AudioTags::from_player_response(video_id, player_response))
+    } else {
+        None
+    };
 
-        if !output.status.success() {
-            let err_msg = String::from_utf8_lossy(&output.stderr).to_string();
-            log::error!(
-                "FFmpeg conversion failed. Status: {:?} | STDERR: {}",
-                output.status, err_msg
-            );
-            let _ = tx.blocking_send(Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("FFmpeg failed: {}", err_msg)
-            )));
-            let _ = fs::remove_file(&temp_file_path);
-            return;
+    let cover_path = if format.supports_cover() {
+        let path = temp_dir.join(format!(
+            "yt_api_audio_{}_cover_{}.jpg",
+            video_id,
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis()
+        ));
+        let cover_url = format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", video_id);
+        match Client::new().get(&cover_url).send().await {
+            Ok(resp) if resp.status().is_success() => match resp.bytes().await {
+                Ok(bytes) if fs::write(&path, &bytes).is_ok() => Some(path),
+                _ => None,
+            },
+            _ => None,
         }
+    } else {
+        None
+    };
 
-        // 5. Stream the resulting file back (Same logic as before)
-        match fs::File::open(&temp_file_path) {
-            Ok(mut file) => {
-                let mut buffer = [0u8; 65536];
-                loop {
-                    match file.read(&mut buffer) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            if tx.blocking_send(Ok(Bytes::copy_from_slice(&buffer[..n]))).is_err() { break; }
-                        }
-                        Err(e) => {
-                            let _ = tx.blocking_send(Err(e));
-                            break;
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                let _ = tx.blocking_send(Err(e));
-            }
+    let mut args = format.ffmpeg_args(bitrate_bps, cover_path.is_some());
+    if let Some(tags) = &tags {
+        args.extend(tags.ffmpeg_args());
+    }
+    let extra_inputs: Vec<String> = cover_path
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let user_agent = data.config.get_innertube_user_agent();
+    let label = format!("audio {} {}", video_id, format.as_str());
+    let permit = crate::conversions::acquire(&data.codec_semaphore, label)
+        .await
+        .map(|p| p.with_output(output_lock));
+    task::spawn_blocking(move || {
+        let _permit = permit;
+        let part_path = conversion_part_path(&cache_path);
+        let result = transcode_to_file(&source_url, &user_agent, &extra_inputs, &args, &part_path)
+            .and_then(|_| {
+                fs::rename(&part_path, &cache_path)
+                    .map_err(|e| format!("Failed to move transcoded audio into cache: {}", e))
+            })
+            .map(|_| cache_path);
+        if result.is_err() {
+            let _ = fs::remove_file(&part_path);
         }
+        if let Some(cover) = cover_path {
+            let _ = fs::remove_file(cover);
+        }
+        result
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
 
-        let _ = fs::remove_file(&temp_file_path);
-    });
 
-    let mime_type = if codec == "mpeg4" { "video/mp4" } else { "video/3gpp" };
-    let stream = ReceiverStream::new(rx).map(|r| r.map(web::Bytes::from).map_err(actix_web::error::ErrorInternalServerError));
-    HttpResponse::Ok()
-        .insert_header((CONTENT_TYPE, HeaderValue::from_str(mime_type).unwrap()))
-        .insert_header(("Cache-Control", "public, max-age=3600"))
-        .streaming(stream)
+/// Temp files produced by muxing (`.mp4`), codec conversions (`.3gp`, `.flv`, `.webm`, `.ogv`)
+/// and audio transcoding (`/direct_audio_url?format=`).
+pub(crate) fn is_direct_url_temp_file(name: &str) -> bool {
+    (name.starts_with("yt_api_video_")
+        && [".mp4", ".3gp", ".flv", ".webm", ".ogv", ".srt"].iter().any(|ext| name.ends_with(ext)))
+        || (name.starts_with("yt_api_audio_")
+            && [".mp3", ".m4a", ".ogg", ".amr", ".jpg"].iter().any(|ext| name.ends_with(ext)))
+}
+
+/// Whether a temp file in `dir` is still being produced: a `.part`, the output of a
+/// running conversion, or a yt-dlp download (final file or its `.fNNN` pieces)
+/// whose `.lock` is held.
+pub(crate) fn is_direct_url_temp_file_in_use(dir: &Path, name: &str) -> bool {
+    if name.ends_with(".part") || crate::conversions::is_output_locked(&dir.join(name)) {
+        return true;
+    }
+    let stem = name.split('.').next().unwrap_or(name);
+    dir.join(format!("{}.lock", stem)).exists()
 }
 
+/// A `.part` of a conversion that will never finish: written by another process
+/// (killed or restarted server), or by this one with nobody producing its file.
+fn is_orphaned_part_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let Some((cache_name, tag)) = name.strip_suffix(".part").and_then(|s| s.rsplit_once('.')) else {
+        return false;
+    };
+    if !is_direct_url_temp_file(cache_name) {
+        return false;
+    }
+    let pid = tag.rsplit_once('_').and_then(|(_, pid)| pid.parse::<u32>().ok());
+    pid != Some(std::process::id())
+        || !crate::conversions::is_output_locked(&path.with_file_name(cache_name))
+}
 
 fn clean_direct_url_temp_files(custom_temp_dir: Option<&Path>) {
     // Системная временная папка чистится всегда
@@ -1190,7 +2441,7 @@ fn clean_direct_url_temp_files(custom_temp_dir: Option<&Path>) {
             let path = entry.path();
             if path.is_file() {
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.starts_with("yt_api_video_") && (name.ends_with(".mp4") || name.ends_with(".3gp")) {
+                    if is_direct_url_temp_file(name) {
                         if let Ok(meta) = fs::metadata(&path) {
                             if let Ok(mtime) = meta.modified() {
                                 if now.duration_since(mtime).unwrap_or(Duration::MAX) > max_age_video {
@@ -1199,6 +2450,9 @@ fn clean_direct_url_temp_files(custom_temp_dir: Option<&Path>) {
                                 }
                             }
                         }
+                    } else if is_orphaned_part_file(&path) {
+                        let _ = fs::remove_file(&path);
+                        log::debug!("direct_url cleanup: removed orphaned {}", path.display());
                     }
                 }
             }
@@ -1230,7 +2484,7 @@ fn clean_direct_url_temp_files(custom_temp_dir: Option<&Path>) {
                     let path = entry.path();
                     if path.is_file() {
                         if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                            if name.starts_with("yt_api_video_") && (name.ends_with(".mp4") || name.ends_with(".3gp")) {
+                            if is_direct_url_temp_file(name) {
                                 if let Ok(meta) = fs::metadata(&path) {
                                     if let Ok(mtime) = meta.modified() {
                                         if now.duration_since(mtime).unwrap_or(Duration::MAX) > max_age_video {
@@ -1239,6 +2493,9 @@ fn clean_direct_url_temp_files(custom_temp_dir: Option<&Path>) {
                                         }
                                     }
                                 }
+                            } else if is_orphaned_part_file(&path) {
+                                let _ = fs::remove_file(&path);
+                                log::debug!("direct_url cleanup: removed orphaned {}", path.display());
                             }
                         }
                     }
@@ -1290,12 +2547,18 @@ async fn resolve_direct_stream_url(
     quality: Option<&str>,
     audio_only: bool,
     config: &crate::config::Config,
+    auth: Option<&PlayerAuth>,
 ) -> Result<String, String> {
     let video_id = video_id.to_string();
+    let auth_args = match auth {
+        Some(a) => Some(a.yt_dlp_args().await?),
+        None => None,
+    };
     let quality = quality
         .map(|q| q.to_string())
         .unwrap_or_else(|| config.video.default_quality.clone());
-    let use_cookies = config.video.use_cookies;
+    // С OAuth-токеном пользователя cookies из конфига не подмешиваем
+    let use_cookies = config.video.use_cookies && auth_args.is_none();
     let yt_dlp = yt_dlp_binary();
     let mut cookie_paths = Vec::new();
     if use_cookies {
@@ -1350,6 +2613,7 @@ async fn resolve_direct_stream_url(
 				.arg("--youtube-skip-dash-manifest")
 				.arg("--youtube-skip-hls-manifest")
 				.arg("--no-playlist")
+				.args(auth_args.as_ref().map(|a| a.args()).unwrap_or_default())
 				.arg(&url);								
 
             if let Some(ref path) = cookie {
@@ -1473,6 +2737,31 @@ pub struct VideoInfoResponse {
     pub channel_thumbnail: String,
     pub thumbnail: String,
     pub video_url: String,
+    /// Device profile `video_url` was built for, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Chapter markers, empty when the video has none.
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    /// Broadcasting right now; `length_seconds` and `duration` are empty then.
+    #[serde(default)]
+    pub is_live: bool,
+    /// Scheduled live stream or premiere that has not started yet.
+    #[serde(default)]
+    pub is_upcoming: bool,
+    /// Scheduled start (RFC 3339) of an upcoming or live broadcast.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled_start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrent_viewers: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub start_seconds: u64,
+    /// Proxied marker thumbnail, or a storyboard frame for description chapters.
+    pub thumbnail: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -1510,28 +2799,254 @@ pub struct HlsManifestUrlResponse {
     pub message: Option<String>,
 }
 
+/// Fitting mode for `w` + `h` on image endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageFit {
+    /// Fit inside the box, keep aspect ratio (output may be smaller).
+    Contain,
+    /// Fill the box, keep aspect ratio, crop the overflow.
+    Cover,
+    /// Stretch to exactly `w`×`h`.
+    Fill,
+    /// Like `Contain`, then letterbox with black to exactly `w`×`h`.
+    Pad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageOutput {
+    Jpeg,
+    Png,
+    Gif,
+    Bmp,
+}
+
+impl ImageOutput {
+    fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "jpeg" | "jpg" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "bmp" => Some(Self::Bmp),
+            _ => None,
+        }
+    }
+
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next().unwrap_or("").trim().to_lowercase().as_str() {
+            "image/jpeg" | "image/jpg" => Some(Self::Jpeg),
+            "image/png" => Some(Self::Png),
+            "image/gif" => Some(Self::Gif),
+            "image/bmp" => Some(Self::Bmp),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpeg",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Bmp => "bmp",
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Gif => "image/gif",
+            Self::Bmp => "image/bmp",
+        }
+    }
+}
+
+/// Resize / re-encode options shared by `/thumbnail` and `/channel_icon`:
+/// `w`, `h`, `fit=contain|cover|fill|pad`, `format=jpeg|png|gif|bmp`, `quality=1..100`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ImageTransform {
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: ImageFit,
+    format: Option<ImageOutput>,
+    quality: u8,
+}
+
+const MAX_IMAGE_DIMENSION: u32 = 2048;
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+impl ImageTransform {
+    fn from_query(query_params: &HashMap<String, String>) -> Result<Self, String> {
+        let dimension = |key: &str| -> Result<Option<u32>, String> {
+            match query_params.get(key) {
+                None => Ok(None),
+                Some(v) => match v.parse::<u32>() {
+                    Ok(n) if (1..=MAX_IMAGE_DIMENSION).contains(&n) => Ok(Some(n)),
+                    _ => Err(format!("{} must be between 1 and {}", key, MAX_IMAGE_DIMENSION)),
+                },
+            }
+        };
+        let fit = match query_params.get("fit").map(|f| f.to_lowercase()).as_deref() {
+            None | Some("contain") => ImageFit::Contain,
+            Some("cover") | Some("crop") => ImageFit::Cover,
+            Some("fill") | Some("stretch") => ImageFit::Fill,
+            Some("pad") => ImageFit::Pad,
+            Some(other) => return Err(format!("Unknown fit '{}'. Available: contain, cover, fill, pad", other)),
+        };
+        let format = match query_params.get("format") {
+            None => None,
+            Some(f) => Some(ImageOutput::from_param(f).ok_or_else(|| {
+                format!("Unknown format '{}'. Available: jpeg, png, gif, bmp", f)
+            })?),
+        };
+        let quality = match query_params.get("q") {
+            None => DEFAULT_JPEG_QUALITY,
+            Some(q) => match q.parse::<u8>() {
+                Ok(n) if (1..=100).contains(&n) => n,
+                _ => return Err("JPEG quality must be between 1 and 100".to_string()),
+            },
+        };
+        Ok(Self {
+            width: dimension("w")?,
+            height: dimension("h")?,
+            fit,
+            format,
+            quality,
+        })
+    }
+
+    fn resizes(&self) -> bool {
+        self.width.is_some() || self.height.is_some()
+    }
+
+    /// Suffix that makes cache keys unique per variant.
+    fn cache_suffix(&self) -> String {
+        format!(
+            "_w{}_h{}_{:?}_{}_q{}",
+            self.width.unwrap_or(0),
+            self.height.unwrap_or(0),
+            self.fit,
+            self.format.map(|f| f.as_str()).unwrap_or("orig"),
+            self.quality
+        )
+    }
+
+    /// Decodes, resizes and re-encodes `data`. Images that need no change are
+    /// returned as is; WebP (and anything else old devices can't show) becomes
+    /// baseline JPEG.
+    fn apply(&self, data: Vec<u8>, content_type: &str) -> Result<(Vec<u8>, String), String> {
+        let source_format = ImageOutput::from_content_type(content_type);
+        let explicit_quality = self.quality != DEFAULT_JPEG_QUALITY;
+        if !self.resizes()
+            && source_format.is_some()
+            && (self.format.is_none() || self.format == source_format)
+            && !(explicit_quality && source_format == Some(ImageOutput::Jpeg))
+        {
+            return Ok((data, content_type.to_string()));
+        }
+
+        let output = self.format.or(source_format).unwrap_or(ImageOutput::Jpeg);
+        let mut img = image::load_from_memory(&data).map_err(|e| e.to_string())?;
+        let filter = image::imageops::FilterType::Triangle;
+        img = match (self.width, self.height) {
+            (None, None) => img,
+            (Some(w), None) => {
+                let h = (img.height() as u64 * w as u64 / img.width().max(1) as u64).max(1) as u32;
+                img.resize_exact(w, h, filter)
+            }
+            (None, Some(h)) => {
+                let w = (img.width() as u64 * h as u64 / img.height().max(1) as u64).max(1) as u32;
+                img.resize_exact(w, h, filter)
+            }
+            (Some(w), Some(h)) => match self.fit {
+                ImageFit::Contain => img.resize(w, h, filter),
+                ImageFit::Cover => img.resize_to_fill(w, h, filter),
+                ImageFit::Fill => img.resize_exact(w, h, filter),
+                ImageFit::Pad => {
+                    let inner = img.resize(w, h, filter).to_rgba8();
+                    let mut canvas = image::RgbaImage::from_pixel(w, h, image::Rgba([0, 0, 0, 255]));
+                    let x = (w - inner.width()) / 2;
+                    let y = (h - inner.height()) / 2;
+                    image::imageops::overlay(&mut canvas, &inner, x as i64, y as i64);
+                    image::DynamicImage::ImageRgba8(canvas)
+                }
+            },
+        };
+
+        let mut out = Vec::new();
+        let mut cursor = std::io::Cursor::new(&mut out);
+        match output {
+            // JPEG и BMP без альфа-канала; кодировщик image пишет baseline JPEG
+            ImageOutput::Jpeg => image::DynamicImage::ImageRgb8(img.to_rgb8())
+                .write_to(&mut cursor, image::ImageOutputFormat::Jpeg(self.quality)),
+            ImageOutput::Bmp => image::DynamicImage::ImageRgb8(img.to_rgb8())
+                .write_to(&mut cursor, image::ImageOutputFormat::Bmp),
+            ImageOutput::Png => img.write_to(&mut cursor, image::ImageOutputFormat::Png),
+            ImageOutput::Gif => image::DynamicImage::ImageRgba8(img.to_rgba8())
+                .write_to(&mut cursor, image::ImageOutputFormat::Gif),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok((out, output.mime_type().to_string()))
+    }
+}
+
+/// Runs `ImageTransform::apply` off the async runtime.
+async fn transform_image(
+    transform: &ImageTransform,
+    data: Vec<u8>,
+    content_type: String,
+) -> Result<(Vec<u8>, String), String> {
+    let transform = transform.clone();
+    task::spawn_blocking(move || transform.apply(data, &content_type))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn parse_query_params(req: &HttpRequest) -> HashMap<String, String> {
+    let mut query_params: HashMap<String, String> = HashMap::new();
+    for pair in req.query_string().split('&') {
+        let mut parts = pair.split('=');
+        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            query_params.insert(key.to_string(), value.to_string());
+        }
+    }
+    query_params
+}
+
 #[utoipa::path(
     get,
     path = "/thumbnail/{video_id}",
     params(
         ("video_id" = String, Path, description = "YouTube video ID"),
-        ("quality" = Option<String>, Query, description = "Thumbnail quality (default, medium, high, standard, maxres)")
+        ("quality" = Option<String>, Query, description = "Thumbnail quality (default, medium, high, standard, maxres)"),
+        ("w" = Option<u32>, Query, description = "Output width in pixels (up to 2048)"),
+        ("h" = Option<u32>, Query, description = "Output height in pixels (up to 2048)"),
+        ("fit" = Option<String>, Query, description = "With both w and h: contain (default), cover, fill or pad (letterbox to the exact size)"),
+        ("format" = Option<String>, Query, description = "Output format: jpeg, png, gif or bmp. WebP sources are always converted to baseline JPEG"),
+        ("q" = Option<u8>, Query, description = "JPEG quality 1-100 (default 85)")
     ),
     responses(
         (status = 200, description = "Thumbnail image", content_type = "image/jpeg"),
+        (status = 400, description = "Invalid resize or format parameters"),
         (status = 404, description = "Thumbnail not found")
     )
 )]
-pub async fn thumbnail_proxy(path: web::Path<String>, req: HttpRequest) -> impl Responder {
+pub async fn thumbnail_proxy(
+    path: web::Path<String>,
+    req: HttpRequest,
+    data: web::Data<crate::AppState>,
+) -> impl Responder {
     let video_id = path.into_inner();
+    let query_params = parse_query_params(&req);
 
-    let mut query_params: HashMap<String, String> = HashMap::new();
-    for pair in req.query_string().split('&') {
-        let mut parts = pair.split('=');
-        if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-            query_params.insert(key.to_string(), value.to_string());
+    let transform = match ImageTransform::from_query(&query_params) {
+        Ok(t) => t,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid image parameters",
+                "details": e
+            }));
         }
-    }
+    };
 
     let quality = query_params
         .get("quality")
@@ -1552,122 +3067,110 @@ pub async fn thumbnail_proxy(path: web::Path<String>, req: HttpRequest) -> impl
         .map(|(_, t)| *t)
         .unwrap_or("mqdefault.jpg");
 
-    let cache_key = format!("{}_{}", video_id, thumbnail_type);
-
-    {
-        let mut cache = THUMBNAIL_CACHE.lock().await;
-        if let Some((data, content_type, timestamp)) = cache.get(&cache_key) {
-            let current_time = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            if current_time - timestamp < CACHE_DURATION {
-                return HttpResponse::Ok()
-                    .content_type(content_type.as_str())
-                    .body(data.clone());
-            }
-        }
+    let cache_key = format!("thumb_{}_{}{}", video_id, thumbnail_type, transform.cache_suffix());
+    let ttl = data.config.cache.thumbnail_ttl_secs;
+    if let Some(image) = data.image_cache.get(&cache_key, ttl).await {
+        return image_response(&req, &image, ttl);
     }
 
     let url = format!("https://i.ytimg.com/vi/{}/{}", video_id, thumbnail_type);
 
     let client = Client::new();
 
-    match client.get(&url).send().await {
-        Ok(resp) => {
-            let status = resp.status().as_u16();
-            let headers = resp.headers().clone();
-            if status == 404 && thumbnail_type != "mqdefault.jpg" {
-                let fallback_url = format!("https://i.ytimg.com/vi/{}/mqdefault.jpg", video_id);
-                match client.get(&fallback_url).send().await {
-                    Ok(fallback_resp) => {
-                        let fallback_headers = fallback_resp.headers().clone();
-                        let content_type = fallback_headers
-                            .get("content-type")
-                            .and_then(|ct| ct.to_str().ok())
-                            .unwrap_or("image/jpeg")
-                            .to_string();
-
-                        match fallback_resp.bytes().await {
-                            Ok(bytes) => {
-                                let current_time = SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_secs();
-
-                                let mut cache = THUMBNAIL_CACHE.lock().await;
-                                cache.put(
-                                    cache_key,
-                                    (bytes.to_vec(), content_type.clone(), current_time),
-                                );
-
-                                HttpResponse::Ok()
-                                    .content_type(content_type.as_str())
-                                    .body(bytes)
-                            }
-                            Err(_) => HttpResponse::NotFound().finish(),
-                        }
-                    }
-                    Err(_) => HttpResponse::NotFound().finish(),
-                }
-            } else {
-                let content_type = headers
-                    .get("content-type")
-                    .and_then(|ct| ct.to_str().ok())
-                    .unwrap_or("image/jpeg")
-                    .to_string();
+    let mut resp = match client.get(&url).send().await {
+        Ok(resp) => resp,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    if resp.status().as_u16() == 404 && thumbnail_type != "mqdefault.jpg" {
+        let fallback_url = format!("https://i.ytimg.com/vi/{}/mqdefault.jpg", video_id);
+        resp = match client.get(&fallback_url).send().await {
+            Ok(resp) => resp,
+            Err(_) => return HttpResponse::NotFound().finish(),
+        };
+    }
+    if !resp.status().is_success() {
+        return HttpResponse::NotFound().finish();
+    }
 
-                match resp.bytes().await {
-                    Ok(bytes) => {
-                        let current_time = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs();
-
-                        let mut cache = THUMBNAIL_CACHE.lock().await;
-                        cache.put(
-                            cache_key,
-                            (bytes.to_vec(), content_type.clone(), current_time),
-                        );
-
-                        HttpResponse::Ok()
-                            .content_type(content_type.as_str())
-                            .body(bytes)
-                    }
-                    Err(_) => HttpResponse::NotFound().finish(),
-                }
-            }
+    let content_type = resp
+        .headers()
+        .get("content-type")
+        .and_then(|ct| ct.to_str().ok())
+        .unwrap_or("image/jpeg")
+        .to_string();
+
+    let bytes = match resp.bytes().await {
+        Ok(bytes) => bytes,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let (image_data, content_type) = match transform_image(&transform, bytes.to_vec(), content_type).await {
+        Ok(result) => result,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to process thumbnail",
+                "details": e
+            }));
         }
-        Err(_) => HttpResponse::NotFound().finish(),
-    }
+    };
+
+    let image = data.image_cache.put(&cache_key, image_data, &content_type).await;
+    image_response(&req, &image, ttl)
 }
 
 #[utoipa::path(
     get,
     path = "/channel_icon/{path_video_id}",
     params(
-        ("path_video_id" = String, Path, description = "Channel ID (UC...), @handle, video ID or direct image URL")
+        ("path_video_id" = String, Path, description = "Channel ID (UC...), @handle, video ID or direct image URL"),
+        ("w" = Option<u32>, Query, description = "Output width in pixels (up to 2048)"),
+        ("h" = Option<u32>, Query, description = "Output height in pixels (up to 2048)"),
+        ("fit" = Option<String>, Query, description = "With both w and h: contain (default), cover, fill or pad"),
+        ("format" = Option<String>, Query, description = "Output format: jpeg, png, gif or bmp. WebP sources are always converted to baseline JPEG"),
+        ("q" = Option<u8>, Query, description = "JPEG quality 1-100 (default 85)")
     ),
     responses(
-        (status = 200, description = "Channel icon image", content_type = "image/jpeg, image/png, image/webp"),
+        (status = 200, description = "Channel icon image", content_type = "image/jpeg, image/png, image/gif"),
         (status = 404, description = "Channel icon not found"),
         (status = 400, description = "Bad request")
     )
 )]
 pub async fn channel_icon(
     path: web::Path<String>,
+    req: HttpRequest,
     data: web::Data<crate::AppState>,
 ) -> impl Responder {
     let input = path.into_inner();
     let config = &data.config;
 
+    let transform = match ImageTransform::from_query(&parse_query_params(&req)) {
+        Ok(t) => t,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid image parameters",
+                "details": e
+            }));
+        }
+    };
+
+    // Кэшируем по исходному запросу: повторный запрос не резолвит канал заново
+    let cache_key = format!("icon_{}{}", input, transform.cache_suffix());
+    let ttl = config.cache.channel_icon_ttl_secs;
+    if let Some(image) = data.image_cache.get(&cache_key, ttl).await {
+        return image_response(&req, &image, ttl);
+    }
+
     let decoded = urlencoding::decode(&input)
         .unwrap_or_else(|_| std::borrow::Cow::Owned(input.clone()))
         .to_string();
     
     if decoded.starts_with("http://") || decoded.starts_with("https://") {
-        return proxy_image(&decoded).await;
+        return match proxy_image(&decoded, &transform).await {
+            Ok((image_data, content_type)) => {
+                let image = data.image_cache.put(&cache_key, image_data, &content_type).await;
+                image_response(&req, &image, ttl)
+            }
+            Err(response) => response,
+        };
     }
 
     let client = Client::builder()
@@ -1735,7 +3238,13 @@ pub async fn channel_icon(
             .json(serde_json::json!({"error": "Channel avatar not found"}));
     }
 
-    proxy_image(&avatar_url).await
+    match proxy_image(&avatar_url, &transform).await {
+        Ok((image_data, content_type)) => {
+            let image = data.image_cache.put(&cache_key, image_data, &content_type).await;
+            image_response(&req, &image, ttl)
+        }
+        Err(response) => response,
+    }
 }
 
 #[utoipa::path(
@@ -1744,11 +3253,14 @@ pub async fn channel_icon(
     params(
         ("video_id" = String, Query, description = "YouTube video ID"),
         ("quality" = Option<String>, Query, description = "Video quality"),
-        ("proxy" = Option<String>, Query, description = "Use video proxy (true/false)")
+        ("proxy" = Option<String>, Query, description = "Use video proxy (true/false)"),
+        ("profile" = Option<String>, Query, description = "Device profile to build video_url for; auto-detected from User-Agent when device_profiles.auto_detect is enabled"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "Video information", body = VideoInfoResponse),
-        (status = 400, description = "Missing video ID"),
+        (status = 400, description = "Missing video ID or unknown profile"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -1787,6 +3299,19 @@ pub async fn get_ytvideo_info(
         .unwrap_or("true".to_string());
     let _use_video_proxy = proxy_param != "false";
 
+    let request_user_agent = req.headers().get("User-Agent").and_then(|h| h.to_str().ok());
+    let device_profile = match config
+        .select_device_profile(query_params.get("profile").map(|p| p.as_str()), request_user_agent)
+    {
+        Ok(p) => p.map(|(name, _)| name),
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Unknown device profile",
+                "details": e
+            }));
+        }
+    };
+
     let innertube_key = match config.get_innertube_key() {
         Some(key) => key,
         None => {
@@ -1797,10 +3322,11 @@ pub async fn get_ytvideo_info(
     };
 
     let client = Client::new();
+    let locale = crate::locale::Locale::from_request(&req, config);
     
-    let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
+    let video_url = format!("https://www.youtube.com/watch?v={}&{}", video_id, locale.query());
     
-    let html = match client.get(&video_url).send().await {
+    let html = match locale.with_header(client.get(&video_url)).send().await {
         Ok(resp) => match resp.text().await {
             Ok(text) => text,
             Err(e) => {
@@ -1830,10 +3356,7 @@ pub async fn get_ytvideo_info(
         })
     });
     
-    if let Some(client) = ctx.get_mut("client").and_then(|c| c.as_object_mut()) {
-        client.insert("gl".to_string(), serde_json::Value::String("US".to_string())); // Set region to USA
-        client.insert("hl".to_string(), serde_json::Value::String("en-US".to_string())); // Set language to English (USA)
-    }
+    locale.apply_context(&mut ctx);
     
     let next_payload = serde_json::json!({
         "context": ctx,
@@ -1936,8 +3459,7 @@ pub async fn get_ytvideo_info(
                                 if let Some(view_count) = primary_info.get("viewCount") {
                                     if let Some(video_view_count) = view_count.get("videoViewCountRenderer") {
                                         if let Some(view_count_simple) = video_view_count.get("viewCount") {
-                                            views = simplify_text(view_count_simple);
-                                            views.retain(|c| c.is_ascii_digit());
+                                            views = crate::locale::count_string(&simplify_text(view_count_simple), "");
                                         }
                                     }
                                 }
@@ -2005,9 +3527,17 @@ to_string();
         channel_id = vd.get("channelId").and_then(|c| c.as_str()).unwrap_or("").to_string();
     }
     
+    let live = LiveStatus::from_player_response(&pr);
+    // У трансляций нет фиксированной длительности, счётчик на странице — зрители онлайн
     let length_seconds: Option<u64> = vd
         .get("lengthSeconds")
-        .and_then(|l| l.as_u64().or_else(|| l.as_str().and_then(|s| s.parse().ok())));
+        .and_then(|l| l.as_u64().or_else(|| l.as_str().and_then(|s| s.parse().ok())))
+        .filter(|_| !live.is_live && !live.is_upcoming);
+    let concurrent_viewers = if live.is_live && !views.is_empty() {
+        Some(views.clone())
+    } else {
+        None
+    };
 
     let duration = if let Some(sec) = length_seconds {
         format!("PT{}M{}S", sec / 60, sec % 60)
@@ -2016,9 +3546,13 @@ to_string();
     };
     
     let final_video_url = if config.video.source == "direct" {
+        let profile_param = device_profile
+            .as_deref()
+            .map(|p| format!("&profile={}", urlencoding::encode(p)))
+            .unwrap_or_default();
         format!(
-            "{}/direct_url?video_id={}",
-            base_trimmed, video_id
+            "{}/direct_url?video_id={}{}",
+            base_trimmed, video_id, profile_param
         )
     } else {
         "".to_string()
@@ -2034,6 +3568,8 @@ to_string();
         final_video_url.clone()
     };
     
+    let chapters = extract_chapters(&next_data, &description, &video_id, base_trimmed);
+
     let response = VideoInfoResponse {
         title: sanitize_text(&title),
         author,
@@ -2067,6 +3603,12 @@ to_string();
         },
         thumbnail: format!("{}/thumbnail/{}", base_trimmed, video_id),
         video_url: final_video_url,
+        profile: device_profile,
+        chapters,
+        is_live: live.is_live,
+        is_upcoming: live.is_upcoming,
+        scheduled_start: live.scheduled_start_rfc3339(),
+        concurrent_viewers,
     };
     
     HttpResponse::Ok().json(response)
@@ -2082,7 +3624,9 @@ to_string();
         ("limit" = Option<i32>, Query, description = "Limit for pagination (default: 50)"),
         ("order" = Option<String>, Query, description = "Order of results (relevance, date, rating, viewCount, title) (default: 
 relevance)"),
-        ("token" = Option<String>, Query, description = "Refresh token for InnerTube recommendations")
+        ("token" = Option<String>, Query, description = "Refresh token for InnerTube recommendations"),
+        ("hl" = Option<String>, Query, description = "Interface language, e.g. en, ru, pt-BR (default: locale.hl from config or the hl cookie)"),
+        ("gl" = Option<String>, Query, description = "Content region, e.g. US, RU (default: locale.gl from config or the gl cookie)")
     ),
     responses(
         (status = 200, description = "List of related videos", body = [RelatedVideo]),
@@ -2155,12 +3699,15 @@ pub async fn get_related_videos(
         }
     });
 
-    let watch_url = format!("https://www.youtube.com/watch?v={}", video_id);
+    let locale = crate::locale::Locale::from_request(&req, config);
+    let watch_url = format!("https://www.youtube.com/watch?v={}&{}", video_id, locale.query());
     let headers_map = {
         let mut map = reqwest::header::HeaderMap::new();
         map.insert(reqwest::header::USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/121.0.0.0 Safari/537.36".parse().
 unwrap());
-        map.insert(reqwest::header::ACCEPT_LANGUAGE, "en-US,en;q=0.9".parse().unwrap());
+        if let Ok(value) = locale.accept_language().parse() {
+            map.insert(reqwest::header::ACCEPT_LANGUAGE, value);
+        }
         map.insert(reqwest::header::CONTENT_TYPE, "application/json".parse().unwrap());
         map
     };
@@ -2183,7 +3730,8 @@ unwrap());
 
     let ytcfg = extract_ytcfg(&html_response);
     let api_key_from_cfg = ytcfg.get("INNERTUBE_API_KEY").and_then(|v| v.as_str()).unwrap_or(innertube_key);
-    let context_from_cfg = ytcfg.get("INNERTUBE_CONTEXT").cloned().unwrap_or(context);
+    let mut context_from_cfg = ytcfg.get("INNERTUBE_CONTEXT").cloned().unwrap_or(context);
+    locale.apply_context(&mut context_from_cfg);
 
     let next_url = format!("https://www.youtube.com/youtubei/v1/next?key={}", api_key_from_cfg);
     let body = serde_json::json!({
@@ -2346,7 +3894,7 @@ pub async fn get_direct_video_url(
     };
 
     let quality = query_params.get("quality").map(|q| q.as_str());
-    match resolve_direct_stream_url(&video_id, quality, false, &data.config).await {
+    match resolve_direct_stream_url(&video_id, quality, false, &data.config, None).await {
         Ok(url) => HttpResponse::Ok().json(DirectUrlResponse { video_url: url }),
         Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
             "error": "Failed to resolve direct url",
@@ -2362,12 +3910,17 @@ pub async fn get_direct_video_url(
         ("video_id" = String, Query, description = "YouTube video ID"),
         ("quality" = Option<String>, Query, description = "Preferred quality"),
         ("proxy" = Option<String>, Query, description = "Pass-through proxy (true/false)"),
-        ("codec" = Option<String>, Query, description = "Video codec for optional conversion: mpeg4 or h263. If passed, quality will 
-be 360p")
+        ("codec" = Option<String>, Query, description = "Video codec for optional conversion: mpeg4, h263, flv (Sorenson H.263 + MP3), flv_h264 (H.264 + AAC in FLV), webm (VP8 + Vorbis) or theora (Ogg Theora + Vorbis). webm/theora honour quality 360/480/720, other codecs are always 360p"),
+        ("start" = Option<u64>, Query, description = "FLV only: byte offset of a keyframe from onMetaData to resume playback from"),
+        ("subs" = Option<String>, Query, description = "Codec conversions only: caption language to burn into the picture (cached separately from the plain output)"),
+        ("profile" = Option<String>, Query, description = "Device profile from config (e.g. nokia-s60, iphone-3g, wii, psp, win-xp-ie6) setting container, codec, resolution and bitrates; explicit codec/quality take precedence. With device_profiles.auto_detect the profile is picked by User-Agent; profile=none disables that"),
+        ("token" = Option<String>, Query, description = "Refresh token (or session_id cookie). Used only when the video cannot be played anonymously (age-restricted, members-only); such results are cached per user"),
+        ("live" = Option<String>, Query, description = "Live streams without codec: hls (default, the HLS manifest rewritten to load through this server; proxy=false leaves segments direct), ts (remux to MPEG-TS) or 3gp (H.263/AMR for phones). With codec the live stream is transcoded to that codec; nothing is cached")
     ),
     responses(
         (status = 200, description = "Video stream"),
-        (status = 400, description = "Missing video_id or invalid codec")
+        (status = 400, description = "Missing video_id, invalid codec or unknown profile"),
+        (status = 503, description = "Live stream or premiere has not started yet (Retry-After when the start time is known)")
     )
 )]
 pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> impl Responder {
@@ -2391,29 +3944,153 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
                 "error": "video_id parameter is required"
             }));
         }
-    };
+    };
+
+    // Учётка пользователя (token= или cookie) — только для видео, которые анонимно не отдаются
+    let player_auth = PlayerAuth::from_request(&req).await;
+
+    // Профиль устройства: явный ?profile= или, если включено, по User-Agent.
+    // Явно заданные codec/quality отключают автоопределение.
+    let explicit_format = query_params.contains_key("codec") || query_params.contains_key("quality");
+    let request_user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .filter(|_| !explicit_format);
+    let device_profile = match data
+        .config
+        .select_device_profile(query_params.get("profile").map(|p| p.as_str()), request_user_agent)
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Unknown device profile",
+                "details": e
+            }));
+        }
+    };
+    let profile_codec = match &device_profile {
+        Some((name, profile)) => match device_profile_codec(profile) {
+            Ok(codec) => {
+                log::info!("Using device profile '{}' for {}", name, video_id);
+                codec
+            }
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Invalid device profile",
+                    "details": format!("Profile '{}': {}", name, e)
+                }));
+            }
+        },
+        None => None,
+    };
+    let codec_param = query_params
+        .get("codec")
+        .cloned()
+        .or_else(|| profile_codec.map(|c| c.as_str().to_string()));
+
+    // 1. Старые кодеки (всегда конвертация на лету)
+    if let Some(codec_str) = codec_param.as_deref() {
+        let codec = match ConversionCodec::from_param(codec_str) {
+            Some(c) => c,
+            None => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Unsupported codec",
+                    "details": format!(
+                        "Codec '{}' is not supported. Available: {}",
+                        codec_str,
+                        ConversionCodec::SUPPORTED.join(", ")
+                    ),
+                    "supported_codecs": ConversionCodec::SUPPORTED
+                }));
+            }
+        };
+        let start: u64 = query_params
+            .get("start")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let requested_height = query_params
+            .get("quality")
+            .and_then(|q| parse_quality_height(q));
+        let mut settings = match &device_profile {
+            Some((name, profile)) if profile_codec == Some(codec) => {
+                ConversionSettings::from_profile(name, profile)
+            }
+            _ => ConversionSettings::new(codec.target_height(requested_height.unwrap_or(360))),
+        };
+        if let (Some(h), Some((_, profile))) = (requested_height, &device_profile) {
+            if settings.profile.is_some() {
+                // quality= выбирает внутри профиля, но не выше его max_height
+                settings.height = codec.target_height(h.min(profile.max_height));
+            }
+        }
+        settings.subtitles_lang = query_params
+            .get("subs")
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        if let Some(lang) = &settings.subtitles_lang {
+            if !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Invalid subs parameter",
+                    "details": format!("'{}' is not a language code", lang)
+                }));
+            }
+        }
+        let height = settings.height;
+
+        let tmp_for_conversion = data.config.cache.temp_dir.as_deref()
+            .filter(|s| !s.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| env::temp_dir());
+        if let Err(e) = fs::create_dir_all(&tmp_for_conversion) {
+            log::warn!("Не удалось создать временную папку {}: {}", tmp_for_conversion.display(), e);
+        }
 
-    // 1. Старые кодеки (всегда конвертация на лету)
-    let codec = query_params.get("codec").map(|c| c.as_str());
-    if let Some(codec_str) = codec {
-        if codec_str != "mpeg4" && codec_str != "h263" {
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Unsupported codec",
-                "details": format!("Codec '{}' is not supported. Available: mpeg4, h263", codec_str),
-                "supported_codecs": ["mpeg4", "h263"]
-            }));
+        // Уже сконвертированный файл отдаём из кэша (с поддержкой Range и start= для FLV)
+        let cache_path = converted_cache_path(&tmp_for_conversion, &video_id, codec, &settings);
+        if let Ok(meta) = fs::metadata(&cache_path) {
+            log::info!("Converted {} ({}) cached. Serving.", video_id, codec.as_str());
+            if let Some(offset) = flv_start_offset(codec, start, meta.len()) {
+                if req.method() != actix_web::http::Method::HEAD {
+                    return serve_flv_from_offset(&cache_path, offset);
+                }
+            }
+            return serve_file_from_cache(&cache_path, &req, codec.mime_type(), None);
         }
 
         // Get video duration and check if it's longer than 55 minutes
-        let player_response = match fetch_player_response(&video_id, &data.config).await {
-            Ok(data) => data,
-            Err(e) => {
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Failed to fetch player response",
-                    "details": e
+        let (player_response, used_auth) =
+            match fetch_player_response_for(&video_id, &data.config, player_auth.as_ref()).await {
+                Ok(data) => data,
+                Err(e) => {
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to fetch player response",
+                        "details": e
+                    }));
+                }
+            };
+        let mut cache_path = cache_path;
+        if let Some(auth) = used_auth {
+            settings.user_scope = Some(auth.scope.clone());
+            cache_path = converted_cache_path(&tmp_for_conversion, &video_id, codec, &settings);
+            if fs::metadata(&cache_path).is_ok() {
+                log::info!("Converted {} ({}) cached for this user. Serving.", video_id, codec.as_str());
+                return serve_file_from_cache(&cache_path, &req, codec.mime_type(), None);
+            }
+        }
+        let live = LiveStatus::from_player_response(&player_response);
+        if live.is_upcoming {
+            return live.not_started_response(&video_id);
+        }
+        if live.is_live {
+            if settings.subtitles_lang.is_some() {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Subtitles are not supported for live streams",
+                    "details": format!("{} is live; drop the subs parameter", video_id)
                 }));
             }
-        };
+            return serve_live_stream(&req, &data, &video_id, &player_response, Some(codec), settings, "").await;
+        }
         let duration_seconds = get_duration_from_player_response(&player_response);
         if duration_seconds > 3300 {
             return HttpResponse::BadRequest().json(serde_json::json!({
@@ -2422,12 +4099,25 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
             }));
         }
 
-        let direct_url = match resolve_direct_stream_url(&video_id, Some("360"), false, &data.config).await {
+        // Параллельные первые запросы того же файла ждут одну конвертацию
+        let output_lock = crate::conversions::lock_output(&cache_path).await;
+        if let Ok(meta) = fs::metadata(&cache_path) {
+            log::info!("Converted {} ({}) finished by another request. Serving.", video_id, codec.as_str());
+            if let Some(offset) = flv_start_offset(codec, start, meta.len()) {
+                if req.method() != actix_web::http::Method::HEAD {
+                    return serve_flv_from_offset(&cache_path, offset);
+                }
+            }
+            return serve_file_from_cache(&cache_path, &req, codec.mime_type(), None);
+        }
+
+        let source_quality = height.to_string();
+        let direct_url = match resolve_direct_stream_url(&video_id, Some(&source_quality), false, &data.config, used_auth).await {
             Ok(url) => {
                 // Если yt-dlp вернул HLS для старых кодеков, форсируем MP4
                 if url.contains(".m3u8") {
                     log::warn!("YT-DLP вернул HLS для {}, форсируем MP4-поиск...", video_id);
-                    match resolve_direct_stream_url(&video_id, None, false, &data.config).await {
+                    match resolve_direct_stream_url(&video_id, None, false, &data.config, used_auth).await {
                         Ok(u) => u,
                         Err(_) => url,
                     }
@@ -2442,25 +4132,35 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
 				}));
 			}
 		};
+		if let Some(lang) = settings.subtitles_lang.clone() {
+			match write_subtitles_file(&video_id, &lang, &tmp_for_conversion, &data.config).await {
+				Ok(path) => settings.subtitles_file = Some(path),
+				Err(e) => {
+					let status = if e.starts_with("No caption track") {
+						actix_web::http::StatusCode::NOT_FOUND
+					} else {
+						actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+					};
+					return HttpResponse::build(status).json(serde_json::json!({
+						"error": "Failed to fetch subtitles",
+						"details": e
+					}));
+				}
+			}
+		}
 		let user_agent = data.config.get_innertube_user_agent();
-		let permit = data.codec_semaphore.clone().acquire_owned().await.ok();
-
-
-        let tmp_for_conversion = data.config.cache.temp_dir.as_deref()
-            .filter(|s| !s.trim().is_empty())
-            .map(PathBuf::from)
-            .unwrap_or_else(|| env::temp_dir());
-        if let Err(e) = fs::create_dir_all(&tmp_for_conversion) {
-            log::warn!("Не удалось создать временную папку {}: {}", tmp_for_conversion.display(), e);
-        }
+		let label = format!("video {} {} {}p", video_id, codec.as_str(), settings.height);
+		let permit = crate::conversions::acquire(&data.codec_semaphore, label)
+			.await
+			.map(|p| p.with_output(output_lock));
 
-		return stream_converted_video(&direct_url, &user_agent, &video_id, codec_str, permit, tmp_for_conversion);
+		return stream_converted_video(&direct_url, &user_agent, codec, &settings, permit, cache_path, start);
 	}
 
     // 2. HLS
     let hls_only = query_params.get("hls").map(|v| v == "true").unwrap_or(false);
     if hls_only {
-        match get_hls_manifest_url(&video_id, &data.config).await {
+        match get_hls_manifest_url(&video_id, &data.config, player_auth.as_ref()).await {
             Ok(manifest_url) => {
                 return HttpResponse::Ok().json(serde_json::json!({
                     "hls_manifest_url": manifest_url,
@@ -2481,23 +4181,48 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
     let use_proxy = proxy_param != "false";
 
     // Получаем инфо о видео (нам нужна длительность)
-    let player_response = match fetch_player_response(&video_id, &data.config).await {
-        Ok(data) => data,
-        Err(e) => {
-             return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch player response",
-                "details": e
-            }));
-        }
-    };
+    let (player_response, used_auth) =
+        match fetch_player_response_for(&video_id, &data.config, player_auth.as_ref()).await {
+            Ok(data) => data,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to fetch player response",
+                    "details": e
+                }));
+            }
+        };
 
     let duration_seconds = get_duration_from_player_response(&player_response);
     let requested_quality = query_params.get("quality").map(|q| q.as_str());
     
+    let profile_max_height = device_profile.as_ref().map(|(_, p)| p.max_height);
     let mut target_height = requested_quality
         .and_then(|q| parse_quality_height(q))
+        .map(|h| profile_max_height.map_or(h, |max| h.min(max)))
+        .or(profile_max_height)
         .unwrap_or_else(|| parse_quality_height(&data.config.video.default_quality).unwrap_or(360));
 
+    let live = LiveStatus::from_player_response(&player_response);
+    if live.is_upcoming {
+        return live.not_started_response(&video_id);
+    }
+    if live.is_live {
+        let live_mode = query_params
+            .get("live")
+            .map(|m| m.to_lowercase())
+            .unwrap_or_else(|| "hls".to_string());
+        return serve_live_stream(
+            &req,
+            &data,
+            &video_id,
+            &player_response,
+            None,
+            ConversionSettings::new(target_height),
+            &live_mode,
+        )
+        .await;
+    }
+
     // Ограничение для ОЧЕНЬ длинных видео: 
     // Если > 30 минут, не даём склеивать качество выше 480p, чтобы сервер не завис
     if target_height > 480 && duration_seconds > 1800 {
@@ -2510,7 +4235,7 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
     // Если запрошено ИМЕННО 360p, пытаемся отдать готовый файл itag=18
     // Это экономит мощности сервера, так как YouTube сам хранит аудио и видео вместе для 360p
     if target_height == 360 {
-        if let Some(u) = resolve_direct_stream_url(&video_id, Some("360"), false, &data.config).await.ok() {
+        if let Some(u) = resolve_direct_stream_url(&video_id, Some("360"), false, &data.config, used_auth).await.ok() {
             // Отдаем только если это честный цельный MP4
             if !u.contains(".m3u8") && u.contains("itag=18") {
                 log::info!("Found ready 360p mp4 stream (itag=18) for {}", video_id);
@@ -2545,7 +4270,7 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
     // Скачиваем DASH видео и аудио, склеиваем через ffmpeg
     log::info!("Target quality {}p requires server-side muxing for {}", target_height, video_id);
     
-    match download_mux_to_temp_file(video_id.clone(), target_height, &data.config.cache).await {
+    match download_mux_to_temp_file(video_id.clone(), target_height, &data.config.cache, used_auth).await {
         Ok(path) => {
             log::info!("Download/mux complete: {}. Serving file via ReaderStream.", path.display());
             // Функция serve_mp4_from_cache теперь отдаёт поток и правильно отвечает на HEAD запросы
@@ -2561,11 +4286,26 @@ pub async fn direct_url(req: HttpRequest, data: web::Data<crate::AppState>) -> i
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/admin/player_clients",
+    responses(
+        (status = 200, description = "Innertube player clients in the order the next request will try them, with success/failure counters and demotion state", body = Vec<crate::player_clients::PlayerClientStatus>),
+        (status = 401, description = "Missing or invalid admin credentials"),
+        (status = 403, description = "Admin access is not configured")
+    ),
+    security(("admin_token" = []), ("admin_basic" = []))
+)]
+pub async fn player_clients_status(data: web::Data<crate::AppState>) -> impl Responder {
+    HttpResponse::Ok().json(crate::player_clients::status(&data.config))
+}
+
 #[utoipa::path(
     get,
     path = "/hls_manifest_url",
     params(
-        ("video_id" = String, Query, description = "YouTube video ID")
+        ("video_id" = String, Query, description = "YouTube video ID"),
+        ("token" = Option<String>, Query, description = "Refresh token (or session_id cookie) for age-restricted and members-only videos")
     ),
     responses(
         (status = 200, description = "HLS Manifest URL", body = HlsManifestUrlResponse),
@@ -2591,7 +4331,8 @@ pub async fn hls_manifest_url(req: HttpRequest, data: web::Data<crate::AppState>
         }
     };
 
-    match get_hls_manifest_url(&video_id, &data.config).await {
+    let player_auth = PlayerAuth::from_request(&req).await;
+    match get_hls_manifest_url(&video_id, &data.config, player_auth.as_ref()).await {
         Ok(manifest_url) => {
             HttpResponse::Ok().json(HlsManifestUrlResponse {
                 hls_manifest_url: manifest_url,
@@ -2613,11 +4354,14 @@ pub async fn hls_manifest_url(req: HttpRequest, data: web::Data<crate::AppState>
     path = "/direct_audio_url",
     params(
         ("video_id" = String, Query, description = "YouTube video ID"),
-        ("proxy" = Option<String>, Query, description = "Pass-through proxy (true/false)")
+        ("proxy" = Option<String>, Query, description = "Pass-through proxy (true/false)"),
+        ("format" = Option<String>, Query, description = "Transcode to mp3, aac-lc, ogg or amr-nb (tagged with title/artist, mp3/aac-lc also with cover art). Without it the original m4a stream is returned"),
+        ("bitrate" = Option<f64>, Query, description = "Target bitrate in kbps for format= (default 128; amr-nb: 4.75-12.2)")
     ),
     responses(
         (status = 200, description = "Audio stream"),
-        (status = 400, description = "Missing video_id")
+        (status = 400, description = "Missing video_id, unsupported format or video longer than 55 minutes"),
+        (status = 500, description = "Failed to resolve or transcode audio")
     )
 )]
 pub async fn direct_audio_url(
@@ -2641,13 +4385,82 @@ pub async fn direct_audio_url(
         }
     };
 
+    if let Some(format_str) = query_params.get("format").map(|f| f.as_str()) {
+        let format = match AudioFormat::from_param(format_str) {
+            Some(f) => f,
+            None => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "Unsupported format",
+                    "details": format!(
+                        "Format '{}' is not supported. Available: {}",
+                        format_str,
+                        AudioFormat::SUPPORTED.join(", ")
+                    ),
+                    "supported_formats": AudioFormat::SUPPORTED
+                }));
+            }
+        };
+        let custom_temp_dir = data.config.cache.temp_dir.clone()
+            .map(PathBuf::from)
+            .filter(|p| !p.as_os_str().is_empty());
+        spawn_direct_url_cleanup_if_needed(custom_temp_dir);
+
+        let requested_kbps = query_params.get("bitrate").and_then(|b| b.parse::<f64>().ok());
+        let bitrate_bps = format.bitrate_bps(requested_kbps);
+        let temp_dir = audio_transcode_temp_dir(&data.config);
+        let cache_path = transcoded_audio_cache_path(&temp_dir, &video_id, format, bitrate_bps);
+        if cache_path.exists() {
+            log::info!("Audio {} ({}) cached. Serving.", video_id, format.as_str());
+            return serve_file_from_cache(&cache_path, &req, format.mime_type(), None);
+        }
+
+        // Транскодируем целиком до ответа, поэтому тот же лимит 55 минут, что и в direct_url
+        let player_response = match fetch_player_metadata(&video_id, &data.config).await {
+            Ok(pr) => pr,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to fetch player response",
+                    "details": e
+                }));
+            }
+        };
+        let duration_seconds = get_duration_from_player_response(&player_response);
+        if duration_seconds > 3300 {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Video too long for conversion",
+                "details": format!("Video duration ({}s) exceeds 55 minutes limit", duration_seconds)
+            }));
+        }
+
+        return match transcode_audio_to_cache(
+            &video_id,
+            format,
+            bitrate_bps,
+            &temp_dir,
+            cache_path,
+            &player_response,
+            &data,
+        )
+        .await
+        {
+            Ok(path) => serve_file_from_cache(&path, &req, format.mime_type(), None),
+            Err(e) => {
+                log::error!("Failed to transcode audio for {}: {}", video_id, e);
+                HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to transcode audio",
+                    "details": e
+                }))
+            }
+        };
+    }
+
     let proxy_param = query_params
         .get("proxy")
         .map(|p| p.to_lowercase())
         .unwrap_or_else(|| "true".to_string());
     let use_proxy = proxy_param != "false";
 
-    let direct_url = match resolve_direct_stream_url(&video_id, None, true, &data.config).await {
+    let direct_url = match resolve_direct_stream_url(&video_id, None, true, &data.config, None).await {
         Ok(url) => url,
         Err(e) => {
             return HttpResponse::InternalServerError().json(serde_json::json!({
@@ -2682,6 +4495,40 @@ pub async fn direct_audio_url(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/hls.proxy",
+    params(
+        ("url" = String, Query, description = "YouTube HLS playlist URL (googlevideo.com / youtube.com)"),
+        ("proxy" = Option<String>, Query, description = "Route segments through /video.proxy (default true)")
+    ),
+    responses(
+        (status = 200, description = "Rewritten HLS playlist"),
+        (status = 400, description = "Missing or non-YouTube url"),
+        (status = 502, description = "Failed to fetch the playlist")
+    )
+)]
+pub async fn hls_proxy(req: HttpRequest, data: web::Data<crate::AppState>) -> impl Responder {
+    let query_params = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map(|q| q.into_inner())
+        .unwrap_or_default();
+    let url = match query_params.get("url") {
+        Some(u) if is_youtube_media_url(u) => u.clone(),
+        Some(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Only YouTube HLS playlists can be proxied"
+            }));
+        }
+        None => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Missing url parameter"
+            }));
+        }
+    };
+    let use_proxy = query_params.get("proxy").map(|p| p.to_lowercase() != "false").unwrap_or(true);
+    serve_hls_playlist(&req, &data.config, &url, use_proxy).await
+}
+
 #[utoipa::path(
     get,
     path = "/video.proxy",
@@ -2762,7 +4609,7 @@ pub async fn download_video(req: HttpRequest, data: web::Data<crate::AppState>)
     };
 
     let quality = query_params.get("quality").map(|q| q.as_str());
-    let direct_url = match resolve_direct_stream_url(&video_id, quality, false, &data.config).await
+    let direct_url = match resolve_direct_stream_url(&video_id, quality, false, &data.config, None).await
     {
         Ok(url) => url,
         Err(e) => {
@@ -2933,7 +4780,7 @@ fn extract_video_from_lockup(lockup: &serde_json::Value) -> Option<RelatedVideoI
                             .and_then(|t| t.get("content"))
                             .and_then(|c| c.as_str())
                         {
-                            published = published_raw.trim().to_string();
+                            published = crate::locale::normalize_relative_time(published_raw.trim());
                         }
                     }
                 }
@@ -2983,24 +4830,220 @@ fn extract_video_from_lockup(lockup: &serde_json::Value) -> Option<RelatedVideoI
 }
 
 
-async fn fetch_player_response(
+/// Access tokens by refresh token, valid until the stored unix time.
+type CachedAccessToken = (String, u64);
+
+lazy_static! {
+    static ref ACCESS_TOKEN_CACHE: tokio::sync::Mutex<lru::LruCache<String, CachedAccessToken>> =
+        tokio::sync::Mutex::new(lru::LruCache::new(std::num::NonZeroUsize::new(500).unwrap()));
+}
+
+/// Google access tokens live an hour; refresh a bit earlier.
+const ACCESS_TOKEN_TTL: u64 = 3000;
+
+/// Credentials of the signed-in user making the request. Used only when the
+/// anonymous player call is refused (age-restricted, members-only videos), so
+/// the refresh token is exchanged for an access token only then.
+#[derive(Clone)]
+pub(crate) struct PlayerAuth {
+    refresh_token: String,
+    auth_config: web::Data<crate::routes::auth::AuthConfig>,
+    /// Filled by `access_token()` on first use.
+    access_token: tokio::sync::OnceCell<String>,
+    /// HMAC of the refresh token (keyed with `server.secret_key`); files produced
+    /// with these credentials are cached under it so other users never get them.
+    scope: String,
+}
+
+impl PlayerAuth {
+    /// Refresh token from `token=` or the `session_id` cookie (as the account
+    /// endpoints do). None for anonymous requests, and when `server.secret_key`
+    /// is unset: the per-user cache scope cannot be derived safely without it.
+    pub(crate) async fn from_request(req: &HttpRequest) -> Option<Self> {
+        let auth_config = req.app_data::<web::Data<crate::routes::auth::AuthConfig>>()?;
+        let refresh_token = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .ok()
+            .and_then(|q| q.get("token").cloned())
+            .or_else(|| {
+                let store = req.app_data::<web::Data<crate::routes::auth::TokenStore>>()?;
+                req.cookie("session_id").and_then(|c| store.get_token(c.value()))
+            })
+            .filter(|t| !t.is_empty() && !t.starts_with("Error"))?;
+
+        let secret = req
+            .app_data::<web::Data<crate::AppState>>()
+            .map(|state| state.config.server.secretkey.clone())
+            .unwrap_or_default();
+        if secret.trim().is_empty() {
+            log::warn!("Player auth: server.secret_key is not set, ignoring the user's token");
+            return None;
+        }
+        let scope = {
+            use hmac::{Hmac, Mac};
+            let mut mac = <Hmac<sha2::Sha256> as Mac>::new_from_slice(secret.as_bytes()).ok()?;
+            mac.update(refresh_token.as_bytes());
+            mac.finalize().into_bytes()[..12]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        };
+        Some(Self {
+            refresh_token,
+            auth_config: auth_config.clone(),
+            access_token: tokio::sync::OnceCell::new(),
+            scope,
+        })
+    }
+
+    /// Access token for the refresh token, from the cache while it is valid.
+    async fn access_token(&self) -> Result<&str, String> {
+        self.access_token
+            .get_or_try_init(|| async {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+                if let Some((token, expires)) = ACCESS_TOKEN_CACHE.lock().await.get(&self.refresh_token) {
+                    if *expires > now {
+                        return Ok(token.clone());
+                    }
+                }
+                let access_token =
+                    crate::routes::oauth::refresh_access_token(&self.refresh_token, &self.auth_config).await?;
+                ACCESS_TOKEN_CACHE
+                    .lock()
+                    .await
+                    .put(self.refresh_token.clone(), (access_token.clone(), now + ACCESS_TOKEN_TTL));
+                Ok(access_token)
+            })
+            .await
+            .map(|t| t.as_str())
+    }
+
+    /// yt-dlp arguments sending the same OAuth token with the TV client. The header
+    /// goes through a private config file so the token never shows up in `ps`.
+    async fn yt_dlp_args(&self) -> Result<YtDlpAuthArgs, String> {
+        let access_token = self.access_token().await?;
+        let path = env::temp_dir().join(format!("yt_api_auth_{}.conf", uuid::Uuid::new_v4().simple()));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .map_err(|e| format!("Failed to create yt-dlp auth config: {}", e))?;
+        let guard = YtDlpAuthArgs { path };
+        writeln!(file, "--add-header \"Authorization:Bearer {}\"", access_token)
+            .map_err(|e| format!("Failed to write yt-dlp auth config: {}", e))?;
+        Ok(guard)
+    }
+}
+
+/// yt-dlp config with the user's Authorization header; removed once dropped,
+/// so keep it alive until yt-dlp exits.
+pub(crate) struct YtDlpAuthArgs {
+    path: PathBuf,
+}
+
+impl YtDlpAuthArgs {
+    fn args(&self) -> Vec<String> {
+        vec![
+            "--config-locations".to_string(),
+            self.path.to_string_lossy().to_string(),
+            "--extractor-args".to_string(),
+            "youtube:player_client=tv".to_string(),
+        ]
+    }
+}
+
+impl Drop for YtDlpAuthArgs {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Player response the video can actually be played from.
+fn is_playable(data: &Value) -> bool {
+    let status = data
+        .get("playabilityStatus")
+        .and_then(|p| p.get("status"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+    // LIVE_STREAM_OFFLINE — запланированная трансляция, логин тут не поможет
+    status == "LIVE_STREAM_OFFLINE" || (status == "OK" && has_plain_streams(data))
+}
+
+/// streamingData with something we can fetch without deciphering: a format with a
+/// plain `url` or an HLS manifest. TV / embedded / MWEB clients often return only
+/// `signatureCipher` formats, which direct_url and the proxy cannot use.
+fn has_plain_streams(data: &Value) -> bool {
+    let Some(streaming) = data.get("streamingData") else {
+        return false;
+    };
+    if streaming.get("hlsManifestUrl").and_then(|u| u.as_str()).is_some() {
+        return true;
+    }
+    ["formats", "adaptiveFormats"].iter().any(|key| {
+        streaming
+            .get(*key)
+            .and_then(|f| f.as_array())
+            .is_some_and(|arr| arr.iter().any(|f| f.get("url").and_then(|u| u.as_str()).is_some()))
+    })
+}
+
+/// Why a player response should be retried with the next client, or None when it
+/// is final (playable, or an error no other client will fix, like a removed video).
+fn player_retry_reason(data: &Value) -> Option<String> {
+    let status = data
+        .get("playabilityStatus")
+        .and_then(|p| p.get("status"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+    match status {
+        "LOGIN_REQUIRED" | "UNPLAYABLE" | "AGE_CHECK_REQUIRED" | "AGE_VERIFICATION_REQUIRED" => {
+            Some(status.to_string())
+        }
+        "OK" if data.get("streamingData").is_none() => Some("no streamingData".to_string()),
+        "OK" if !has_plain_streams(data) => Some("only ciphered formats".to_string()),
+        _ => None,
+    }
+}
+
+/// Retry reason for callers that only read metadata (captions, storyboards,
+/// duration, tags): the streams do not matter, only that YouTube served the video.
+fn metadata_retry_reason(data: &Value) -> Option<String> {
+    let status = data
+        .get("playabilityStatus")
+        .and_then(|p| p.get("status"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
+    match status {
+        "LOGIN_REQUIRED" | "UNPLAYABLE" | "AGE_CHECK_REQUIRED" | "AGE_VERIFICATION_REQUIRED" => {
+            Some(status.to_string())
+        }
+        _ if data.get("videoDetails").is_none() => Some("no videoDetails".to_string()),
+        _ => None,
+    }
+}
+
+async fn fetch_player_response_with_client(
     video_id: &str,
+    api_key: &str,
+    player_client: &crate::config::InnertubeClientConfig,
     config: &crate::config::Config,
 ) -> Result<Value, String> {
-    let api_key = config
-        .get_innertube_key()
-        .ok_or("innertube api key не задан в config.yml (api.innertube.key)")?;
-    let client = Client::new();
-    let user_agent = config.get_innertube_user_agent();
-    let player_client = config.get_innertube_player_client();
-    let json_data = serde_json::json!({
+    let user_agent = config.get_innertube_client_user_agent(player_client);
+    let mut json_data = serde_json::json!({
         "context": {
             "client": player_client.to_player_context_value()
         },
         "videoId": video_id
     });
+    if player_client.client_screen.as_deref() == Some("EMBED") {
+        json_data["context"]["thirdParty"] = serde_json::json!({ "embedUrl": "https://www.youtube.com/" });
+    }
     let url = format!("https://www.youtube.com/youtubei/v1/player?key={}", api_key);
-    let resp = client
+    let resp = Client::new()
         .post(&url)
         .header("User-Agent", &user_agent)
         .header("Accept-Language", "en-US,en;q=0.9")
@@ -3015,8 +5058,153 @@ async fn fetch_player_response(
     resp.json::<Value>().await.map_err(|e| e.to_string())
 }
 
-async fn get_hls_manifest_url(video_id: &str, config: &crate::config::Config) -> Result<String, String> {
-    let data = fetch_player_response(video_id, config).await?;
+/// Player response for callers that only need metadata: stops at the first
+/// client that serves the video, whether or not its streams are usable.
+pub(crate) async fn fetch_player_metadata(
+    video_id: &str,
+    config: &crate::config::Config,
+) -> Result<Value, String> {
+    fetch_player_response_chain(video_id, config, metadata_retry_reason, None).await
+}
+
+/// Player response from the first client in the chain (see `player_clients`)
+/// that `retry_reason` accepts. When none does, the first response is returned
+/// so callers still see YouTube's playability error. `skip_client` is left out
+/// (it is about to be tried with the user's credentials).
+async fn fetch_player_response_chain(
+    video_id: &str,
+    config: &crate::config::Config,
+    retry_reason: fn(&Value) -> Option<String>,
+    skip_client: Option<&str>,
+) -> Result<Value, String> {
+    let api_key = config
+        .get_innertube_key()
+        .ok_or("innertube api key не задан в config.yml (api.innertube.key)")?;
+
+    let mut failed: Vec<(String, String)> = Vec::new();
+    let mut first_result: Option<Result<Value, String>> = None;
+    for player_client in crate::player_clients::ordered_clients(config) {
+        if skip_client.is_some_and(|skip| player_client.client_name.eq_ignore_ascii_case(skip)) {
+            continue;
+        }
+        let name = player_client.client_name.clone();
+        let result = fetch_player_response_with_client(video_id, &api_key, &player_client, config).await;
+        let reason = match &result {
+            Ok(data) => match retry_reason(data) {
+                None => {
+                    // Неудачи предыдущих клиентов засчитываем, только если какой-то клиент справился
+                    if data.get("streamingData").is_some() {
+                        for (failed_name, failed_reason) in &failed {
+                            crate::player_clients::record_failure(failed_name, failed_reason, config);
+                        }
+                        crate::player_clients::record_success(&name);
+                    }
+                    if !failed.is_empty() {
+                        log::info!("Player for {} served by fallback client {}", video_id, name);
+                    }
+                    return result;
+                }
+                Some(reason) => reason,
+            },
+            Err(e) => e.clone(),
+        };
+        log::info!("Player client {} refused {}: {}", name, video_id, reason);
+        failed.push((name, reason));
+        if !matches!(first_result, Some(Ok(_))) {
+            first_result = Some(result);
+        }
+    }
+    first_result.unwrap_or_else(|| Err("no innertube player clients configured".to_string()))
+}
+
+/// Client of `fetch_player_response_authenticated`.
+const AUTHENTICATED_PLAYER_CLIENT: &str = "TVHTML5";
+
+/// Player call as the signed-in user with the TV client, which accepts OAuth tokens.
+async fn fetch_player_response_authenticated(
+    video_id: &str,
+    config: &crate::config::Config,
+    auth: &PlayerAuth,
+) -> Result<Value, String> {
+    let api_key = config
+        .get_innertube_key()
+        .ok_or("innertube api key не задан в config.yml (api.innertube.key)")?;
+    let json_data = serde_json::json!({
+        "context": {
+            "client": {
+                "hl": "en", "gl": "US", "deviceMake": "Samsung", "deviceModel": "SmartTV",
+                "userAgent": "Mozilla/5.0 (SMART-TV; Linux; Tizen 5.0) AppleWebKit/538.1",
+                "clientName": "TVHTML5", "clientVersion": "7.20250209.19.00",
+                "osName": "Tizen", "osVersion": "5.0", "platform": "TV",
+                "clientFormFactor": "UNKNOWN_FORM_FACTOR", "screenPixelDensity": 1
+            }
+        },
+        "videoId": video_id,
+        "contentCheckOk": true,
+        "racyCheckOk": true
+    });
+    let access_token = auth.access_token().await?;
+    let url = format!("https://www.youtube.com/youtubei/v1/player?key={}", api_key);
+    let resp = Client::new()
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("User-Agent", "Mozilla/5.0 (SMART-TV; Linux; Tizen 5.0) AppleWebKit/538.1")
+        .header("Content-Type", "application/json")
+        .json(&json_data)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("authenticated player API HTTP {}", resp.status()));
+    }
+    resp.json::<Value>().await.map_err(|e| e.to_string())
+}
+
+/// Anonymous player call, retried with the user's credentials when YouTube refuses
+/// it (age gate, members-only). Returns the auth that was actually used, so callers
+/// can pass it on to yt-dlp and keep the results out of shared caches.
+async fn fetch_player_response_for<'a>(
+    video_id: &str,
+    config: &crate::config::Config,
+    auth: Option<&'a PlayerAuth>,
+) -> Result<(Value, Option<&'a PlayerAuth>), String> {
+    // С учёткой TVHTML5 всё равно пробуется ниже, анонимная попытка им лишняя
+    let skip_client = auth.map(|_| AUTHENTICATED_PLAYER_CLIENT);
+    let anonymous = fetch_player_response_chain(video_id, config, player_retry_reason, skip_client).await;
+    let auth = match (&anonymous, auth) {
+        (Ok(data), _) if is_playable(data) => return Ok((anonymous?, None)),
+        (_, None) => return anonymous.map(|data| (data, None)),
+        (_, Some(auth)) => auth,
+    };
+
+    let reason = anonymous
+        .as_ref()
+        .ok()
+        .and_then(|d| d.get("playabilityStatus"))
+        .and_then(|p| p.get("status"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("error")
+        .to_string();
+    log::info!("Player for {} refused anonymously ({}), retrying as signed-in user", video_id, reason);
+    match fetch_player_response_authenticated(video_id, config, auth).await {
+        Ok(data) if is_playable(&data) => Ok((data, Some(auth))),
+        Ok(data) => {
+            log::info!("Signed-in player for {} is not playable either", video_id);
+            anonymous.or(Ok(data)).map(|d| (d, None))
+        }
+        Err(e) => {
+            log::warn!("Authenticated player request for {} failed: {}", video_id, e);
+            anonymous.map(|d| (d, None))
+        }
+    }
+}
+
+async fn get_hls_manifest_url(
+    video_id: &str,
+    config: &crate::config::Config,
+    auth: Option<&PlayerAuth>,
+) -> Result<String, String> {
+    let (data, _) = fetch_player_response_for(video_id, config, auth).await?;
     get_hls_manifest_url_from_player(&data)
 }
 
@@ -3063,9 +5251,14 @@ fn get_direct_stream_url_from_player_response(data: &Value) -> Option<String> {
     let streaming = data.get("streamingData")?;
     let mut best: Option<(u32, &str)> = None;
     for key in &["formats", "adaptiveFormats"] {
-        let arr = streaming.get(*key)?.as_array()?;
+        let Some(arr) = streaming.get(*key).and_then(|v| v.as_array()) else {
+            continue;
+        };
         for f in arr {
-            let url = f.get("url").and_then(|v| v.as_str())?;
+            // Форматы с signatureCipher без расшифровки не скачать
+            let Some(url) = f.get("url").and_then(|v| v.as_str()) else {
+                continue;
+            };
             let label = f.get("qualityLabel").and_then(|v| v.as_str()).unwrap_or("");
             let height: u32 = label.trim_end_matches('p').parse().unwrap_or(0);
             if *key == "adaptiveFormats" && height == 0 {
@@ -3087,6 +5280,15 @@ fn serve_mp4_from_cache(
     path: &Path,
     req: &HttpRequest,
     duration_seconds: Option<u64>,
+) -> HttpResponse {
+    serve_file_from_cache(path, req, "video/mp4", duration_seconds)
+}
+
+fn serve_file_from_cache(
+    path: &Path,
+    req: &HttpRequest,
+    content_type: &str,
+    duration_seconds: Option<u64>,
 ) -> HttpResponse {
     let file_size = match fs::metadata(path) {
         Ok(m) => m.len(),
@@ -3096,7 +5298,7 @@ fn serve_mp4_from_cache(
     if req.method() == actix_web::http::Method::HEAD {
         let mut builder = HttpResponse::Ok();
         builder
-            .insert_header((CONTENT_TYPE, "video/mp4"))
+            .insert_header((CONTENT_TYPE, content_type.to_string()))
             .insert_header(("Accept-Ranges", "bytes"))
             .insert_header((CONTENT_LENGTH, file_size.to_string()));
         if let Some(secs) = duration_seconds {
@@ -3138,7 +5340,7 @@ fn serve_mp4_from_cache(
 
     let mut builder = HttpResponse::build(status);
     builder
-        .insert_header((CONTENT_TYPE, "video/mp4"))
+        .insert_header((CONTENT_TYPE, content_type.to_string()))
         .insert_header(("Accept-Ranges", "bytes"))
         .insert_header((CONTENT_LENGTH, limit.to_string()));
 
@@ -3243,9 +5445,21 @@ async fn get_channel_avatar_url(
     }
 }
 
-async fn proxy_image(url: &str) -> HttpResponse {
-    let processed_url = url.replace("s900", "s88");
-    
+/// Downloads an image (avatar, banner), validates it and applies `transform`.
+/// Errors are ready-made responses.
+async fn proxy_image(url: &str, transform: &ImageTransform) -> Result<(Vec<u8>, String), HttpResponse> {
+    lazy_static! {
+        static ref SIZE_PARAM: regex::Regex = regex::Regex::new(r"=s\d+(-|$)").unwrap();
+    }
+
+    // Аватарки googleusercontent/ggpht отдаются нужного размера по =sNNN
+    let processed_url = match transform.width.max(transform.height) {
+        Some(size) => SIZE_PARAM
+            .replace(url, format!("=s{}$1", size).as_str())
+            .to_string(),
+        None => url.replace("s900", "s88"),
+    };
+
     let client = Client::builder()
         .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36"
 )
@@ -3264,10 +5478,10 @@ async fn proxy_image(url: &str) -> HttpResponse {
             // Validate that content type is an image
             if !content_type.starts_with("image/") {
                 crate::log::info!("Blocked non-image content-type: {} from URL: {}", content_type, url);
-                return HttpResponse::Forbidden()
+                return Err(HttpResponse::Forbidden()
                     .json(serde_json::json!({
                         "error": "Only image files are allowed"
-                    }));
+                    })));
             }
 
             // Additional validation: check for dangerous content types
@@ -3290,20 +5504,20 @@ async fn proxy_image(url: &str) -> HttpResponse {
 
             if !is_allowed {
                 crate::log::info!("Blocked unsupported image type: {} from URL: {}", content_type, url);
-                return HttpResponse::Forbidden()
+                return Err(HttpResponse::Forbidden()
                     .json(serde_json::json!({
                         "error": "Unsupported image format"
-                    }));
+                    })));
             }
 
             match resp.bytes().await {
                 Ok(bytes) => {
                     // Additional security: verify the file starts with valid image magic bytes
                     if bytes.len() < 2 {
-                        return HttpResponse::NotFound()
+                        return Err(HttpResponse::NotFound()
                             .json(serde_json::json!({
                                 "error": "Invalid image file"
-                            }));
+                            })));
                     }
 
                     // Check magic bytes for common image formats
@@ -3333,25 +5547,33 @@ async fn proxy_image(url: &str) -> HttpResponse {
 
                     if !is_valid_image {
                         crate::log::info!("Blocked file with invalid magic bytes, content-type: {}", content_type);
-                        return HttpResponse::Forbidden()
+                        return Err(HttpResponse::Forbidden()
                             .json(serde_json::json!({
                                 "error": "Invalid image file format"
-                            }));
+                            })));
                     }
 
-                    HttpResponse::Ok()
-                        .content_type(content_type)
-                        .insert_header(("Cache-Control", "public, max-age=86400"))
-                        .body(bytes)
+                    let (data, content_type) = match transform_image(transform, bytes.to_vec(), content_type.clone()).await {
+                        Ok(result) => result,
+                        // SVG/ICO и прочее, что image не декодирует, отдаём как есть, если изменений не просили
+                        Err(_) if !transform.resizes() && transform.format.is_none() => (bytes.to_vec(), content_type),
+                        Err(e) => {
+                            return Err(HttpResponse::UnsupportedMediaType().json(serde_json::json!({
+                                "error": "Failed to process image",
+                                "details": e
+                            })));
+                        }
+                    };
+
+                    Ok((data, content_type))
                 },
-                Err(_) => HttpResponse::NotFound().finish(),
+                Err(_) => Err(HttpResponse::NotFound().finish()),
             }
         }
-        _ => HttpResponse::NotFound().finish(),
+        _ => Err(HttpResponse::NotFound().finish()),
     }
 }
 
 fn clean_views_string(views_raw: &str) -> String {
-    let cleaned = views_raw.replace(|c: char| !c.is_ascii_digit() && c != 'K' && c != 'M' && c != '.', "");
-    cleaned.replace("K", "000").replace("M", "000000").replace(".", "")
+    crate::locale::count_string(views_raw, "")
 }
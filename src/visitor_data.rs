@@ -0,0 +1,233 @@
+//! Visitor IDs (`visitorData`) for anonymous innertube requests. YouTube treats
+//! calls without one as a new browser every time, which skews results and trips
+//! bot checks; a fixed one makes every user share the same feed. We keep a small
+//! rotating pool, and optionally pin one ID per client session. Signed-in calls
+//! get an ID pinned to the account and never one from the shared pool.
+
+use actix_web::HttpRequest;
+use lazy_static::lazy_static;
+use lru::LruCache;
+use reqwest::{Client, RequestBuilder};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+
+/// After a failed fetch, serve what we have (or nothing) for this long.
+const FETCH_RETRY_SECS: u64 = 60;
+
+#[derive(Clone)]
+struct VisitorId {
+    value: String,
+    created: u64,
+}
+
+struct Pool {
+    entries: Vec<VisitorId>,
+    next: usize,
+    last_failure: u64,
+}
+
+lazy_static! {
+    static ref POOL: Mutex<Pool> = Mutex::new(Pool {
+        entries: Vec::new(),
+        next: 0,
+        last_failure: 0,
+    });
+    static ref PINNED: Mutex<LruCache<String, VisitorId>> =
+        Mutex::new(LruCache::new(std::num::NonZeroUsize::new(2000).unwrap()));
+    static ref CLIENT: Client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+    static ref VISITOR_DATA_RE: regex::Regex = regex::Regex::new(r#""VISITOR_DATA":"([^"]+)""#).unwrap();
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+async fn fetch_visitor_data(config: &Config) -> Result<String, String> {
+    let client = &*CLIENT;
+    if let Some(key) = config.get_innertube_key() {
+        let payload = serde_json::json!({
+            "context": {
+                "client": {
+                    "clientName": "WEB",
+                    "clientVersion": "2.20260220.00.00",
+                    "hl": "en",
+                    "gl": "US"
+                }
+            }
+        });
+        let url = format!("https://www.youtube.com/youtubei/v1/visitor_id?key={}", key);
+        if let Ok(resp) = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+        {
+            if let Ok(data) = resp.json::<Value>().await {
+                if let Some(visitor) = data
+                    .get("responseContext")
+                    .and_then(|r| r.get("visitorData"))
+                    .and_then(|v| v.as_str())
+                {
+                    return Ok(visitor.to_string());
+                }
+            }
+        }
+    }
+
+    // Запасной путь: VISITOR_DATA из ytcfg главной страницы
+    let html = client
+        .get("https://www.youtube.com/")
+        .header(
+            "User-Agent",
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+        )
+        .header("Accept-Language", "en-US,en;q=0.9")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+    VISITOR_DATA_RE
+        .captures(&html)
+        .map(|c| c[1].to_string())
+        .ok_or_else(|| "VISITOR_DATA not found on youtube.com".to_string())
+}
+
+/// Client session to pin a visitor ID to: the login `session_id` cookie, and only
+/// when the server issued it (a token is stored for it). Arbitrary client-chosen
+/// values would each cost an outbound fetch, so they share the pool instead.
+pub fn session_key(req: &HttpRequest) -> Option<String> {
+    let store = req.app_data::<actix_web::web::Data<crate::routes::auth::TokenStore>>()?;
+    req.cookie("session_id")
+        .map(|c| c.value().to_string())
+        .filter(|s| !s.is_empty() && store.get_token(s).is_some())
+}
+
+async fn pooled(config: &Config) -> Option<String> {
+    let settings = &config.visitor_data;
+    let now = now_secs();
+    {
+        let mut pool = POOL.lock().await;
+        pool.entries
+            .retain(|v| now.saturating_sub(v.created) < settings.max_age_secs);
+        let full = pool.entries.len() >= settings.pool_size.max(1);
+        let backing_off = now.saturating_sub(pool.last_failure) < FETCH_RETRY_SECS;
+        if !pool.entries.is_empty() && (full || backing_off) {
+            let index = pool.next % pool.entries.len();
+            pool.next = pool.next.wrapping_add(1);
+            return Some(pool.entries[index].value.clone());
+        }
+        if backing_off {
+            return None;
+        }
+    }
+
+    match fetch_visitor_data(config).await {
+        Ok(value) => {
+            let mut pool = POOL.lock().await;
+            if pool.entries.len() < settings.pool_size.max(1) {
+                pool.entries.push(VisitorId {
+                    value: value.clone(),
+                    created: now,
+                });
+            }
+            Some(value)
+        }
+        Err(e) => {
+            log::warn!("Visitor data: fetch failed: {}", e);
+            POOL.lock().await.last_failure = now;
+            None
+        }
+    }
+}
+
+/// Visitor ID for an innertube call: the session's pinned one when `pin_sessions`
+/// is on and `session` is given, otherwise one from the rotating pool. None when
+/// disabled or YouTube did not hand one out; the call then goes without.
+pub async fn visitor_data(config: &Config, session: Option<&str>) -> Option<String> {
+    let settings = &config.visitor_data;
+    if !settings.enabled {
+        return None;
+    }
+    match session.filter(|_| settings.pin_sessions) {
+        // pooled() тоже уважает backoff и отдаст имеющиеся ID без нового запроса
+        Some(s) => match pinned(config, s).await {
+            Some(value) => Some(value),
+            None => pooled(config).await,
+        },
+        None => pooled(config).await,
+    }
+}
+
+/// Visitor ID for a signed-in user's innertube call, pinned to the account (keyed
+/// by a hash of its refresh token). Never taken from the pool, so accounts never
+/// share an anonymous ID; None when disabled or the fetch failed.
+pub async fn for_account(config: &Config, refresh_token: &str) -> Option<String> {
+    if !config.visitor_data.enabled {
+        return None;
+    }
+    let digest = Sha256::digest(refresh_token.as_bytes());
+    let key: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    pinned(config, &format!("account:{}", key)).await
+}
+
+/// The ID pinned to `key`, fetched when missing or expired. None while failed
+/// fetches back off (shared with the pool) or when the fetch fails.
+async fn pinned(config: &Config, key: &str) -> Option<String> {
+    let now = now_secs();
+    if let Some(pinned) = PINNED.lock().await.get(key) {
+        if now.saturating_sub(pinned.created) < config.visitor_data.max_age_secs {
+            return Some(pinned.value.clone());
+        }
+    }
+    if now.saturating_sub(POOL.lock().await.last_failure) < FETCH_RETRY_SECS {
+        return None;
+    }
+    match fetch_visitor_data(config).await {
+        Ok(value) => {
+            PINNED.lock().await.put(
+                key.to_string(),
+                VisitorId {
+                    value: value.clone(),
+                    created: now,
+                },
+            );
+            Some(value)
+        }
+        Err(e) => {
+            log::warn!("Visitor data: fetch for pinned session failed: {}", e);
+            POOL.lock().await.last_failure = now;
+            None
+        }
+    }
+}
+
+/// Puts the visitor ID into `context.client.visitorData` of an innertube payload.
+pub fn attach(payload: &mut Value, visitor: Option<&str>) {
+    if let (Some(visitor), Some(client)) = (
+        visitor,
+        payload.get_mut("context").and_then(|c| c.get_mut("client")),
+    ) {
+        client["visitorData"] = Value::String(visitor.to_string());
+    }
+}
+
+/// Adds the matching `X-Goog-Visitor-Id` header.
+pub fn with_header(builder: RequestBuilder, visitor: Option<&str>) -> RequestBuilder {
+    match visitor {
+        Some(v) => builder.header("X-Goog-Visitor-Id", v),
+        None => builder,
+    }
+}